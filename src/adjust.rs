@@ -0,0 +1,192 @@
+//! Records where the target that is optimized differs from the one that was given, so that
+//! every deviation from it can be audited.
+use crate::Target;
+use serde::Serialize;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Adjustment {
+    // e.g. "kcal", "ratio" or "constraint_at_most.seeds"
+    pub field: String,
+    pub original: String,
+    pub effective: String,
+    pub reason: String,
+}
+
+/// e.g. "44.4:33.3:22.2"
+fn ratio(carb: f64, fat: f64, protein: f64) -> String {
+    let round = |x: f64| (10.0 * x).round() / 10.0;
+    format!("{}:{}:{}", round(carb), round(fat), round(protein))
+}
+
+/// Constraints that aren't in the effective list anymore, as (name, amount)
+fn dropped<'a, T, F>(
+    original: &'a Option<Vec<T>>,
+    effective: &Option<Vec<T>>,
+    name: F,
+) -> Vec<&'a T>
+where
+    F: Fn(&T) -> &str,
+{
+    let kept = |candidate: &T| {
+        effective
+            .iter()
+            .flatten()
+            .any(|constraint| name(constraint) == name(candidate))
+    };
+    original
+        .iter()
+        .flatten()
+        .filter(|constraint| !kept(constraint))
+        .collect()
+}
+
+/// Compares the target as given with the one that is optimized
+pub fn adjustments(original: &Target, effective: &Target) -> Vec<Adjustment> {
+    let mut adjustments = Vec::new();
+    let mut adjust = |field: String, original: String, effective: String, reason: &str| {
+        adjustments.push(Adjustment {
+            field,
+            original,
+            effective,
+            reason: reason.to_string(),
+        })
+    };
+    let percent = |target: &Target| {
+        let normalized = target.normalize();
+        ratio(
+            100.0 * normalized.carb,
+            100.0 * normalized.fat,
+            100.0 * normalized.protein,
+        )
+    };
+    let given = ratio(
+        original.carb as f64,
+        original.fat as f64,
+        original.protein as f64,
+    );
+    let scaled = percent(original);
+    if original.carb + original.fat + original.protein != 100 {
+        adjust(
+            "ratio".to_string(),
+            given,
+            scaled.clone(),
+            "scaled to add up to 100",
+        );
+    }
+    let meals = "the rest of the day after the imported meals and fixed foods";
+    if percent(effective) != scaled {
+        adjust("ratio".to_string(), scaled, percent(effective), meals);
+    }
+    if effective.kcal != original.kcal {
+        adjust(
+            "kcal".to_string(),
+            original.kcal.to_string(),
+            effective.kcal.to_string(),
+            meals,
+        );
+    }
+
+    let unused = "the ingredient isn't used in this run";
+    let grams = [
+        (
+            "constraint_exact",
+            &original.constraint_exact,
+            &effective.constraint_exact,
+        ),
+        (
+            "constraint_at_least",
+            &original.constraint_at_least,
+            &effective.constraint_at_least,
+        ),
+        (
+            "constraint_at_most",
+            &original.constraint_at_most,
+            &effective.constraint_at_most,
+        ),
+    ];
+    for (field, original, effective) in grams.iter() {
+        for constraint in dropped(original, effective, |constraint| &constraint.name) {
+            adjust(
+                format!("{}.{}", field, constraint.name),
+                format!("{}g", constraint.g),
+                "none".to_string(),
+                unused,
+            );
+        }
+    }
+    let shares = [
+        (
+            "constraint_max_weight_pct",
+            &original.constraint_max_weight_pct,
+            &effective.constraint_max_weight_pct,
+        ),
+        (
+            "constraint_max_kcal_pct",
+            &original.constraint_max_kcal_pct,
+            &effective.constraint_max_kcal_pct,
+        ),
+    ];
+    for (field, original, effective) in shares.iter() {
+        for constraint in dropped(original, effective, |constraint| &constraint.name) {
+            adjust(
+                format!("{}.{}", field, constraint.name),
+                format!("{}%", constraint.pct),
+                "none".to_string(),
+                unused,
+            );
+        }
+    }
+    adjustments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::meal::Meal;
+
+    #[test]
+    fn test_adjustments() {
+        let original: Target = toml::from_str(
+            "kcal = 2100\ncarb = 40\nfat = 30\nprotein = 20\n\
+             [[constraint_at_most]]\nname = \"seeds\"\ng = 75",
+        )
+        .unwrap();
+        let mut effective = original.clone();
+        effective.retain_constraints(|name| name != "seeds");
+        assert_eq!(
+            adjustments(&original, &effective),
+            vec![
+                Adjustment {
+                    field: "ratio".to_string(),
+                    original: "40:30:20".to_string(),
+                    effective: "44.4:33.3:22.2".to_string(),
+                    reason: "scaled to add up to 100".to_string(),
+                },
+                Adjustment {
+                    field: "constraint_at_most.seeds".to_string(),
+                    original: "75g".to_string(),
+                    effective: "none".to_string(),
+                    reason: "the ingredient isn't used in this run".to_string(),
+                },
+            ]
+        );
+
+        let meal = Meal {
+            name: "shake".to_string(),
+            kcal: 400.0,
+            carb: 10.0,
+            fat: 5.0,
+            protein: 80.0,
+            tags: Vec::new(),
+            notes: None,
+            ingredients: Vec::new(),
+        };
+        let effective = crate::meal::reconcile(&original, &[meal]).unwrap();
+        let adjusted = adjustments(&original, &effective);
+        let fields: Vec<&str> = adjusted
+            .iter()
+            .map(|adjustment| adjustment.field.as_str())
+            .collect();
+        assert_eq!(fields, vec!["ratio", "ratio", "kcal"]);
+    }
+}
@@ -0,0 +1,153 @@
+//! Alternative mixes that are nearly as good as the result but noticeably different, to choose
+//! by taste.
+use crate::{optimize, Ingredients, NormalizedTarget, Proposal, TargetConstraints};
+
+/// Whether some ingredient of the two proposals differs by at least `min_difference`, in grams
+/// per kcal of a piece like the normalized ingredients
+fn differ(a: &Proposal, b: &Proposal, ingredients: &Ingredients, min_difference: f64) -> bool {
+    let pieces = |proposal: &Proposal, name: &str| proposal.0.get(name).copied().unwrap_or(0);
+    ingredients.0.iter().any(|(name, ingredient)| {
+        pieces(a, name).abs_diff(pieces(b, name)) as f64 * ingredient.g >= min_difference
+    })
+}
+
+/// Results of the greedy search with one ingredient of `proposal` at a time limited to
+/// `min_difference` less than the proposal has of it
+fn branches(
+    target: &NormalizedTarget,
+    constraints: &TargetConstraints,
+    ingredients: &Ingredients,
+    steps: usize,
+    proposal: &Proposal,
+    min_difference: f64,
+) -> Vec<Proposal> {
+    let mut branches = Vec::new();
+    for (name, pieces) in proposal.0.iter().filter(|(_, pieces)| **pieces > 0) {
+        let fewer = (min_difference / ingredients.0[name].g).ceil() as u64;
+        let at_most = pieces.saturating_sub(fewer);
+        let at_least = constraints.at_least.0.get(name).copied().unwrap_or(0);
+        if constraints.exact.0.contains_key(name) || at_least > at_most {
+            continue;
+        }
+        let mut branch = constraints.clone();
+        let limit = branch.at_most.0.entry(name.clone()).or_insert(at_most);
+        *limit = (*limit).min(at_most);
+        if let Ok(proposal) = optimize(target, branch, ingredients, steps) {
+            branches.push(proposal);
+        }
+    }
+    branches
+}
+
+/// Up to `count` proposals besides `best`, cheapest first, each of which differs from `best` and
+/// the others by at least `min_difference` of some ingredient, which is in grams divided by the
+/// kcal of a piece.
+///
+/// The candidates are the greedy search's results with an ingredient of a chosen proposal
+/// limited to less than it has, and the cheapest one that differs enough is chosen next.
+pub fn alternatives(
+    target: &NormalizedTarget,
+    constraints: &TargetConstraints,
+    ingredients: &Ingredients,
+    steps: usize,
+    best: &Proposal,
+    count: usize,
+    min_difference: f64,
+) -> Vec<Proposal> {
+    let cost = |proposal: &Proposal| {
+        target.evaluate(proposal, ingredients) + constraints.evaluate(proposal)
+    };
+    let mut chosen = vec![best.clone()];
+    let mut candidates: Vec<(Proposal, f64)> = Vec::new();
+    while chosen.len() <= count {
+        let last = chosen.last().unwrap();
+        candidates.extend(
+            branches(
+                target,
+                constraints,
+                ingredients,
+                steps,
+                last,
+                min_difference,
+            )
+            .into_iter()
+            .map(|proposal| {
+                let cost = cost(&proposal);
+                (proposal, cost)
+            }),
+        );
+        candidates.retain(|(candidate, _)| {
+            chosen
+                .iter()
+                .all(|proposal| differ(candidate, proposal, ingredients, min_difference))
+        });
+        let cheapest =
+            (0..candidates.len()).min_by(|a, b| candidates[*a].1.total_cmp(&candidates[*b].1));
+        match cheapest {
+            Some(i) => chosen.push(candidates.swap_remove(i).0),
+            None => break,
+        }
+    }
+    // a later branch can be cheaper than an alternative chosen before it
+    let mut alternatives = chosen.split_off(1);
+    alternatives.sort_by(|a, b| cost(a).total_cmp(&cost(b)));
+    alternatives
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Ingredient;
+
+    #[test]
+    fn test_alternatives() {
+        let t = NormalizedTarget {
+            carb: 0.4,
+            fat: 0.25,
+            protein: 0.35,
+            ..Default::default()
+        };
+        let ingredient = |carb, fat, protein| {
+            let mut ingredient = Ingredient {
+                g: 100,
+                carb,
+                fat,
+                protein,
+                ..Default::default()
+            };
+            ingredient.kcal = ingredient.macro_kcal();
+            ingredient.normalize()
+        };
+        let mut ingredients = Ingredients(Default::default());
+        for (name, carb, fat, protein) in [
+            ("apple", 20.0, 1.0, 1.0),
+            ("cheese", 1.0, 30.0, 25.0),
+            ("chicken", 0.0, 3.0, 30.0),
+            ("rice", 28.0, 0.5, 3.0),
+        ] {
+            ingredients
+                .0
+                .insert(name.to_string(), ingredient(carb, fat, protein));
+        }
+        let constraints = TargetConstraints::new();
+        let best = optimize(&t, constraints.clone(), &ingredients, 100).unwrap();
+        let alternatives = alternatives(&t, &constraints, &ingredients, 100, &best, 3, 10.0);
+        assert_eq!(alternatives.len(), 3);
+        let mut proposals = vec![&best];
+        for alternative in &alternatives {
+            assert_eq!(alternative.kcal(), 100);
+            assert!(proposals.iter().all(|proposal| differ(
+                alternative,
+                proposal,
+                &ingredients,
+                10.0
+            )));
+            proposals.push(alternative);
+        }
+        let costs: Vec<f64> = alternatives
+            .iter()
+            .map(|alternative| t.evaluate(alternative, &ingredients))
+            .collect();
+        assert!(costs.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+}
@@ -0,0 +1,98 @@
+//! JSON API to the optimizer without files, shared by the `serve` subcommand and the WASM build.
+use crate::error::Error;
+use crate::output::{Report, Rounding};
+use crate::{
+    exclude_tagged, optimize, search_target, to_grams, Ingredient, RawIngredients, Target,
+    TargetConstraints,
+};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Target and ingredients to optimize, the body of `POST /optimize`
+#[derive(Deserialize)]
+pub struct OptimizeRequest {
+    pub target: Target,
+    pub ingredients: Vec<Ingredient>,
+    // number of kcal pieces, the target's resolution or 2000 if omitted
+    #[serde(default)]
+    pub steps: Option<usize>,
+}
+
+pub fn optimize_request(request: OptimizeRequest) -> Result<Report, Error> {
+    let mut raw_ingredients = RawIngredients(BTreeMap::new());
+    for mut ingredient in request.ingredients {
+        ingredient.resolve_per()?;
+        if ingredient.kcal == 0.0 {
+            ingredient.kcal = ingredient.macro_kcal();
+        }
+        raw_ingredients
+            .0
+            .insert(ingredient.name.clone(), ingredient);
+    }
+    let mut ingredients = raw_ingredients.normalize();
+    let mut target = request.target;
+    target.resolve_grams()?;
+    let mut target = crate::meal::reconcile(&target, &[])?;
+    target.resolve_names(&raw_ingredients, false)?;
+    exclude_tagged(&mut target, &mut ingredients, &mut raw_ingredients);
+    let steps = target.optimization_steps(request.steps);
+    let constraints = TargetConstraints::from_target(&target, &raw_ingredients, steps)?;
+    let proposal = optimize(
+        &search_target(&target, &raw_ingredients),
+        constraints,
+        &ingredients,
+        steps,
+    )?;
+    let gram_proposal = to_grams(&proposal, &target, &raw_ingredients, steps);
+    Ok(Report::new(
+        &gram_proposal,
+        &raw_ingredients,
+        Rounding::default(),
+    ))
+}
+
+pub fn error_json(code: &str, message: &str) -> String {
+    serde_json::json!({ "code": code, "message": message }).to_string()
+}
+
+/// Report of the plan for the target of `target_json` with the list of ingredients of
+/// `ingredients_json`, or the error as JSON with the `code` and `message` of
+/// `--error-format json`
+#[cfg(feature = "wasm")]
+pub fn optimize_json(target_json: &str, ingredients_json: &str) -> Result<String, String> {
+    let parse = |e: serde_json::Error| error_json("parse", &e.to_string());
+    let request = OptimizeRequest {
+        target: serde_json::from_str(target_json).map_err(parse)?,
+        ingredients: serde_json::from_str(ingredients_json).map_err(parse)?,
+        steps: None,
+    };
+    match optimize_request(request) {
+        Ok(report) => Ok(serde_json::to_string(&report).unwrap()),
+        Err(e) => Err(serde_json::to_string(&e.report()).unwrap()),
+    }
+}
+
+#[cfg(all(test, feature = "wasm"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_optimize_json() {
+        let ingredients = r#"[
+            {"name": "oats", "g": 100, "carb": 60, "fat": 7, "protein": 13},
+            {"name": "quark", "g": 100, "kcal": 67, "carb": 4, "fat": 0.2, "protein": 12}
+        ]"#;
+        let report = optimize_json(
+            r#"{"kcal": 500, "carb": 60, "fat": 10, "protein": 30, "resolution": 100}"#,
+            ingredients,
+        )
+        .unwrap();
+        let report: serde_json::Value = serde_json::from_str(&report).unwrap();
+        assert!((report["total"]["kcal"].as_f64().unwrap() - 500.0).abs() < 5.0);
+
+        let error = optimize_json("{", ingredients).unwrap_err();
+        assert!(error.contains(r#""code":"parse""#), "{}", error);
+        let error = optimize_json(r#"{"kcal": 500}"#, ingredients).unwrap_err();
+        assert!(error.contains(r#""code":"invalid_target""#), "{}", error);
+    }
+}
@@ -0,0 +1,65 @@
+//! Spreads prepared foods that are cooked in batches over the days of a plan.
+use crate::{Proposal, RawIngredients};
+use serde::Serialize;
+
+/// How much of a batch-cooked ingredient the plan uses on all of its days
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Batch {
+    pub name: String,
+    pub batches: f64,
+    pub g_per_day: u64,
+}
+
+/// Whole or half batches of `g_per_day` over `days`, rounded to the nearest half
+pub fn batches(g_per_day: u64, batch_g: u64, days: u64) -> f64 {
+    (2.0 * (g_per_day * days) as f64 / batch_g as f64).round() / 2.0
+}
+
+/// The batches of the ingredients of the plan that declare a batch size
+pub fn plan(gram_proposal: &Proposal, raw_ingredients: &RawIngredients, days: u64) -> Vec<Batch> {
+    gram_proposal
+        .0
+        .iter()
+        .filter(|(_, g)| **g > 0)
+        .filter_map(|(name, g)| {
+            let batch_g = raw_ingredients.0[name].batch_g?;
+            Some(Batch {
+                name: name.clone(),
+                batches: batches(*g, batch_g, days),
+                g_per_day: *g,
+            })
+        })
+        .collect()
+}
+
+/// e.g. "chili: cook 1.5 batches, 429g a day" for the days of the plan
+pub fn print_batches(batches: &[Batch], days: u64) {
+    if batches.is_empty() {
+        return;
+    }
+    println!("Batch cooking for {} days", days);
+    for batch in batches {
+        let noun = if batch.batches == 1.0 {
+            "batch"
+        } else {
+            "batches"
+        };
+        println!(
+            "\t{}: cook {} {}, {}g a day",
+            batch.name, batch.batches, noun, batch.g_per_day
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batches() {
+        // 1.5 batches of 2kg are 3kg, 429g a day for a week
+        assert_eq!(batches(429, 2000, 7), 1.5);
+        assert_eq!(batches(500, 2000, 4), 1.0);
+        assert_eq!(batches(200, 2000, 5), 0.5);
+    }
+}
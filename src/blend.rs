@@ -0,0 +1,94 @@
+//! Bulk blends of dry ingredients, e.g. a custom protein/oat mix that is portioned by scoop.
+use crate::{Macros, Proposal, RawIngredients};
+
+/// Turns a proposal in kcal pieces into grams of a batch weighing `batch_g`
+pub fn batch_grams(
+    proposal: &Proposal,
+    raw_ingredients: &RawIngredients,
+    batch_g: u64,
+) -> Proposal {
+    let grams: Vec<(&String, f64)> = proposal
+        .0
+        .iter()
+        .map(|(name, pieces)| {
+            let ingredient = &raw_ingredients.0[name];
+            (name, *pieces as f64 * ingredient.g as f64 / ingredient.kcal)
+        })
+        .collect();
+    let total: f64 = grams.iter().map(|(_, g)| g).sum();
+    let mut batch = Proposal(Default::default());
+    for (name, g) in grams {
+        batch.0.insert(
+            name.to_string(),
+            (g / total * batch_g as f64).round() as u64,
+        );
+    }
+    batch
+}
+
+fn describe(macros: &Macros) -> String {
+    format!(
+        "{:.1}g carb, {:.1}g fat, {:.1}g protein in {} kcal",
+        macros.carb,
+        macros.fat,
+        macros.protein,
+        macros.kcal.round()
+    )
+}
+
+pub fn print_blend(
+    batch: &Proposal,
+    raw_ingredients: &RawIngredients,
+    profile: &Macros,
+    scoop_g: Option<u64>,
+) {
+    let batch_g = batch.0.values().sum::<u64>() as f64;
+    let macros = Macros::from_grams(batch, raw_ingredients);
+    println!("---- BLEND ----");
+    println!("Mix the following together (in grams) {:?}", batch);
+    println!("Results in {} for {}g.", describe(&macros), batch_g);
+    println!(
+        "Per 100g {} (wanted {:.1}g carb, {:.1}g fat, {:.1}g protein).",
+        describe(&macros.per_g(100.0, batch_g)),
+        profile.carb,
+        profile.fat,
+        profile.protein
+    );
+    if let Some(scoop_g) = scoop_g {
+        println!(
+            "Per {}g scoop {}.",
+            scoop_g,
+            describe(&macros.per_g(scoop_g as f64, batch_g))
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Ingredient;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_batch_grams() {
+        let mut raw_ingredients = RawIngredients(BTreeMap::new());
+        for (name, kcal) in &[("oats", 400.0), ("whey", 200.0)] {
+            raw_ingredients.0.insert(
+                name.to_string(),
+                Ingredient {
+                    name: name.to_string(),
+                    g: 100,
+                    kcal: *kcal,
+                    ..Default::default()
+                },
+            );
+        }
+        // equal kcal means twice as much whey by weight
+        let mut proposal = Proposal(BTreeMap::new());
+        proposal.0.insert("oats".to_string(), 10);
+        proposal.0.insert("whey".to_string(), 10);
+        let batch = batch_grams(&proposal, &raw_ingredients, 1500);
+        assert_eq!(batch.0["oats"], 500);
+        assert_eq!(batch.0["whey"], 1000);
+    }
+}
@@ -0,0 +1,194 @@
+//! Computes a target from body stats, with the Mifflin-St Jeor equation for the basal
+//! metabolic rate.
+use crate::error::Error;
+use crate::{Target, KCAL_PER_G_CARB, KCAL_PER_G_FAT, KCAL_PER_G_PROTEIN};
+use clap::ValueEnum;
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sex {
+    Male,
+    Female,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Activity {
+    /// Desk job and little exercise
+    Sedentary,
+    /// Exercise 1-3 days a week
+    Light,
+    /// Exercise 3-5 days a week
+    Moderate,
+    /// Hard exercise 6-7 days a week
+    Active,
+    /// Hard exercise and a physical job
+    VeryActive,
+}
+
+impl Activity {
+    /// Factor of the basal metabolic rate that gives the total daily energy expenditure
+    fn factor(self) -> f64 {
+        match self {
+            Activity::Sedentary => 1.2,
+            Activity::Light => 1.375,
+            Activity::Moderate => 1.55,
+            Activity::Active => 1.725,
+            Activity::VeryActive => 1.9,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Goal {
+    /// Lose fat with a 20% deficit
+    Cut,
+    Maintain,
+    /// Gain muscle with a 10% surplus
+    Bulk,
+}
+
+impl Goal {
+    fn kcal_factor(self) -> f64 {
+        match self {
+            Goal::Cut => 0.8,
+            Goal::Maintain => 1.0,
+            Goal::Bulk => 1.1,
+        }
+    }
+
+    /// Recommended protein in g per kg body weight, higher in a deficit to keep muscle
+    pub fn protein_g_per_kg(self) -> f64 {
+        match self {
+            Goal::Cut => 2.2,
+            Goal::Maintain => 1.8,
+            Goal::Bulk => 2.0,
+        }
+    }
+}
+
+pub struct BodyStats {
+    pub weight_kg: f64,
+    pub height_cm: f64,
+    pub age: u64,
+    pub sex: Sex,
+    pub activity: Activity,
+}
+
+impl BodyStats {
+    /// Basal metabolic rate in kcal per day
+    pub fn bmr(&self) -> f64 {
+        let base = 10.0 * self.weight_kg + 6.25 * self.height_cm - 5.0 * self.age as f64;
+        match self.sex {
+            Sex::Male => base + 5.0,
+            Sex::Female => base - 161.0,
+        }
+    }
+
+    /// Total daily energy expenditure in kcal
+    pub fn tdee(&self) -> f64 {
+        self.bmr() * self.activity.factor()
+    }
+}
+
+/// Macros of the target in g per day
+#[derive(Debug, PartialEq)]
+pub struct DailyMacros {
+    pub kcal: u64,
+    pub carb: f64,
+    pub fat: f64,
+    pub protein: f64,
+}
+
+impl DailyMacros {
+    /// Protein by body weight, fat by share of the kcal and carbs for the rest, an error if
+    /// protein and fat alone have more than the kcal
+    pub fn new(
+        stats: &BodyStats,
+        goal: Goal,
+        protein_g_per_kg: f64,
+        fat_pct: f64,
+    ) -> Result<Self, Error> {
+        let kcal = (stats.tdee() * goal.kcal_factor()).round();
+        let protein = stats.weight_kg * protein_g_per_kg;
+        let fat = kcal * fat_pct / 100.0 / KCAL_PER_G_FAT;
+        let carb_kcal = kcal - protein * KCAL_PER_G_PROTEIN - fat * KCAL_PER_G_FAT;
+        if carb_kcal < 0.0 {
+            return Err(Error::InvalidTarget(format!(
+                "of {} kcal is less than the {} kcal of {}g protein and {}g fat",
+                kcal,
+                (kcal - carb_kcal).round(),
+                protein.round(),
+                fat.round()
+            )));
+        }
+        Ok(DailyMacros {
+            kcal: kcal as u64,
+            carb: carb_kcal / KCAL_PER_G_CARB,
+            fat,
+            protein,
+        })
+    }
+
+    /// Target with the macros as ratio of their grams, which adds up to 100
+    pub fn target(&self) -> Target {
+        let sum = self.carb + self.fat + self.protein;
+        let fat = (100.0 * self.fat / sum).round() as u64;
+        let protein = (100.0 * self.protein / sum).round() as u64;
+        Target {
+            kcal: self.kcal,
+            carb: 100 - fat - protein,
+            fat,
+            protein,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_target() {
+        let stats = BodyStats {
+            weight_kg: 80.0,
+            height_cm: 180.0,
+            age: 30,
+            sex: Sex::Male,
+            activity: Activity::Moderate,
+        };
+        assert_eq!(stats.bmr(), 1780.0);
+        assert!((stats.tdee() - 2759.0).abs() < 1e-9);
+
+        let macros =
+            DailyMacros::new(&stats, Goal::Cut, Goal::Cut.protein_g_per_kg(), 25.0).unwrap();
+        assert_eq!(macros.kcal, 2207);
+        assert!((macros.protein - 176.0).abs() < 1e-9);
+        // 25% of the kcal
+        assert!((macros.fat * KCAL_PER_G_FAT - 551.75).abs() < 1e-9);
+        let target = macros.target();
+        assert_eq!(target.kcal, 2207);
+        assert_eq!((target.carb, target.fat, target.protein), (50, 13, 37));
+
+        let female = BodyStats {
+            sex: Sex::Female,
+            ..stats
+        };
+        assert_eq!(female.bmr(), 1614.0);
+    }
+
+    #[test]
+    fn test_macros_exceed_kcal() {
+        let stats = BodyStats {
+            weight_kg: 60.0,
+            height_cm: 160.0,
+            age: 40,
+            sex: Sex::Female,
+            activity: Activity::Sedentary,
+        };
+        // 150g protein and 50% fat are more than the 1189 kcal of the cut
+        assert!(matches!(
+            DailyMacros::new(&stats, Goal::Cut, 2.5, 50.0),
+            Err(Error::InvalidTarget(_))
+        ));
+    }
+}
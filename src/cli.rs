@@ -0,0 +1,692 @@
+use crate::calculator::{Activity, Goal, Sex};
+use crate::stock::Date;
+use crate::targets::Preset;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::convert::TryFrom;
+use std::path::PathBuf;
+
+/// Mixes ingredients to hit a kcal target with a desired macro ratio.
+#[derive(Parser, Debug)]
+#[command(name = "natural-bodybuilder", version)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+    /// Format of error messages on stderr
+    #[arg(long, global = true, value_enum, default_value_t = ErrorFormat::Text)]
+    pub error_format: ErrorFormat,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Search for a mix of the ingredients that matches the target
+    Optimize(Box<OptimizeArgs>),
+    /// Check that the target and ingredient files can be read
+    Validate(InspectArgs),
+    /// Print the normalized target and ingredients
+    Show(InspectArgs),
+    /// Add up the ingredients of several meals and targets, rounded up to whole packages
+    ShoppingList(ShoppingListArgs),
+    /// Divide cooked meals across meal prep containers
+    Portion(PortionArgs),
+    /// Print the kcal and macros of given amounts of ingredients, e.g. chicken=200g rice=150g
+    Mix(MixArgs),
+    /// Evaluate a saved or hand-made proposal against a target, e.g. after the target changed
+    #[command(visible_alias = "evaluate")]
+    Reeval(ReevalArgs),
+    /// Print how the grams and the macros of two saved proposals differ
+    Diff(DiffArgs),
+    /// Write a saved proposal as CSV to import into a food tracker
+    Export(ExportArgs),
+    /// Recommend stock levels per ingredient from the meals of the last weeks
+    Stock(StockArgs),
+    /// Take the grams of an accepted plan out of the inventory
+    Consume(ConsumeArgs),
+    /// Append the plan eaten on a day to a history file
+    Log(LogArgs),
+    /// Weekly averages, protein per kg and adherence to the targets of a history file
+    Stats(StatsArgs),
+    /// Compute a bulk blend of dry ingredients with a given macro profile per 100g
+    Blend(BlendArgs),
+    /// Compute a target from body stats and a goal
+    CalcTarget(CalcTargetArgs),
+    /// Create an ingredient file from a food database
+    #[command(subcommand)]
+    Import(ImportSource),
+    /// Create an ingredient file in the pantry by answering questions about its label
+    AddIngredient(AddIngredientArgs),
+    /// Compare and patch pantries of ingredient files
+    #[command(subcommand)]
+    Ingredients(PantryCommand),
+    /// Serve the optimizer as a JSON API at POST /optimize
+    #[cfg(feature = "server")]
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        addr: String,
+    },
+    /// Worked examples to explore the commands with
+    #[command(subcommand)]
+    Examples(ExamplesCommand),
+}
+
+#[derive(Args, Debug)]
+pub struct CalcTargetArgs {
+    /// Body weight in kg
+    #[arg(long)]
+    pub weight_kg: f64,
+    /// Height in cm
+    #[arg(long)]
+    pub height_cm: f64,
+    /// Age in years
+    #[arg(long)]
+    pub age: u64,
+    #[arg(long, value_enum)]
+    pub sex: Sex,
+    #[arg(long, value_enum)]
+    pub activity: Activity,
+    #[arg(long, value_enum, default_value_t = Goal::Maintain)]
+    pub goal: Goal,
+    /// Protein in g per kg body weight [default: 2.2 to cut, 1.8 to maintain, 2.0 to bulk]
+    #[arg(long, value_name = "G")]
+    pub protein_per_kg: Option<f64>,
+    /// Share of the kcal from fat in percent
+    #[arg(long, value_name = "PERCENT", default_value_t = 25.0, value_parser = parse_percent)]
+    pub fat_pct: f64,
+    /// Write the target to this file instead of printing it
+    #[arg(short, long, value_name = "FILE")]
+    pub out: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ExamplesCommand {
+    /// Write a pantry of about 30 foods, recipes and targets into a directory and print
+    /// commands to try on them
+    Generate {
+        /// Directory to create, must be empty if it exists
+        dir: PathBuf,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum PantryCommand {
+    /// Print the field-level changes that turn the pantry into another one as a patch
+    Diff(PantryDiffArgs),
+    /// Apply a patch to the pantry, or nothing if any change conflicts with it
+    Apply(PantryApplyArgs),
+    /// Print the ingredients and recipes that are used and the file each one is read from
+    Effective(IngredientArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct AddIngredientArgs {
+    /// Directory of the pantry
+    #[arg(short = 'd', long)]
+    pub ingredients_dir: PathBuf,
+}
+
+#[derive(Args, Debug)]
+pub struct PantryDiffArgs {
+    /// Directory of the pantry
+    #[arg(short = 'd', long)]
+    pub ingredients_dir: PathBuf,
+    /// Directory of the other pantry
+    pub other_dir: PathBuf,
+    /// Write the patch to this file instead of printing it
+    #[arg(short, long, value_name = "FILE")]
+    pub out: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct PantryApplyArgs {
+    /// Directory of the pantry
+    #[arg(short = 'd', long)]
+    pub ingredients_dir: PathBuf,
+    /// Patch toml file created by `ingredients diff`
+    pub patch: PathBuf,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ImportSource {
+    /// USDA FoodData Central, through its API or a CSV download
+    Usda(UsdaArgs),
+    /// Open Food Facts, by barcode
+    #[cfg(feature = "http")]
+    Off(OffArgs),
+    /// German Bundeslebensmittelschlüssel (BLS), from its CSV export
+    Bls(TableArgs),
+    /// Swiss Food Composition Database, from its CSV export
+    SwissFcd(TableArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct TableArgs {
+    /// ID or name of the food
+    pub query: String,
+    /// CSV export of the table
+    #[arg(long, value_name = "FILE")]
+    pub csv: PathBuf,
+    #[command(flatten)]
+    pub ingredient: ImportedIngredientArgs,
+}
+
+#[cfg(feature = "http")]
+#[derive(Args, Debug)]
+pub struct OffArgs {
+    /// Barcode of the product
+    pub barcode: String,
+    #[command(flatten)]
+    pub ingredient: ImportedIngredientArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct UsdaArgs {
+    /// FDC ID or description of the food
+    pub query: String,
+    /// Directory of the FoodData Central CSV download to search instead of the API
+    #[arg(long, value_name = "DIR")]
+    #[cfg_attr(not(feature = "http"), arg(required = true))]
+    pub csv_dir: Option<PathBuf>,
+    /// FoodData Central API key
+    #[cfg(feature = "http")]
+    #[arg(long, env = "FDC_API_KEY", default_value = "DEMO_KEY")]
+    pub api_key: String,
+    #[command(flatten)]
+    pub ingredient: ImportedIngredientArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct ImportedIngredientArgs {
+    /// Name of the ingredient [default: the database's description]
+    #[arg(long)]
+    pub name: Option<String>,
+    /// Write the ingredient to this file instead of printing it
+    #[arg(short, long, value_name = "FILE")]
+    pub out: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct OptimizeArgs {
+    /// Target toml file
+    #[arg(
+        short,
+        long,
+        required_unless_present = "preset",
+        conflicts_with = "preset"
+    )]
+    pub target: Option<PathBuf>,
+    /// Built-in target to use instead of a target file
+    #[arg(long, value_enum)]
+    pub preset: Option<Preset>,
+    /// Replaces the target's kcal
+    #[arg(long)]
+    pub kcal: Option<u64>,
+    /// Replaces the target's carb ratio
+    #[arg(long)]
+    pub carb: Option<u64>,
+    /// Replaces the target's fat ratio
+    #[arg(long)]
+    pub fat: Option<u64>,
+    /// Replaces the target's protein ratio
+    #[arg(long)]
+    pub protein: Option<u64>,
+    #[command(flatten)]
+    pub ingredients: IngredientArgs,
+    /// Number of kcal pieces the target is split into during the search
+    /// [default: the target's resolution or 2000]
+    #[arg(short, long)]
+    pub steps: Option<usize>,
+    /// Format of the result
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+    /// Print the result as a document with the ingredients, macros and constraints instead, e.g.
+    /// for a coach or the kitchen
+    #[arg(long, value_enum, value_name = "FORMAT", conflicts_with = "output")]
+    pub report: Option<DocumentFormat>,
+    /// Tolerance of nutrition labels in percent for ingredients that don't declare one; shows
+    /// an interval of the result's macros
+    #[arg(long, value_name = "PERCENT")]
+    pub tolerance: Option<f64>,
+    /// Show every step of the search: which ingredient was added, how the cost changed and
+    /// which constraints blocked other ingredients. Written to FILE if given
+    #[arg(long, value_name = "FILE", num_args = 0..=1)]
+    pub explain: Option<Option<PathBuf>>,
+    /// Only print the result, without the search summary and progress bar
+    #[arg(short, long, conflicts_with = "verbose")]
+    pub quiet: bool,
+    /// Also print the normalized ingredients, and with -vv every step of the search
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+    /// Toggle ingredients and change the kcal in a terminal UI, the mix is optimized again
+    /// after every change
+    #[cfg(feature = "tui")]
+    #[arg(long)]
+    pub interactive: bool,
+    /// Search algorithm, e.g. exact with `--steps 100` for a pantry of a few ingredients
+    #[arg(long, value_enum, default_value_t = Algorithm::Greedy, conflicts_with = "explain")]
+    pub algorithm: Algorithm,
+    /// Number of times the search is run; all but the first try the ingredients in random
+    /// order and the best result is kept
+    #[arg(long, default_value_t = 1, value_name = "N")]
+    pub restarts: usize,
+    /// Seed of the random order in restarts, the same seed gives the same result
+    #[arg(long, default_value_t = 0)]
+    pub seed: u64,
+    /// Meal file that is eaten in addition to the mix; the target is reduced by its kcal and
+    /// macros. Can be given multiple times
+    #[arg(long, value_name = "FILE")]
+    pub import_meal: Vec<PathBuf>,
+    /// Round the grams of ingredients in the result to multiples of this, e.g. 5 for a
+    /// kitchen scale with 5g steps. Ingredients with a portion size stay in whole portions
+    #[arg(long, value_name = "G", default_value_t = 1)]
+    pub round_g: u64,
+    /// Decimals of the macros in the result
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    pub macro_decimals: usize,
+    /// Decimals of percentages in the result, e.g. 0 for whole percent
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    pub pct_decimals: usize,
+    /// Also list the amounts of the result in these units, e.g. "3 pieces" of eggs
+    #[arg(long, value_enum, default_value_t = Units::Metric)]
+    pub units: Units,
+    /// Days the plan is eaten on, e.g. 7 for a week. Ingredients with a batch_g are cooked in
+    /// whole or half batches for all of them
+    #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+    pub days: u64,
+    /// Write the resulting mix as a shareable meal file
+    #[arg(long, value_name = "FILE")]
+    pub export_meal: Option<PathBuf>,
+    /// Name of the exported meal
+    #[arg(long, requires = "export_meal")]
+    pub meal_name: Option<String>,
+    /// Tag of the exported meal. Can be given multiple times
+    #[arg(long, requires = "export_meal")]
+    pub meal_tag: Vec<String>,
+    /// Notes of the exported meal
+    #[arg(long, requires = "export_meal")]
+    pub meal_notes: Option<String>,
+    /// Save the grams of the result to a proposal file that `reeval` can load again
+    #[arg(long, value_name = "FILE")]
+    pub save: Option<PathBuf>,
+    /// Write the best plans with different numbers of ingredients that the genetic search found
+    /// to a file, each cheaper than the ones with fewer ingredients (json if it ends in .json)
+    #[arg(long, value_name = "FILE")]
+    pub population: Option<PathBuf>,
+    /// Inventory file of the grams of each ingredient in stock, e.g. written by `stock --out`;
+    /// the plan's days use at most that much of the listed ingredients
+    #[arg(long, value_name = "FILE")]
+    pub inventory: Option<PathBuf>,
+    /// How much using ingredients of the inventory that expire soon is preferred, compared to
+    /// missing the target
+    #[arg(long, default_value_t = 0.001, requires = "inventory")]
+    pub expiry_weight: f64,
+    /// Also print this many alternatives to the result that are nearly as good, to choose by
+    /// taste, each differing from the others by at least `--min-difference` of some ingredient
+    #[arg(long, default_value_t = 0, value_name = "K")]
+    pub alternatives: usize,
+    /// Grams of some ingredient by which the alternatives differ from the result and each other
+    #[arg(
+        long,
+        default_value_t = 50.0,
+        value_name = "G",
+        requires = "alternatives"
+    )]
+    pub min_difference: f64,
+    /// Start the search from a proposal file saved with `--save`, e.g. yesterday's plan, so
+    /// that the result only changes as much as the target needs
+    #[arg(long, value_name = "FILE")]
+    pub start_from: Option<PathBuf>,
+    /// Prefer results whose ingredients have similar shares as in a proposal file saved with
+    /// `--save`, e.g. what is already prepped, while still hitting the target
+    #[arg(long, value_name = "FILE")]
+    pub reference: Option<PathBuf>,
+    /// How much a deviation from the reference costs compared to missing the target
+    #[arg(long, default_value_t = 0.01, requires = "reference")]
+    pub reference_weight: f64,
+}
+
+#[derive(Args, Debug)]
+pub struct ReevalArgs {
+    /// Proposal file saved with `optimize --save`, or a table of ingredient names and grams
+    pub proposal: PathBuf,
+    /// Target the proposal is evaluated against
+    #[arg(short, long)]
+    pub target: PathBuf,
+    #[command(flatten)]
+    pub ingredients: IngredientArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct DiffArgs {
+    /// Proposal file saved with `optimize --save` before the change
+    pub old: PathBuf,
+    /// Proposal file saved after the change
+    pub new: PathBuf,
+    #[command(flatten)]
+    pub ingredients: IngredientArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct ExportArgs {
+    /// Proposal file saved with `optimize --save`
+    pub proposal: PathBuf,
+    /// Tracker whose import format is written
+    #[arg(long, value_enum)]
+    pub tracker: Tracker,
+    /// Day the foods are logged for, e.g. 2024-05-01
+    #[arg(long, value_name = "YYYY-MM-DD")]
+    pub date: String,
+    /// Meal or diary group the foods are logged to [default: Snacks for MyFitnessPal,
+    /// Uncategorized for Cronometer]
+    #[arg(long)]
+    pub meal: Option<String>,
+    /// Write the CSV to this file instead of printing it
+    #[arg(short, long, value_name = "FILE")]
+    pub out: Option<PathBuf>,
+    #[command(flatten)]
+    pub ingredients: IngredientArgs,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tracker {
+    #[value(name = "myfitnesspal")]
+    MyFitnessPal,
+    Cronometer,
+}
+
+#[derive(Args, Debug)]
+pub struct InspectArgs {
+    /// Target toml file
+    #[arg(short, long)]
+    pub target: Option<PathBuf>,
+    #[command(flatten)]
+    pub ingredients: IngredientArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct ShoppingListArgs {
+    /// Meal file whose ingredients are bought. Can be given multiple times
+    #[arg(long, value_name = "FILE")]
+    pub meal: Vec<PathBuf>,
+    /// Target that is optimized with the ingredients and whose result is bought. Can be given
+    /// multiple times
+    #[arg(short, long, value_name = "FILE")]
+    pub target: Vec<PathBuf>,
+    #[command(flatten)]
+    pub ingredients: IngredientArgs,
+    /// Number of kcal pieces the targets are split into during the search
+    /// [default: the target's resolution or 2000]
+    #[arg(short, long)]
+    pub steps: Option<usize>,
+}
+
+#[derive(Args, Debug)]
+pub struct PortionArgs {
+    /// Meal file of a cooked batch. Can be given multiple times
+    #[arg(long = "meal", value_name = "FILE", required = true)]
+    pub meals: Vec<PathBuf>,
+    /// Number of equally sized containers
+    #[arg(
+        long,
+        required_unless_present = "shares",
+        conflicts_with = "shares",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub containers: Option<u64>,
+    /// Relative size of each container, e.g. 2,1,1 if the first day needs twice as much
+    #[arg(long, value_name = "SHARE,...", value_delimiter = ',')]
+    pub shares: Vec<f64>,
+    /// Weight of each cooked meal in the order of the meals [default: the weight of the raw
+    /// ingredients]
+    #[arg(long, value_name = "G,...", value_delimiter = ',')]
+    pub cooked_g: Vec<u64>,
+}
+
+#[derive(Args, Debug)]
+pub struct MixArgs {
+    /// Amount of an ingredient or recipe in g
+    #[arg(value_name = "NAME=G", value_parser = parse_amount, required = true)]
+    pub amounts: Vec<(String, u64)>,
+    /// Directory from which every .toml and .json file is loaded as an ingredient
+    #[arg(short = 'd', long)]
+    pub ingredients_dir: Vec<PathBuf>,
+    /// Target whose ratio and kcal the mix is compared to
+    #[arg(short, long)]
+    pub target: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct StockArgs {
+    /// Meal file eaten during the weeks, e.g. exported with --export-meal. Can be given
+    /// multiple times
+    #[arg(long = "meal", value_name = "FILE", required = true)]
+    pub meals: Vec<PathBuf>,
+    /// Number of weeks the meals cover
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    pub weeks: u64,
+    /// Extra stock on top of the average weekly consumption in percent
+    #[arg(long, default_value_t = 25.0)]
+    pub margin_pct: f64,
+    /// Write the stock levels as an inventory template to this file
+    #[arg(short, long, value_name = "FILE")]
+    pub out: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct ConsumeArgs {
+    /// Proposal file of the accepted plan, saved with `optimize --save`
+    pub proposal: PathBuf,
+    /// Inventory file the grams are taken from, e.g. written by `stock --out`; it's updated in
+    /// place
+    #[arg(long, value_name = "FILE")]
+    pub inventory: PathBuf,
+    /// Days the plan is eaten on, each takes its grams from the inventory
+    #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+    pub days: u64,
+}
+
+#[derive(Args, Debug)]
+pub struct LogArgs {
+    /// Proposal file of the plan eaten on the day, saved with `optimize --save`
+    pub proposal: PathBuf,
+    /// History file the day is appended to, one JSON object per line; logging a day again
+    /// replaces it in `stats`
+    #[arg(long, value_name = "FILE")]
+    pub history: PathBuf,
+    /// Day the plan was eaten on [default: today]
+    #[arg(long, value_name = "YYYY-MM-DD", value_parser = |text: &str| Date::try_from(text.to_string()))]
+    pub date: Option<Date>,
+    /// Target of the day, `stats` checks the day against it
+    #[arg(short, long)]
+    pub target: Option<PathBuf>,
+    /// Body weight in kg on the day, for the protein per kg in `stats`
+    #[arg(long, value_parser = parse_positive)]
+    pub weight_kg: Option<f64>,
+    #[command(flatten)]
+    pub ingredients: IngredientArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct StatsArgs {
+    /// History file written by `log`
+    pub history: PathBuf,
+    /// Body weight in kg of the days that were logged without one
+    #[arg(long, value_parser = parse_positive)]
+    pub weight_kg: Option<f64>,
+}
+
+#[derive(Args, Debug)]
+pub struct BlendArgs {
+    /// Wanted carb per 100g of the blend
+    #[arg(long, value_name = "G")]
+    pub carb: f64,
+    /// Wanted fat per 100g of the blend
+    #[arg(long, value_name = "G")]
+    pub fat: f64,
+    /// Wanted protein per 100g of the blend
+    #[arg(long, value_name = "G")]
+    pub protein: f64,
+    /// Weight of the whole batch
+    #[arg(long, value_name = "G", default_value_t = 1000)]
+    pub batch_g: u64,
+    /// Show the macros of a scoop of this size
+    #[arg(long, value_name = "G")]
+    pub scoop_g: Option<u64>,
+    /// Largest share of the blend's weight of an ingredient, e.g. cocoa=8. Can be given
+    /// multiple times
+    #[arg(long, value_name = "NAME=PCT", value_parser = parse_name_value)]
+    pub max_weight_pct: Vec<(String, f64)>,
+    #[command(flatten)]
+    pub ingredients: IngredientArgs,
+    /// Number of kcal pieces the blend is split into during the search
+    #[arg(short, long, default_value_t = 2000)]
+    pub steps: usize,
+}
+
+#[derive(Args, Debug, Default)]
+pub struct IngredientArgs {
+    /// Directory from which every .toml and .json file is loaded as an ingredient, including
+    /// subdirectories. Can be given multiple times, e.g. a base pantry and seasonal additions;
+    /// ingredients in later directories replace those of the same name in earlier ones
+    #[arg(short = 'd', long)]
+    pub ingredients_dir: Vec<PathBuf>,
+    /// Ingredient toml or json files
+    pub files: Vec<PathBuf>,
+    /// Don't use this ingredient or recipe, e.g. because it's out of stock. Can be given
+    /// multiple times
+    #[arg(long, value_name = "NAME")]
+    pub exclude: Vec<String>,
+    /// Only use these ingredients and recipes
+    #[arg(long, value_name = "NAME,...", value_delimiter = ',')]
+    pub only: Vec<String>,
+    /// File with calorie-free flavorings (spices, vinegar, ...) that are used to taste instead
+    /// of being optimized, and are added to shopping lists
+    #[arg(long, value_name = "FILE")]
+    pub flavorings: Option<PathBuf>,
+    /// What to do if two files of the same directory, or two of the files given, have the same
+    /// name
+    #[arg(long, value_enum, default_value_t = OnDuplicate::Error)]
+    pub on_duplicate: OnDuplicate,
+    /// Constraints may name an ingredient in a different case, e.g. Oats for oats, if that
+    /// matches only one ingredient
+    #[arg(long)]
+    pub ignore_case: bool,
+}
+
+impl IngredientArgs {
+    /// Whether the ingredient is used in this run
+    pub fn is_selected(&self, name: &str) -> bool {
+        !self.exclude.iter().any(|excluded| excluded == name)
+            && (self.only.is_empty() || self.only.iter().any(|only| only == name))
+    }
+}
+
+/// How to handle ingredients or recipes that have the same name as another one of their directory
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OnDuplicate {
+    /// Fail and name both files
+    #[default]
+    Error,
+    /// Use the file that comes first in path order
+    First,
+    /// Use the file that comes last in path order
+    Last,
+    /// Use both, the later one with a number after its name
+    Rename,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human readable report
+    Text,
+    /// Report as a json object
+    Json,
+    /// One row per ingredient, a total and a per 100g row
+    Csv,
+}
+
+/// How the mix is searched for
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Algorithm {
+    /// Add the ingredient that improves the mix most, one step at a time
+    #[default]
+    Greedy,
+    /// Branch and bound over all proposals, which finds the best one at the given steps; only
+    /// for small pantries and few steps
+    Exact,
+    /// Cross and mutate a population of proposals, seeded with `--seed`; can write several good
+    /// plans with different numbers of ingredients with `--population`
+    Genetic,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DocumentFormat {
+    /// Markdown
+    Md,
+    /// A standalone HTML page
+    Html,
+}
+
+/// Units the amounts of the result are measured in, in addition to the grams of the table
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Units {
+    /// Grams only
+    Metric,
+    /// Ounces, fluid ounces for ingredients measured in ml, and pieces
+    Imperial,
+    /// The unit each ingredient declares, e.g. ml or pieces, otherwise grams
+    Natural,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorFormat {
+    /// Human readable message
+    Text,
+    /// Json object with a stable code, the message and the offending file and field
+    Json,
+}
+
+/// Parses arguments of the form name=grams, with an optional g unit
+fn parse_amount(arg: &str) -> Result<(String, u64), String> {
+    let (name, g) = arg
+        .rsplit_once('=')
+        .ok_or_else(|| format!("expected NAME=G but got \"{}\"", arg))?;
+    let g = g
+        .strip_suffix('g')
+        .unwrap_or(g)
+        .parse()
+        .map_err(|_| format!("\"{}\" is not an amount in g", g))?;
+    Ok((name.to_string(), g))
+}
+
+/// Parses a percentage from 0 to 100
+fn parse_percent(arg: &str) -> Result<f64, String> {
+    let pct: f64 = arg
+        .parse()
+        .map_err(|_| format!("\"{}\" is not a number", arg))?;
+    if !(0.0..=100.0).contains(&pct) {
+        return Err(format!("{} is not between 0 and 100", arg));
+    }
+    Ok(pct)
+}
+
+/// Parses a number greater than 0
+fn parse_positive(arg: &str) -> Result<f64, String> {
+    let value: f64 = arg
+        .parse()
+        .map_err(|_| format!("\"{}\" is not a number", arg))?;
+    if !(value > 0.0 && value.is_finite()) {
+        return Err(format!("{} is not greater than 0", arg));
+    }
+    Ok(value)
+}
+
+/// Parses arguments of the form name=value
+fn parse_name_value(arg: &str) -> Result<(String, f64), String> {
+    let (name, value) = arg
+        .rsplit_once('=')
+        .ok_or_else(|| format!("expected NAME=VALUE but got \"{}\"", arg))?;
+    let value = value
+        .parse()
+        .map_err(|_| format!("\"{}\" is not a number", value))?;
+    Ok((name.to_string(), value))
+}
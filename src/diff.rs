@@ -0,0 +1,98 @@
+//! Compares two saved proposals, e.g. the results before and after the target was tweaked.
+use crate::{Macros, Proposal, RawIngredients};
+
+/// Amount of an ingredient that differs between the proposals, 0 if it's only in one of them
+#[derive(Debug, PartialEq)]
+pub struct Change {
+    pub name: String,
+    pub old_g: u64,
+    pub new_g: u64,
+}
+
+/// The ingredients whose grams differ, by name
+pub fn changes(old: &Proposal, new: &Proposal) -> Vec<Change> {
+    let mut names: Vec<&String> = old.0.keys().chain(new.0.keys()).collect();
+    names.sort();
+    names.dedup();
+    names
+        .into_iter()
+        .map(|name| Change {
+            name: name.clone(),
+            old_g: old.0.get(name).copied().unwrap_or(0),
+            new_g: new.0.get(name).copied().unwrap_or(0),
+        })
+        .filter(|change| change.old_g != change.new_g)
+        .collect()
+}
+
+/// e.g. "+25" or "-150"
+fn delta(old: f64, new: f64) -> String {
+    format!("{:+.0}", new - old)
+}
+
+/// e.g. "oats                    100g ->    125g     +25g"
+fn row(name: &str, old: f64, new: f64, unit: &str) -> String {
+    format!(
+        "{:<20} {:>8} -> {:>8} {:>8}",
+        name,
+        format!("{:.0}{}", old, unit),
+        format!("{:.0}{}", new, unit),
+        format!("{}{}", delta(old.round(), new.round()), unit)
+    )
+}
+
+pub fn print_diff(old: &Proposal, new: &Proposal, raw_ingredients: &RawIngredients) {
+    println!("---- DIFF ----");
+    let changes = changes(old, new);
+    if changes.is_empty() {
+        println!("The ingredients are the same.");
+    }
+    for change in &changes {
+        let (old_g, new_g) = (change.old_g as f64, change.new_g as f64);
+        println!("{}", row(&change.name, old_g, new_g, "g"));
+    }
+    let old = Macros::from_grams(old, raw_ingredients);
+    let new = Macros::from_grams(new, raw_ingredients);
+    println!();
+    println!("{}", row("kcal", old.kcal, new.kcal, ""));
+    println!("{}", row("carb", old.carb, new.carb, "g"));
+    println!("{}", row("fat", old.fat, new.fat, "g"));
+    println!("{}", row("protein", old.protein, new.protein, "g"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changes() {
+        let proposal = |pieces: &[(&str, u64)]| {
+            Proposal(
+                pieces
+                    .iter()
+                    .map(|(name, g)| (name.to_string(), *g))
+                    .collect(),
+            )
+        };
+        let old = proposal(&[("oats", 100), ("milk", 300), ("tofu", 150)]);
+        let new = proposal(&[("oats", 125), ("milk", 300), ("egg", 120)]);
+        let change = |name: &str, old_g, new_g| Change {
+            name: name.to_string(),
+            old_g,
+            new_g,
+        };
+        assert_eq!(
+            changes(&old, &new),
+            vec![
+                change("egg", 0, 120),
+                change("oats", 100, 125),
+                change("tofu", 150, 0)
+            ]
+        );
+        assert_eq!(
+            row("oats", 100.0, 125.0, "g"),
+            "oats                     100g ->     125g     +25g"
+        );
+        assert_eq!(delta(150.0, 0.0), "-150");
+    }
+}
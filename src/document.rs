@@ -0,0 +1,301 @@
+//! The result as a Markdown or HTML document, e.g. to send to a coach or to print for the
+//! kitchen.
+use crate::cli::DocumentFormat;
+use crate::output::{Ratio, Report};
+use crate::Target;
+
+enum Block {
+    Heading(String),
+    Paragraph(String),
+    Table {
+        header: Vec<String>,
+        rows: Vec<Vec<String>>,
+    },
+    List(Vec<String>),
+}
+
+/// e.g. "oats: at most 80g", one per constraint and limit of the target
+fn constraints(target: &Target, report: &Report) -> Vec<String> {
+    let mut constraints = Vec::new();
+    let grams = [
+        ("exactly", &target.constraint_exact),
+        ("at least", &target.constraint_at_least),
+        ("at most", &target.constraint_at_most),
+    ];
+    for (limit, list) in grams.iter() {
+        for constraint in list.iter().flatten() {
+            constraints.push(format!("{}: {} {}g", constraint.name, limit, constraint.g));
+        }
+    }
+    for constraint in target.constraint_max_weight_pct.iter().flatten() {
+        constraints.push(format!(
+            "{}: at most {}% of the weight",
+            constraint.name, constraint.pct
+        ));
+    }
+    for constraint in target.constraint_max_kcal_pct.iter().flatten() {
+        constraints.push(format!(
+            "{}: at most {}% of the kcal",
+            constraint.name, constraint.pct
+        ));
+    }
+    if let Some(share) = target.max_single_ingredient_kcal_share {
+        constraints.push(format!(
+            "any ingredient: at most {}% of the kcal",
+            (share * 100.0).round()
+        ));
+    }
+    if let Some(sodium) = report.sodium.as_ref().filter(|sodium| sodium.max.is_some()) {
+        constraints.push(sodium.summary("Sodium", " mg"));
+    }
+    if let Some(load) = report
+        .glycemic_load
+        .as_ref()
+        .filter(|load| load.max.is_some())
+    {
+        constraints.push(load.summary("Glycemic load", ""));
+    }
+    if let Some(quality) = report
+        .quality_protein
+        .as_ref()
+        .filter(|quality| quality.min_pct.is_some())
+    {
+        constraints.push(quality.summary());
+    }
+    if let Some(omega) = report
+        .omega
+        .as_ref()
+        .filter(|omega| omega.max_ratio.is_some())
+    {
+        constraints.push(omega.summary());
+    }
+    let bounded = [
+        (&report.water, "Water", " ml"),
+        (&report.caffeine, "Caffeine", " mg"),
+        (&report.leucine, "Leucine", "g"),
+        (&report.eaa, "Essential amino acids", "g"),
+        (&report.prep_minutes, "Prep time", " min"),
+    ];
+    for (limited, name, unit) in bounded.iter() {
+        if let Some(limited) = limited
+            .as_ref()
+            .filter(|limited| limited.min.is_some() || limited.max.is_some())
+        {
+            constraints.push(limited.summary(name, unit));
+        }
+    }
+    constraints
+}
+
+fn blocks(report: &Report, target: &Target, target_ratio: &Ratio) -> Vec<Block> {
+    let mut blocks = vec![
+        Block::Heading("Meal plan".to_string()),
+        Block::Paragraph(format!(
+            "{} kcal with {}g carbs, {}g fat and {}g protein, for a target of {} kcal.",
+            report.total.kcal,
+            report.total.carb,
+            report.total.fat,
+            report.total.protein,
+            target.kcal
+        )),
+    ];
+    let header = [
+        "Ingredient",
+        "g",
+        "kcal",
+        "Carbs (g)",
+        "Fat (g)",
+        "Protein (g)",
+    ];
+    let rows = report
+        .ingredients
+        .iter()
+        // the kitchen only needs what is used
+        .filter(|amount| amount.g > 0)
+        .chain(std::iter::once(&report.total))
+        .map(|amount| {
+            vec![
+                amount.name.clone(),
+                amount.g.to_string(),
+                amount.kcal.to_string(),
+                amount.carb.to_string(),
+                amount.fat.to_string(),
+                amount.protein.to_string(),
+            ]
+        })
+        .collect();
+    blocks.push(Block::Table {
+        header: header.iter().map(|column| column.to_string()).collect(),
+        rows,
+    });
+
+    blocks.push(Block::Heading("Macros".to_string()));
+    let header = ["Macro", "g", "% of the macros", "Target %"];
+    let macros = [
+        (
+            "Carbs",
+            report.total.carb,
+            report.ratio.carb,
+            target_ratio.carb,
+        ),
+        ("Fat", report.total.fat, report.ratio.fat, target_ratio.fat),
+        (
+            "Protein",
+            report.total.protein,
+            report.ratio.protein,
+            target_ratio.protein,
+        ),
+    ];
+    blocks.push(Block::Table {
+        header: header.iter().map(|column| column.to_string()).collect(),
+        rows: macros
+            .iter()
+            .map(|(name, g, pct, target_pct)| {
+                vec![
+                    name.to_string(),
+                    g.to_string(),
+                    pct.to_string(),
+                    target_pct.to_string(),
+                ]
+            })
+            .collect(),
+    });
+
+    blocks.push(Block::Heading("Constraints".to_string()));
+    let constraints = constraints(target, report);
+    if constraints.is_empty() {
+        blocks.push(Block::Paragraph("None.".to_string()));
+    } else {
+        blocks.push(Block::List(constraints));
+    }
+    blocks
+}
+
+fn markdown(blocks: &[Block]) -> String {
+    let mut document = Vec::new();
+    for (i, block) in blocks.iter().enumerate() {
+        document.push(match block {
+            // the first heading is the title
+            Block::Heading(text) if i == 0 => format!("# {}", text),
+            Block::Heading(text) => format!("## {}", text),
+            Block::Paragraph(text) => text.clone(),
+            Block::Table { header, rows } => {
+                let row = |cells: &[String]| {
+                    let cells: Vec<String> =
+                        cells.iter().map(|cell| cell.replace('|', "\\|")).collect();
+                    format!("| {} |", cells.join(" | "))
+                };
+                let mut lines = vec![row(header)];
+                // the name column is left aligned and the amounts right aligned
+                let align: Vec<&str> = (0..header.len())
+                    .map(|i| if i == 0 { ":--" } else { "--:" })
+                    .collect();
+                lines.push(format!("| {} |", align.join(" | ")));
+                lines.extend(rows.iter().map(|cells| row(cells)));
+                lines.join("\n")
+            }
+            Block::List(items) => {
+                let items: Vec<String> = items.iter().map(|item| format!("- {}", item)).collect();
+                items.join("\n")
+            }
+        });
+    }
+    document.join("\n\n") + "\n"
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn html(blocks: &[Block]) -> String {
+    let title = match blocks.first() {
+        Some(Block::Heading(title)) => escape(title),
+        _ => String::new(),
+    };
+    let mut document = vec![
+        "<!DOCTYPE html>".to_string(),
+        "<html>".to_string(),
+        format!("<head><meta charset=\"utf-8\"><title>{}</title>", title),
+        "<style>table { border-collapse: collapse; } th, td { border: 1px solid #999; \
+         padding: 0.2em 0.6em; } td + td { text-align: right; }</style>"
+            .to_string(),
+        "</head>".to_string(),
+        "<body>".to_string(),
+    ];
+    for (i, block) in blocks.iter().enumerate() {
+        match block {
+            Block::Heading(text) if i == 0 => document.push(format!("<h1>{}</h1>", escape(text))),
+            Block::Heading(text) => document.push(format!("<h2>{}</h2>", escape(text))),
+            Block::Paragraph(text) => document.push(format!("<p>{}</p>", escape(text))),
+            Block::Table { header, rows } => {
+                let row = |cells: &[String], tag: &str| {
+                    let cells: Vec<String> = cells
+                        .iter()
+                        .map(|cell| format!("<{}>{}</{}>", tag, escape(cell), tag))
+                        .collect();
+                    format!("<tr>{}</tr>", cells.concat())
+                };
+                document.push("<table>".to_string());
+                document.push(row(header, "th"));
+                document.extend(rows.iter().map(|cells| row(cells, "td")));
+                document.push("</table>".to_string());
+            }
+            Block::List(items) => {
+                document.push("<ul>".to_string());
+                document.extend(
+                    items
+                        .iter()
+                        .map(|item| format!("<li>{}</li>", escape(item))),
+                );
+                document.push("</ul>".to_string());
+            }
+        }
+    }
+    document.push("</body>".to_string());
+    document.push("</html>".to_string());
+    document.join("\n") + "\n"
+}
+
+/// The ingredient table, the macros compared to the target's ratio and the constraints
+pub fn render(
+    report: &Report,
+    target: &Target,
+    target_ratio: &Ratio,
+    format: DocumentFormat,
+) -> String {
+    let blocks = blocks(report, target, target_ratio);
+    match format {
+        DocumentFormat::Md => markdown(&blocks),
+        DocumentFormat::Html => html(&blocks),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let blocks = vec![
+            Block::Heading("Meal plan".to_string()),
+            Block::Table {
+                header: vec!["Ingredient".to_string(), "g".to_string()],
+                rows: vec![vec!["mac & cheese".to_string(), "250".to_string()]],
+            },
+            Block::Heading("Constraints".to_string()),
+            Block::List(vec!["olive oil: at most 15g".to_string()]),
+        ];
+        assert_eq!(
+            markdown(&blocks),
+            "# Meal plan\n\n\
+             | Ingredient | g |\n| :-- | --: |\n| mac & cheese | 250 |\n\n\
+             ## Constraints\n\n- olive oil: at most 15g\n"
+        );
+        let html = html(&blocks);
+        assert!(html.contains("<tr><td>mac &amp; cheese</td><td>250</td></tr>"));
+        assert!(html.contains("<h2>Constraints</h2>\n<ul>\n<li>olive oil: at most 15g</li>\n</ul>"));
+    }
+}
@@ -0,0 +1,289 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("could not read {}: {source}", .path.display())]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("could not write {}: {source}", .path.display())]
+    Write {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("could not parse {}: {source}", .path.display())]
+    Parse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    #[error("could not parse {}: {source}", .path.display())]
+    ParseJson {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[error("could not serialize {}: {source}", .path.display())]
+    Serialize {
+        path: PathBuf,
+        source: toml::ser::Error,
+    },
+    #[error("could not write json output: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("could not write csv output: {0}")]
+    Csv(#[from] csv::Error),
+    #[error("could not read {}: {source}", .path.display())]
+    ReadCsv { path: PathBuf, source: csv::Error },
+    #[error("{} has no column \"{column}\"", .path.display())]
+    MissingColumn { path: PathBuf, column: String },
+    #[cfg(feature = "http")]
+    #[error("request to {url} failed: {source}")]
+    Http { url: String, source: ureq::Error },
+    #[error("no food matches \"{0}\"")]
+    FoodNotFound(String),
+    #[error("\"{query}\" matches several foods, use a more specific name or the ID:\n\t{}", .candidates.join("\n\t"))]
+    AmbiguousFood {
+        query: String,
+        candidates: Vec<String>,
+    },
+    #[error("{food} has no {nutrient} value")]
+    MissingNutrient { food: String, nutrient: String },
+    #[error("recipe \"{recipe}\" contains \"{component}\" which isn't loaded or contains the recipe itself")]
+    MissingComponent { recipe: String, component: String },
+    #[error("ingredient \"{name}\" {reason}")]
+    InvalidAmount { name: String, reason: String },
+    #[error("target {0}")]
+    InvalidTarget(String),
+    #[error("{} has no name", .0.display())]
+    MissingName(PathBuf),
+    #[error("\"{name}\" is the name of both {} and {}, choose one with --on-duplicate", .first.display(), .second.display())]
+    DuplicateName {
+        name: String,
+        first: PathBuf,
+        second: PathBuf,
+    },
+    #[error("{0} change(s) of the patch conflict with the pantry, nothing was applied")]
+    PatchConflicts(usize),
+    #[error("target constraint refers to ingredient \"{name}\" which isn't loaded{}", did_you_mean(.suggestions))]
+    MissingIngredient {
+        name: String,
+        // loaded names that are close to it
+        suggestions: Vec<String>,
+    },
+    #[error("\"{alias}\" can mean both \"{first}\" and \"{second}\", remove it from the aliases of one of them")]
+    AmbiguousAlias {
+        alias: String,
+        first: String,
+        second: String,
+    },
+    #[error("ingredient \"{0}\" isn't loaded")]
+    UnknownIngredient(String),
+    #[error("exact and at least constraints on {constraints} need {assigned} kcal pieces but the target only has {steps}")]
+    ConstraintsExceedTarget {
+        assigned: u64,
+        steps: usize,
+        constraints: String,
+    },
+    #[error("at most constraints on {constraints} leave room for only {available} of the target's {steps} kcal pieces")]
+    AtMostBelowTarget {
+        available: u64,
+        steps: usize,
+        constraints: String,
+    },
+    #[error("{name} needs at least {at_least} but at most {at_most} kcal pieces")]
+    ConflictingConstraints {
+        name: String,
+        at_least: u64,
+        at_most: u64,
+    },
+    #[error("no ingredient can be added after {assigned} of {steps} kcal pieces because of the constraints")]
+    Infeasible { assigned: usize, steps: usize },
+    #[error("exact and at least constraints have {amount} {nutrient} but the target allows at most {max}")]
+    ConstraintsExceedLimit {
+        nutrient: &'static str,
+        amount: f64,
+        max: f64,
+    },
+    #[error("{0} file(s) failed validation")]
+    ValidationFailed(usize),
+    #[error("{weights} cooked weight(s) given for {meals} meal(s)")]
+    CookedWeightCount { meals: usize, weights: usize },
+    #[error("container shares {0}")]
+    InvalidShares(String),
+    #[cfg(feature = "server")]
+    #[error("could not listen on {addr}: {source}")]
+    Serve {
+        addr: String,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    #[error("{} already exists", .0.display())]
+    FileExists(PathBuf),
+    #[error("\"{0}\" can't be the name of a file in the pantry")]
+    InvalidFileName(String),
+    #[error("{} already exists and isn't empty", .0.display())]
+    DirectoryNotEmpty(PathBuf),
+    #[error("the exact search over {ingredients} ingredients at {steps} steps has too many proposals to try; use fewer ingredients or --steps")]
+    ExactSearchTooLarge { ingredients: usize, steps: usize },
+    #[error("imported meals and fixed foods have {meal_kcal} kcal which doesn't leave anything of the target's {target_kcal} kcal")]
+    MealsExceedTarget { meal_kcal: f64, target_kcal: u64 },
+    #[error("imported meals and fixed foods have all of the target's carb, fat and protein but only {meal_kcal} of its {target_kcal} kcal")]
+    MealsExceedMacros { meal_kcal: f64, target_kcal: u64 },
+}
+
+/// An error as printed with `--error-format json`
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ErrorReport<'a> {
+    /// Stable identifier of the kind of error
+    pub code: &'static str,
+    pub message: String,
+    /// File that caused the error
+    pub file: Option<&'a Path>,
+    /// Field, column or name in the file or target that caused the error
+    pub field: Option<&'a str>,
+    /// Position of a parse error in the file
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+/// e.g. ", did you mean \"oats\" or \"oat flakes\"?", or nothing without suggestions
+fn did_you_mean(suggestions: &[String]) -> String {
+    if suggestions.is_empty() {
+        return String::new();
+    }
+    let quoted: Vec<String> = suggestions
+        .iter()
+        .map(|suggestion| format!("\"{}\"", suggestion))
+        .collect();
+    format!(", did you mean {}?", quoted.join(" or "))
+}
+
+impl Error {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::Read { .. } => "read",
+            Error::Write { .. } => "write",
+            Error::Parse { .. } | Error::ParseJson { .. } => "parse",
+            Error::Serialize { .. } => "serialize",
+            Error::Json(_) | Error::Csv(_) => "output",
+            Error::ReadCsv { .. } => "read_csv",
+            Error::MissingColumn { .. } => "missing_column",
+            #[cfg(feature = "http")]
+            Error::Http { .. } => "http",
+            Error::FoodNotFound(_) => "food_not_found",
+            Error::AmbiguousFood { .. } => "ambiguous_food",
+            Error::MissingNutrient { .. } => "missing_nutrient",
+            Error::MissingComponent { .. } => "missing_component",
+            Error::InvalidAmount { .. } => "invalid_amount",
+            Error::InvalidTarget(_) => "invalid_target",
+            Error::MissingName(_) => "missing_name",
+            Error::DuplicateName { .. } => "duplicate_name",
+            Error::PatchConflicts(_) => "patch_conflicts",
+            Error::MissingIngredient { .. } => "missing_ingredient",
+            Error::AmbiguousAlias { .. } => "ambiguous_alias",
+            Error::UnknownIngredient(_) => "unknown_ingredient",
+            Error::ConstraintsExceedTarget { .. } => "constraints_exceed_target",
+            Error::AtMostBelowTarget { .. } => "at_most_below_target",
+            Error::ConflictingConstraints { .. } => "conflicting_constraints",
+            Error::Infeasible { .. } => "infeasible",
+            Error::ExactSearchTooLarge { .. } => "exact_search_too_large",
+            Error::ConstraintsExceedLimit { .. } => "constraints_exceed_limit",
+            Error::ValidationFailed(_) => "validation_failed",
+            Error::MealsExceedTarget { .. } => "meals_exceed_target",
+            Error::MealsExceedMacros { .. } => "meals_exceed_macros",
+            Error::CookedWeightCount { .. } => "cooked_weight_count",
+            Error::InvalidShares(_) => "invalid_shares",
+            Error::FileExists(_) => "file_exists",
+            Error::InvalidFileName(_) => "invalid_file_name",
+            Error::DirectoryNotEmpty(_) => "directory_not_empty",
+            #[cfg(feature = "server")]
+            Error::Serve { .. } => "serve",
+        }
+    }
+
+    pub fn report(&self) -> ErrorReport<'_> {
+        let file = match self {
+            Error::Read { path, .. }
+            | Error::Write { path, .. }
+            | Error::Parse { path, .. }
+            | Error::ParseJson { path, .. }
+            | Error::Serialize { path, .. }
+            | Error::ReadCsv { path, .. }
+            | Error::MissingColumn { path, .. }
+            | Error::DuplicateName { second: path, .. } => Some(path.as_path()),
+            Error::MissingName(path) | Error::FileExists(path) | Error::DirectoryNotEmpty(path) => {
+                Some(path.as_path())
+            }
+            _ => None,
+        };
+        let field = match self {
+            Error::MissingColumn { column, .. } => Some(column.as_str()),
+            Error::MissingNutrient { nutrient, .. } => Some(nutrient.as_str()),
+            Error::MissingComponent { component, .. } => Some(component.as_str()),
+            Error::MissingIngredient { name, .. }
+            | Error::UnknownIngredient(name)
+            | Error::InvalidFileName(name)
+            | Error::InvalidAmount { name, .. }
+            | Error::DuplicateName { name, .. }
+            | Error::AmbiguousAlias { alias: name, .. }
+            | Error::ConflictingConstraints { name, .. } => Some(name.as_str()),
+            _ => None,
+        };
+        let (line, column) = match self {
+            // toml counts from 0
+            Error::Parse { source, .. } => match source.line_col() {
+                Some((line, column)) => (Some(line + 1), Some(column + 1)),
+                None => (None, None),
+            },
+            Error::ParseJson { source, .. } => (Some(source.line()), Some(source.column())),
+            _ => (None, None),
+        };
+        ErrorReport {
+            code: self.code(),
+            message: self.to_string(),
+            file,
+            field,
+            line,
+            column,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let source = toml::from_str::<toml::Value>("kcal = 2000\ncarb = ").unwrap_err();
+        let err = Error::Parse {
+            path: PathBuf::from("target.toml"),
+            source,
+        };
+        let report = err.report();
+        assert_eq!(report.code, "parse");
+        assert_eq!(report.file, Some(Path::new("target.toml")));
+        assert_eq!(report.line, Some(2));
+
+        let err = Error::MissingIngredient {
+            name: "oats".to_string(),
+            suggestions: vec!["oat flakes".to_string()],
+        };
+        assert_eq!(
+            err.to_string(),
+            "target constraint refers to ingredient \"oats\" which isn't loaded, \
+             did you mean \"oat flakes\"?"
+        );
+        assert_eq!(
+            serde_json::to_value(err.report()).unwrap(),
+            serde_json::json!({
+                "code": "missing_ingredient",
+                "message": err.to_string(),
+                "file": null,
+                "field": "oats",
+                "line": null,
+                "column": null,
+            })
+        );
+    }
+}
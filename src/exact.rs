@@ -0,0 +1,353 @@
+//! Exact search for small pantries: branch and bound over the pieces of every ingredient, which
+//! finds the proposal with the lowest cost at the given steps.
+use crate::error::Error;
+use crate::{
+    feasibility, optimize, Ingredients, MixTotals, NormalizedIngredient, NormalizedTarget,
+    Proposal, TargetConstraints,
+};
+
+/// Most proposals the exact search may have to try, beyond which it wouldn't finish in
+/// reasonable time
+const MAX_EXACT_PROPOSALS: f64 = 1e11;
+
+/// Amounts an ingredient can have, in pieces
+pub struct Domain {
+    // ascending, the largest amount is also limited by the rest of the pieces
+    pub values: Vec<u64>,
+}
+
+impl Domain {
+    pub fn new(name: &str, constraints: &TargetConstraints, max_steps: u64) -> Domain {
+        if let Some(exact) = constraints.exact.0.get(name) {
+            return Domain {
+                values: vec![*exact],
+            };
+        }
+        let portion = constraints.portion.0.get(name).copied().unwrap_or(1).max(1);
+        let at_least = constraints.at_least.0.get(name).copied().unwrap_or(0);
+        let at_most = constraints
+            .at_most
+            .0
+            .get(name)
+            .copied()
+            .unwrap_or(max_steps)
+            .min(max_steps);
+        let mut values = Vec::new();
+        // an ingredient with a minimum if used is either left out or used at least that much
+        let start = match constraints.at_least_if_used.0.get(name) {
+            Some(min) if at_least == 0 => {
+                values.push(0);
+                (*min).max(portion)
+            }
+            _ => at_least,
+        };
+        values.extend((start..=at_most).step_by(portion as usize));
+        Domain { values }
+    }
+
+    /// Index of the allowed amount closest to `pieces`
+    pub fn nearest(&self, pieces: u64) -> usize {
+        match self.values.binary_search(&pieces) {
+            Ok(index) => index,
+            Err(0) => 0,
+            Err(index) if index == self.values.len() => index - 1,
+            Err(index) if pieces - self.values[index - 1] <= self.values[index] - pieces => {
+                index - 1
+            }
+            Err(index) => index,
+        }
+    }
+}
+
+/// Whether the mix has more sodium, glycemic load, water or caffeine than the target allows
+pub fn exceeds_limits(constraints: &TargetConstraints, totals: &MixTotals) -> bool {
+    let exceeds = |max: Option<f64>, total: f64| max.is_some_and(|max| total > max);
+    exceeds(constraints.max_sodium, totals.sodium_mg)
+        || exceeds(constraints.max_glycemic_load, totals.glycemic_load)
+        || exceeds(constraints.max_water, totals.water_ml)
+        || exceeds(constraints.max_caffeine, totals.caffeine_mg)
+}
+
+/// Number of ways to split `pieces` pieces among `ingredients` ingredients, as an upper bound of
+/// the proposals the search tries
+fn proposals(ingredients: usize, pieces: usize) -> f64 {
+    (1..ingredients).fold(1.0, |count, i| count * (pieces + i) as f64 / i as f64)
+}
+
+/// Cost of the mix's macros alone, which is at most the cost of the whole mix
+fn macro_cost(target: &NormalizedTarget, shares: [(f64, f64); 3]) -> f64 {
+    let weights = &target.weights;
+    let wanted = [
+        (target.carb, weights.carb),
+        (target.fat, weights.fat),
+        (target.protein, weights.protein),
+    ];
+    wanted
+        .iter()
+        .zip(shares.iter())
+        .map(|((wanted, weight), (least, most))| {
+            let distance = (least - wanted).max(wanted - most).max(0.0);
+            weight * distance * distance
+        })
+        .sum()
+}
+
+/// Lowest cost of the macros of any mix that adds `rest` pieces of `remaining` to `totals`.
+///
+/// A macro's share of such a mix is a ratio of sums that are linear in the pieces, so its
+/// smallest and largest values are at mixes of a single ingredient.
+fn lower_bound(
+    target: &NormalizedTarget,
+    totals: &MixTotals,
+    remaining: &[&NormalizedIngredient],
+    rest: (u64, u64),
+) -> f64 {
+    let macros = |carb: f64, fat: f64, protein: f64, alcohol: f64| {
+        let sum = carb + fat + protein + alcohol;
+        [carb / sum, fat / sum, protein / sum]
+    };
+    let mut shares = [(f64::INFINITY, f64::NEG_INFINITY); 3];
+    let mut include = |mix: [f64; 3]| {
+        for (share, value) in shares.iter_mut().zip(mix.iter()) {
+            if value.is_finite() {
+                share.0 = share.0.min(*value);
+                share.1 = share.1.max(*value);
+            }
+        }
+    };
+    if rest.0 == 0 {
+        include(macros(
+            totals.carb,
+            totals.fat,
+            totals.protein,
+            totals.alcohol,
+        ));
+    }
+    for ingredient in remaining {
+        for pieces in [rest.0, rest.1] {
+            let pieces = pieces as f64;
+            include(macros(
+                totals.carb + pieces * ingredient.carb,
+                totals.fat + pieces * ingredient.fat,
+                totals.protein + pieces * ingredient.protein,
+                totals.alcohol + pieces * ingredient.alcohol,
+            ));
+        }
+    }
+    if shares.iter().any(|(least, _)| !least.is_finite()) {
+        return 0.0;
+    }
+    macro_cost(target, shares)
+}
+
+struct Search<'a> {
+    target: &'a NormalizedTarget,
+    constraints: &'a TargetConstraints,
+    names: Vec<&'a String>,
+    ingredients: Vec<&'a NormalizedIngredient>,
+    domains: Vec<Domain>,
+    // fewest and most pieces of the result
+    band: (u64, u64),
+    proposal: Vec<u64>,
+    best: Option<(Vec<u64>, f64)>,
+}
+
+impl Search<'_> {
+    fn exceeds_weight_shares(&self, totals: &MixTotals) -> bool {
+        self.names.iter().enumerate().any(|(i, name)| {
+            self.constraints
+                .max_weight_share
+                .get(*name)
+                .is_some_and(|max_share| {
+                    self.proposal[i] as f64 * self.ingredients[i].g > max_share * totals.g
+                })
+        })
+    }
+
+    /// Whether using ingredient `i` too takes longer to prepare than the target allows
+    fn exceeds_prep_time(&self, i: usize) -> bool {
+        let used = self.names[..i]
+            .iter()
+            .zip(&self.proposal)
+            .filter(|(_, pieces)| **pieces > 0)
+            .map(|(name, _)| *name);
+        self.constraints
+            .exceeds_prep_time(used.chain([self.names[i]]))
+    }
+
+    fn cost(&self, totals: &MixTotals) -> f64 {
+        let square_sum = self.proposal.iter().map(|p| (p * p) as f64).sum();
+        let reference_cost = self
+            .constraints
+            .reference
+            .as_ref()
+            .map_or(0.0, |reference| {
+                let dot = self
+                    .names
+                    .iter()
+                    .zip(&self.proposal)
+                    .map(|(name, p)| *p as f64 * reference.share(name))
+                    .sum();
+                reference.cost(square_sum, dot, totals.pieces as f64)
+            });
+        let expiry_cost = self.constraints.expiry.as_ref().map_or(0.0, |expiry| {
+            let dot = self
+                .names
+                .iter()
+                .zip(&self.proposal)
+                .map(|(name, p)| *p as f64 * expiry.urgency(name))
+                .sum();
+            expiry.cost(dot, totals.pieces as f64)
+        });
+        self.target.evaluate_totals(totals)
+            + self
+                .target
+                .concentration_cost(square_sum, totals.pieces as f64)
+            + reference_cost
+            + expiry_cost
+    }
+
+    fn visit(&mut self, i: usize, totals: MixTotals) {
+        let assigned = totals.pieces;
+        if i == self.ingredients.len() {
+            if assigned < self.band.0 || self.exceeds_weight_shares(&totals) {
+                return;
+            }
+            let cost = self.cost(&totals);
+            if self.best.as_ref().is_none_or(|(_, best)| cost < *best) {
+                self.best = Some((self.proposal.clone(), cost));
+            }
+            return;
+        }
+        if let Some((_, best)) = &self.best {
+            let rest = (self.band.0.saturating_sub(assigned), self.band.1 - assigned);
+            if lower_bound(self.target, &totals, &self.ingredients[i..], rest) >= *best {
+                return;
+            }
+        }
+        for value_index in 0..self.domains[i].values.len() {
+            let pieces = self.domains[i].values[value_index];
+            // every amount but 0 uses the ingredient
+            if assigned + pieces > self.band.1 || (pieces > 0 && self.exceeds_prep_time(i)) {
+                break;
+            }
+            let totals = totals.with(self.ingredients[i], pieces);
+            // the nutrients only grow with more pieces
+            if exceeds_limits(self.constraints, &totals) {
+                break;
+            }
+            self.proposal[i] = pieces;
+            self.visit(i + 1, totals);
+        }
+        self.proposal[i] = 0;
+    }
+}
+
+/// Finds the proposal with the lowest cost by trying all of them, skipping those that can't be
+/// better than the best one so far. The greedy search's result is the first best one.
+pub fn optimize_exact(
+    target: &NormalizedTarget,
+    constraints: TargetConstraints,
+    ingredients: &Ingredients,
+    steps: usize,
+) -> Result<Proposal, Error> {
+    feasibility::check(&constraints, ingredients, steps)?;
+    let (min_steps, max_steps) = constraints.step_band(steps);
+    let count = proposals(ingredients.0.len(), max_steps);
+    if count > MAX_EXACT_PROPOSALS {
+        return Err(Error::ExactSearchTooLarge {
+            ingredients: ingredients.0.len(),
+            steps: max_steps,
+        });
+    }
+    let names: Vec<&String> = ingredients.0.keys().collect();
+    let mut search = Search {
+        target,
+        constraints: &constraints,
+        domains: names
+            .iter()
+            .map(|name| Domain::new(name, &constraints, max_steps as u64))
+            .collect(),
+        ingredients: names.iter().map(|name| &ingredients.0[*name]).collect(),
+        proposal: vec![0; names.len()],
+        names,
+        band: (min_steps as u64, max_steps as u64),
+        best: None,
+    };
+    if let Ok(greedy) = optimize(target, constraints.clone(), ingredients, steps) {
+        search.proposal = search.names.iter().map(|name| greedy.0[*name]).collect();
+        let totals = MixTotals::new(&greedy, ingredients);
+        let used = greedy.0.iter().filter(|(_, pieces)| **pieces > 0);
+        if !exceeds_limits(&constraints, &totals)
+            && !search.exceeds_weight_shares(&totals)
+            && !constraints.exceeds_prep_time(used.map(|(name, _)| name))
+        {
+            search.best = Some((search.proposal.clone(), search.cost(&totals)));
+        }
+        search.proposal = vec![0; search.names.len()];
+    }
+    search.visit(
+        0,
+        MixTotals::new(&Proposal(Default::default()), ingredients),
+    );
+    match search.best {
+        Some((pieces, _)) => Ok(Proposal(
+            search
+                .names
+                .iter()
+                .map(|name| name.to_string())
+                .zip(pieces)
+                .collect(),
+        )),
+        None => Err(Error::Infeasible { assigned: 0, steps }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Ingredient;
+
+    #[test]
+    fn test_optimize_exact() {
+        assert_eq!(proposals(3, 10), 66.0);
+        let t = NormalizedTarget {
+            carb: 0.25,
+            fat: 0.4,
+            protein: 0.35,
+            ..Default::default()
+        };
+        let ingredient = |carb, fat, protein| {
+            let mut ingredient = Ingredient {
+                g: 100,
+                carb,
+                fat,
+                protein,
+                ..Default::default()
+            };
+            ingredient.kcal = ingredient.macro_kcal();
+            ingredient.normalize()
+        };
+        let mut ingredients = Ingredients(Default::default());
+        ingredients
+            .0
+            .insert("apple".to_string(), ingredient(20.0, 3.0, 5.0));
+        ingredients
+            .0
+            .insert("banana".to_string(), ingredient(4.0, 30.0, 6.0));
+        let exact = optimize_exact(&t, TargetConstraints::new(), &ingredients, 20).unwrap();
+        assert_eq!(exact.kcal(), 20);
+        // no proposal of 20 pieces is better
+        let best = (0..=20)
+            .map(|apple| {
+                let mut proposal = Proposal(Default::default());
+                proposal.0.insert("apple".to_string(), apple);
+                proposal.0.insert("banana".to_string(), 20 - apple);
+                t.evaluate(&proposal, &ingredients)
+            })
+            .fold(f64::INFINITY, f64::min);
+        assert_eq!(t.evaluate(&exact, &ingredients), best);
+        let greedy = optimize(&t, TargetConstraints::new(), &ingredients, 20).unwrap();
+        assert!(t.evaluate(&exact, &ingredients) <= t.evaluate(&greedy, &ingredients));
+    }
+}
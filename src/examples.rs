@@ -0,0 +1,348 @@
+//! Writes a worked example with a pantry, recipes and targets to try every command on.
+use crate::error::Error;
+use crate::flavoring::{Flavoring, Flavorings};
+use crate::pantry::file_name;
+use crate::recipe::{Component, Recipe};
+use crate::{write_toml, Ingredient, Target, TargetConstraint};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+// name and g per plan on the shopping list
+const FLAVORINGS: &[(&str, u64)] = &[
+    ("cinnamon", 5),
+    ("chili powder", 10),
+    ("mustard", 15),
+    ("vinegar", 30),
+    ("sweetener", 2),
+];
+
+// name, kcal, carb, fat and protein per 100g
+const FOODS: &[(&str, f64, f64, f64, f64)] = &[
+    ("oats", 372.0, 58.7, 7.0, 13.5),
+    ("rice", 350.0, 77.0, 0.6, 7.0),
+    ("pasta", 359.0, 71.0, 1.5, 13.0),
+    ("potatoes", 77.0, 17.0, 0.1, 2.0),
+    ("sweet potatoes", 86.0, 20.0, 0.1, 1.6),
+    ("whole wheat bread", 247.0, 41.0, 3.4, 13.0),
+    ("banana", 89.0, 20.2, 0.3, 1.1),
+    ("apple", 52.0, 12.0, 0.2, 0.3),
+    ("blueberries", 57.0, 12.2, 0.3, 0.7),
+    ("broccoli", 34.0, 4.4, 0.4, 2.8),
+    ("spinach", 23.0, 1.6, 0.4, 2.9),
+    ("bell pepper", 31.0, 6.0, 0.3, 1.0),
+    ("canned tomatoes", 21.0, 3.5, 0.2, 1.1),
+    ("kidney beans", 110.0, 18.0, 0.5, 7.5),
+    ("lentils", 352.0, 60.0, 1.1, 25.0),
+    ("chickpeas", 139.0, 20.0, 2.6, 7.2),
+    ("egg", 143.0, 0.7, 9.5, 12.6),
+    ("quark", 67.0, 4.0, 0.2, 12.0),
+    ("greek yogurt", 59.0, 3.6, 0.4, 10.0),
+    ("milk", 47.0, 4.8, 1.5, 3.4),
+    ("cottage cheese", 98.0, 3.4, 4.3, 11.0),
+    ("whey protein", 400.0, 8.0, 7.0, 78.0),
+    ("chicken breast", 120.0, 0.0, 2.6, 23.0),
+    ("lean ground beef", 137.0, 0.0, 5.0, 21.0),
+    ("salmon", 208.0, 0.0, 13.0, 20.0),
+    ("tuna", 116.0, 0.0, 0.8, 26.0),
+    ("tofu", 144.0, 3.0, 8.7, 15.0),
+    ("olive oil", 884.0, 0.0, 100.0, 0.0),
+    ("peanut butter", 588.0, 20.0, 50.0, 25.0),
+    ("almonds", 579.0, 22.0, 50.0, 21.0),
+    ("dark chocolate", 580.0, 19.0, 46.0, 12.5),
+];
+// foods that are used in whole pieces, cans or jars
+const PORTIONS: &[(&str, u64)] = &[
+    ("banana", 120),
+    ("apple", 180),
+    ("egg", 60),
+    ("canned tomatoes", 400),
+    ("kidney beans", 240),
+    ("chickpeas", 240),
+    ("tuna", 130),
+];
+const PACKAGES: &[(&str, u64)] = &[
+    ("oats", 500),
+    ("rice", 1000),
+    ("pasta", 500),
+    ("lentils", 500),
+    ("quark", 500),
+    ("greek yogurt", 500),
+    ("whey protein", 1000),
+    ("chicken breast", 400),
+    ("lean ground beef", 500),
+    ("tofu", 400),
+    ("almonds", 200),
+];
+const MIN_G: &[(&str, u64)] = &[("rice", 60), ("pasta", 80), ("salmon", 125)];
+const MAX_G: &[(&str, u64)] = &[
+    ("whey protein", 60),
+    ("olive oil", 30),
+    ("peanut butter", 40),
+    ("almonds", 50),
+    ("dark chocolate", 20),
+];
+
+// groups the result is summarized by
+const GROUPS: &[(&str, &[&str])] = &[
+    ("grains", &["oats", "rice", "pasta", "whole wheat bread"]),
+    ("potatoes", &["potatoes", "sweet potatoes"]),
+    ("fruit", &["banana", "apple", "blueberries"]),
+    (
+        "vegetables",
+        &["broccoli", "spinach", "bell pepper", "canned tomatoes"],
+    ),
+    ("legumes", &["kidney beans", "lentils", "chickpeas"]),
+    (
+        "protein sources",
+        &[
+            "egg",
+            "quark",
+            "greek yogurt",
+            "milk",
+            "cottage cheese",
+            "whey protein",
+            "chicken breast",
+            "lean ground beef",
+            "salmon",
+            "tuna",
+            "tofu",
+        ],
+    ),
+    ("fats", &["olive oil", "peanut butter", "almonds"]),
+    ("treats", &["dark chocolate"]),
+];
+
+fn group(name: &str) -> Option<String> {
+    GROUPS
+        .iter()
+        .find(|(_, foods)| foods.contains(&name))
+        .map(|(group, _)| group.to_string())
+}
+
+fn lookup(table: &[(&str, u64)], name: &str) -> Option<u64> {
+    table
+        .iter()
+        .find(|(food, _)| *food == name)
+        .map(|(_, g)| *g)
+}
+
+fn recipe(name: &str, components: &[(&str, u64)], portion_g: Option<u64>) -> Recipe {
+    Recipe {
+        name: name.to_string(),
+        components: components
+            .iter()
+            .map(|(name, g)| Component {
+                name: name.to_string(),
+                g: *g,
+            })
+            .collect(),
+        min_g: None,
+        max_g: None,
+        portion_g,
+        batch_g: None,
+        prep_minutes: None,
+        group: Some("recipes".to_string()),
+        tags: Vec::new(),
+        meals: Vec::new(),
+    }
+}
+
+fn target(kcal: u64, carb: u64, fat: u64, protein: u64) -> Target {
+    Target {
+        kcal,
+        carb,
+        fat,
+        protein,
+        ..Default::default()
+    }
+}
+
+fn create_dir(dir: &Path) -> Result<(), Error> {
+    std::fs::create_dir_all(dir).map_err(|source| Error::Write {
+        path: dir.to_path_buf(),
+        source,
+    })
+}
+
+/// Writes a toml file of a struct that has arrays of tables before other fields
+fn write_tables<T: Serialize>(path: &Path, value: &T) -> Result<(), Error> {
+    // as a value, so tables are written after the other fields
+    let value = toml::Value::try_from(value).map_err(|source| Error::Serialize {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    write_toml(path, &value)
+}
+
+/// Writes the example into `dir`, which must not exist or be empty
+pub fn generate(dir: &Path) -> Result<(), Error> {
+    if std::fs::read_dir(dir).is_ok_and(|mut entries| entries.next().is_some()) {
+        return Err(Error::DirectoryNotEmpty(dir.to_path_buf()));
+    }
+    let pantry = dir.join("pantry");
+    let recipes = pantry.join("recipes");
+    let targets = dir.join("targets");
+    for dir in [&recipes, &targets] {
+        create_dir(dir)?;
+    }
+
+    for (name, kcal, carb, fat, protein) in FOODS {
+        let ingredient = Ingredient {
+            name: name.to_string(),
+            g: 100,
+            kcal: *kcal,
+            carb: *carb,
+            fat: *fat,
+            protein: *protein,
+            portion_g: lookup(PORTIONS, name),
+            package_g: lookup(PACKAGES, name),
+            min_g: lookup(MIN_G, name),
+            max_g: lookup(MAX_G, name),
+            group: group(name),
+            ..Default::default()
+        };
+        write_toml(&pantry.join(file_name(name)), &ingredient)?;
+    }
+    let recipe_files = [
+        recipe(
+            "protein pancake",
+            &[("oats", 50), ("egg", 120), ("whey protein", 30)],
+            Some(200),
+        ),
+        recipe(
+            "overnight oats",
+            &[
+                ("oats", 60),
+                ("greek yogurt", 150),
+                ("milk", 100),
+                ("blueberries", 80),
+            ],
+            Some(390),
+        ),
+        recipe(
+            "chili",
+            &[
+                ("lean ground beef", 500),
+                ("kidney beans", 480),
+                ("canned tomatoes", 800),
+                ("bell pepper", 300),
+            ],
+            None,
+        ),
+    ];
+    for recipe in &recipe_files {
+        write_tables(&recipes.join(file_name(&recipe.name)), recipe)?;
+    }
+
+    let mut cut = target(2000, 35, 25, 40);
+    cut.constraint_at_most = Some(vec![TargetConstraint {
+        name: "olive oil".to_string(),
+        g: 15,
+    }]);
+    cut.max_single_ingredient_kcal_share = Some(0.35);
+    let mut bulk = target(3000, 45, 20, 35);
+    bulk.constraint_at_least = Some(vec![TargetConstraint {
+        name: "oats".to_string(),
+        g: 100,
+    }]);
+    let target_files = [
+        ("cut", cut),
+        ("bulk", bulk),
+        ("training-day", target(2500, 45, 20, 35)),
+        ("rest-day", target(2100, 30, 30, 40)),
+    ];
+    for (name, target) in &target_files {
+        write_tables(&targets.join(format!("{}.toml", name)), target)?;
+    }
+    let flavorings = Flavorings {
+        flavoring: FLAVORINGS
+            .iter()
+            .map(|(name, g)| Flavoring {
+                name: name.to_string(),
+                g: *g,
+            })
+            .collect(),
+    };
+    write_tables(&dir.join("flavorings.toml"), &flavorings)?;
+    print_commands(dir);
+    Ok(())
+}
+
+fn print_commands(dir: &Path) {
+    let path = |file: &str| -> PathBuf { dir.join(file) };
+    let pantry = path("pantry");
+    let target = |name: &str| path(&format!("targets/{}.toml", name));
+    let week: Vec<String> = ["training-day"; 4]
+        .iter()
+        .chain(&["rest-day"; 3])
+        .map(|name| format!("-t {}", target(name).display()))
+        .collect();
+    println!(
+        "Wrote an example pantry, recipes, targets and flavorings to {}",
+        dir.display()
+    );
+    println!("Commands to try:");
+    let commands = [
+        format!("validate -d {}", pantry.display()),
+        format!("ingredients effective -d {}", pantry.display()),
+        format!(
+            "optimize -t {} -d {}",
+            target("cut").display(),
+            pantry.display()
+        ),
+        format!(
+            "optimize -t {} -d {} --explain --exclude \"dark chocolate\"",
+            target("bulk").display(),
+            pantry.display()
+        ),
+        format!(
+            "optimize -t {} -d {} --only \"chili,rice,olive oil\" --export-meal {}",
+            target("training-day").display(),
+            pantry.display(),
+            path("meal.toml").display()
+        ),
+        format!(
+            "portion --meal {} --containers 3",
+            path("meal.toml").display()
+        ),
+        format!(
+            "shopping-list -d {} {} --flavorings {}",
+            pantry.display(),
+            week.join(" "),
+            path("flavorings.toml").display()
+        ),
+    ];
+    for command in &commands {
+        println!("\tnatural-bodybuilder {}", command);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_foods() {
+        // the declared kcal of every food match its macros
+        for (name, kcal, carb, fat, protein) in FOODS {
+            let ingredient = Ingredient {
+                name: name.to_string(),
+                g: 100,
+                kcal: *kcal,
+                carb: *carb,
+                fat: *fat,
+                protein: *protein,
+                ..Default::default()
+            };
+            assert_eq!(ingredient.kcal_mismatch(crate::KCAL_MISMATCH_PCT), None);
+        }
+        for (name, ..) in FOODS {
+            assert!(group(name).is_some(), "{}", name);
+        }
+        for table in [PORTIONS, PACKAGES, MIN_G, MAX_G] {
+            for (name, _) in table {
+                assert!(FOODS.iter().any(|food| food.0 == *name), "{}", name);
+            }
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! Records the steps of the greedy search to explain how a result came about.
+use std::fmt;
+use std::io::Write;
+
+/// Why a candidate couldn't be added in a step
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlockReason {
+    // its portion or minimum amount is more than the pieces that are left
+    Remaining,
+    AtMost,
+    MaxWeightShare,
+    Sodium,
+    GlycemicLoad,
+    Water,
+    Caffeine,
+    PrepTime,
+}
+
+impl fmt::Display for BlockReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BlockReason::Remaining => write!(f, "doesn't fit in the rest"),
+            BlockReason::AtMost => write!(f, "at most"),
+            BlockReason::MaxWeightShare => write!(f, "max weight share"),
+            BlockReason::Sodium => write!(f, "sodium budget"),
+            BlockReason::GlycemicLoad => write!(f, "glycemic load"),
+            BlockReason::Water => write!(f, "water limit"),
+            BlockReason::Caffeine => write!(f, "caffeine limit"),
+            BlockReason::PrepTime => write!(f, "prep time"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    pub ingredient: String,
+    pub pieces: u64,
+    // None if nothing was in the mix before
+    pub cost_before: Option<f64>,
+    pub cost_after: f64,
+    pub blocked: Vec<(String, BlockReason)>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Explanation {
+    pub steps: Vec<Step>,
+}
+
+impl Explanation {
+    /// Writes one line per step with the added kcal
+    pub fn write<W: Write>(&self, mut writer: W, kcal_per_piece: f64) -> std::io::Result<()> {
+        writeln!(writer, "---- SEARCH ----")?;
+        for (i, step) in self.steps.iter().enumerate() {
+            let cost_before = match step.cost_before {
+                Some(cost) => format!("{:.6}", cost),
+                None => "-".to_string(),
+            };
+            write!(
+                writer,
+                "step {}: add {:.1} kcal of {} (cost {} -> {:.6})",
+                i + 1,
+                step.pieces as f64 * kcal_per_piece,
+                step.ingredient,
+                cost_before,
+                step.cost_after
+            )?;
+            if !step.blocked.is_empty() {
+                let blocked: Vec<String> = step
+                    .blocked
+                    .iter()
+                    .map(|(name, reason)| format!("{} ({})", name, reason))
+                    .collect();
+                write!(writer, ", blocked: {}", blocked.join(", "))?;
+            }
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write() {
+        let explanation = Explanation {
+            steps: vec![
+                Step {
+                    ingredient: "oats".to_string(),
+                    pieces: 2,
+                    cost_before: None,
+                    cost_after: 0.25,
+                    blocked: Vec::new(),
+                },
+                Step {
+                    ingredient: "quark".to_string(),
+                    pieces: 1,
+                    cost_before: Some(0.25),
+                    cost_after: 0.125,
+                    blocked: vec![("seeds".to_string(), BlockReason::AtMost)],
+                },
+            ],
+        };
+        let mut out = Vec::new();
+        explanation.write(&mut out, 0.5).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "---- SEARCH ----\n\
+             step 1: add 1.0 kcal of oats (cost - -> 0.250000)\n\
+             step 2: add 0.5 kcal of quark (cost 0.250000 -> 0.125000), blocked: seeds (at most)\n"
+        );
+    }
+}
@@ -0,0 +1,107 @@
+//! Writes a plan in the CSV import formats of food trackers, so it can be logged without
+//! retyping it.
+use crate::cli::Tracker;
+use crate::output::IngredientAmount;
+use std::io::Write;
+
+impl Tracker {
+    /// Header of the tracker's import format
+    fn columns(self) -> [&'static str; 8] {
+        match self {
+            Tracker::MyFitnessPal => [
+                "Date",
+                "Meal",
+                "Food Name",
+                "Amount (g)",
+                "Calories",
+                "Carbohydrates (g)",
+                "Fat (g)",
+                "Protein (g)",
+            ],
+            Tracker::Cronometer => [
+                "Day",
+                "Group",
+                "Food Name",
+                "Amount",
+                "Energy (kcal)",
+                "Carbs (g)",
+                "Fat (g)",
+                "Protein (g)",
+            ],
+        }
+    }
+
+    /// Meal the foods are logged to if none is given
+    pub fn default_meal(self) -> &'static str {
+        match self {
+            Tracker::MyFitnessPal => "Snacks",
+            Tracker::Cronometer => "Uncategorized",
+        }
+    }
+
+    fn amount(self, g: u64) -> String {
+        match self {
+            Tracker::MyFitnessPal => g.to_string(),
+            Tracker::Cronometer => format!("{} g", g),
+        }
+    }
+}
+
+/// Writes one row per ingredient, all logged on `date` to `meal`
+pub fn write_csv<W: Write>(
+    ingredients: &[IngredientAmount],
+    tracker: Tracker,
+    date: &str,
+    meal: &str,
+    writer: W,
+) -> csv::Result<()> {
+    let mut csv_writer = csv::Writer::from_writer(writer);
+    csv_writer.write_record(tracker.columns())?;
+    for ingredient in ingredients {
+        csv_writer.write_record([
+            date.to_string(),
+            meal.to_string(),
+            ingredient.name.clone(),
+            tracker.amount(ingredient.g),
+            ingredient.kcal.to_string(),
+            ingredient.carb.to_string(),
+            ingredient.fat.to_string(),
+            ingredient.protein.to_string(),
+        ])?;
+    }
+    csv_writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_csv() {
+        let oats = IngredientAmount {
+            name: "oats".to_string(),
+            g: 80,
+            kcal: 304.0,
+            carb: 48.0,
+            fat: 5.6,
+            protein: 10.4,
+            alcohol: 0.0,
+            polyols: 0.0,
+        };
+        let mut csv = Vec::new();
+        write_csv(
+            &[oats],
+            Tracker::Cronometer,
+            "2024-05-01",
+            "Breakfast",
+            &mut csv,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "Day,Group,Food Name,Amount,Energy (kcal),Carbs (g),Fat (g),Protein (g)\n\
+             2024-05-01,Breakfast,oats,80 g,304,48,5.6,10.4\n"
+        );
+    }
+}
@@ -0,0 +1,317 @@
+//! Checks before the search whether the constraints can be met at all, and names the ones that
+//! can't.
+use crate::error::Error;
+use crate::{Ingredients, NormalizedIngredient, NormalizedTarget, TargetConstraints};
+use std::collections::BTreeMap;
+
+/// Share of the macros by which the target may be out of reach before it's reported
+const UNREACHABLE_TOLERANCE: f64 = 0.01;
+
+type MacroAmount = fn(&NormalizedIngredient) -> f64;
+
+// fewest and most pieces an ingredient can have in the mix
+type Bounds<'a> = BTreeMap<&'a String, (u64, u64)>;
+
+fn bounds<'a>(
+    constraints: &TargetConstraints,
+    ingredients: &'a Ingredients,
+    steps: usize,
+) -> Bounds<'a> {
+    ingredients
+        .0
+        .keys()
+        .map(|name| match constraints.exact.0.get(name) {
+            Some(pieces) => (name, (*pieces, *pieces)),
+            None => {
+                let get = |proposal: &crate::Proposal| proposal.0.get(name).copied();
+                let at_least = get(&constraints.at_least).unwrap_or(0);
+                let at_most = get(&constraints.at_most).unwrap_or(steps as u64);
+                (name, (at_least, at_most))
+            }
+        })
+        .collect()
+}
+
+/// e.g. "oats (40), whey protein (30)", largest first
+fn list<'a>(pieces: impl Iterator<Item = (&'a String, u64)>) -> String {
+    let mut pieces: Vec<_> = pieces.filter(|(_, pieces)| *pieces > 0).collect();
+    pieces.sort_by_key(|(_, pieces)| std::cmp::Reverse(*pieces));
+    let pieces: Vec<String> = pieces
+        .iter()
+        .map(|(name, pieces)| format!("{} ({})", name, pieces))
+        .collect();
+    pieces.join(", ")
+}
+
+/// Fails if no mix of `steps` pieces, or as many as the kcal band allows, can meet the exact,
+/// at least and at most constraints
+pub fn check(
+    constraints: &TargetConstraints,
+    ingredients: &Ingredients,
+    steps: usize,
+) -> Result<(), Error> {
+    let (min_steps, max_steps) = constraints.step_band(steps);
+    let bounds = bounds(constraints, ingredients, max_steps);
+    for (name, (at_least, at_most)) in &bounds {
+        if at_least > at_most {
+            return Err(Error::ConflictingConstraints {
+                name: name.to_string(),
+                at_least: *at_least,
+                at_most: *at_most,
+            });
+        }
+    }
+    let assigned: u64 = bounds.values().map(|(at_least, _)| at_least).sum();
+    if assigned > max_steps as u64 {
+        return Err(Error::ConstraintsExceedTarget {
+            assigned,
+            steps: max_steps,
+            constraints: list(
+                bounds
+                    .iter()
+                    .map(|(name, (at_least, _))| (*name, *at_least)),
+            ),
+        });
+    }
+    let available: u64 = bounds.values().map(|(_, at_most)| at_most).sum();
+    if available < min_steps as u64 {
+        return Err(Error::AtMostBelowTarget {
+            available,
+            steps: min_steps,
+            constraints: list(bounds.iter().map(|(name, (_, at_most))| (*name, *at_most))),
+        });
+    }
+    Ok(())
+}
+
+/// Macro grams per kcal, alcohol counts like in `NormalizedTarget::evaluate`
+fn macros(ingredient: &NormalizedIngredient) -> f64 {
+    ingredient.carb + ingredient.fat + ingredient.protein + ingredient.alcohol
+}
+
+/// The mix within the bounds with the largest sum of `coefficient` over its pieces
+fn maximize<'a>(
+    bounds: &Bounds<'a>,
+    steps: usize,
+    coefficient: impl Fn(&String) -> f64,
+) -> (f64, BTreeMap<&'a String, u64>) {
+    let mut mix: BTreeMap<&String, u64> = bounds
+        .iter()
+        .map(|(name, (at_least, _))| (*name, *at_least))
+        .collect();
+    let mut rest = steps as u64 - mix.values().sum::<u64>();
+    let mut names: Vec<&String> = bounds.keys().copied().collect();
+    names.sort_by(|a, b| coefficient(b).total_cmp(&coefficient(a)));
+    for name in names {
+        let added = (bounds[name].1 - mix[name]).min(rest);
+        *mix.get_mut(name).unwrap() += added;
+        rest -= added;
+    }
+    let value = mix
+        .iter()
+        .map(|(name, pieces)| *pieces as f64 * coefficient(name))
+        .sum();
+    (value, mix)
+}
+
+/// Largest share of the macros that `macro_amount` can have in a mix within the bounds
+///
+/// A share is reachable if some mix has at least as much of the macro as the share of all
+/// macros, which is a linear problem for a fixed share, so the largest one is found by bisection.
+fn max_share<'a>(
+    bounds: &Bounds<'a>,
+    ingredients: &Ingredients,
+    steps: usize,
+    macro_amount: &impl Fn(&NormalizedIngredient) -> f64,
+) -> (f64, BTreeMap<&'a String, u64>) {
+    let excess = |share: f64| {
+        maximize(bounds, steps, |name| {
+            let ingredient = &ingredients.0[name];
+            macro_amount(ingredient) - share * macros(ingredient)
+        })
+    };
+    let (mut low, mut high) = (0.0, 1.0);
+    for _ in 0..50 {
+        let share = (low + high) / 2.0;
+        if excess(share).0 >= 0.0 {
+            low = share;
+        } else {
+            high = share;
+        }
+    }
+    (low, excess(low).1)
+}
+
+/// Describes the macros of the target that no mix within the constraints can reach, and the
+/// constraints that limit them
+pub fn unreachable(
+    target: &NormalizedTarget,
+    constraints: &TargetConstraints,
+    ingredients: &Ingredients,
+    steps: usize,
+) -> Vec<String> {
+    if ingredients.0.is_empty() || check(constraints, ingredients, steps).is_err() {
+        return Vec::new();
+    }
+    let bounds = bounds(constraints, ingredients, steps);
+    let targets: [(&str, f64, MacroAmount); 3] = [
+        ("carb", target.carb, |ingredient| ingredient.carb),
+        ("fat", target.fat, |ingredient| ingredient.fat),
+        ("protein", target.protein, |ingredient| ingredient.protein),
+    ];
+    let mut unreachable = Vec::new();
+    for (name, wanted, amount) in targets.iter() {
+        let (most, mix) = max_share(&bounds, ingredients, steps, amount);
+        let (least, least_mix) = max_share(&bounds, ingredients, steps, &|ingredient| {
+            macros(ingredient) - amount(ingredient)
+        });
+        let least = 1.0 - least;
+        let (limit, share, mix) = if *wanted > most + UNREACHABLE_TOLERANCE {
+            ("at most", most, mix)
+        } else if *wanted < least - UNREACHABLE_TOLERANCE {
+            ("at least", least, least_mix)
+        } else {
+            continue;
+        };
+        // the constraints that hold back ingredients with more or less of the macro than wanted
+        let richer = |ingredient: &NormalizedIngredient| {
+            let richer = amount(ingredient) > share * macros(ingredient);
+            if limit == "at most" {
+                richer
+            } else {
+                !richer
+            }
+        };
+        let mut capped = Vec::new();
+        let mut forced = Vec::new();
+        for (ingredient, pieces) in &mix {
+            let (at_least, at_most) = bounds[ingredient];
+            if richer(&ingredients.0[*ingredient]) {
+                if *pieces == at_most && at_most < steps as u64 {
+                    capped.push(ingredient.to_string());
+                }
+            } else if at_least > 0 && *pieces == at_least {
+                forced.push(ingredient.to_string());
+            }
+        }
+        let mut reasons = Vec::new();
+        if !capped.is_empty() {
+            reasons.push(format!("at most constraints on {}", capped.join(", ")));
+        }
+        if !forced.is_empty() {
+            reasons.push(format!(
+                "exact and at least constraints on {}",
+                forced.join(", ")
+            ));
+        }
+        if reasons.is_empty() {
+            reasons.push("the loaded ingredients".to_string());
+        }
+        unreachable.push(format!(
+            "{} can be {} {:.0}% of the macros but the target wants {:.0}%, because of {}",
+            name,
+            limit,
+            share * 100.0,
+            wanted * 100.0,
+            reasons.join(" and ")
+        ));
+    }
+    unreachable
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Proposal;
+
+    fn ingredient(carb: f64, fat: f64, protein: f64) -> NormalizedIngredient {
+        NormalizedIngredient {
+            carb,
+            fat,
+            protein,
+            quality_protein: 0.0,
+            leucine_g: 0.0,
+            eaa_g: 0.0,
+            omega3_g: 0.0,
+            omega6_g: 0.0,
+            alcohol: 0.0,
+            sat_fat: 0.0,
+            sugar: 0.0,
+            sodium_mg: 0.0,
+            water_ml: 0.0,
+            caffeine_mg: 0.0,
+            glycemic_load: 0.0,
+            g: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_check() {
+        let mut ingredients = Ingredients(BTreeMap::new());
+        ingredients
+            .0
+            .insert("oats".to_string(), ingredient(0.6, 0.1, 0.3));
+        ingredients
+            .0
+            .insert("whey".to_string(), ingredient(0.1, 0.0, 0.9));
+        let constraints = |exact: &[(&str, u64)], at_most: &[(&str, u64)]| {
+            let proposal = |pieces: &[(&str, u64)]| {
+                Proposal(
+                    pieces
+                        .iter()
+                        .map(|(name, pieces)| (name.to_string(), *pieces))
+                        .collect(),
+                )
+            };
+            let mut constraints = TargetConstraints::new();
+            constraints.exact = proposal(exact);
+            constraints.at_most = proposal(at_most);
+            constraints
+        };
+        let target = NormalizedTarget {
+            carb: 0.3,
+            fat: 0.05,
+            protein: 0.65,
+            ..Default::default()
+        };
+
+        let none = constraints(&[], &[]);
+        assert!(check(&none, &ingredients, 10).is_ok());
+        assert!(unreachable(&target, &none, &ingredients, 10).is_empty());
+
+        let err = check(&constraints(&[("oats", 12)], &[]), &ingredients, 10).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "exact and at least constraints on oats (12) need 12 kcal pieces but the target only has 10"
+        );
+        let err = check(
+            &constraints(&[], &[("oats", 3), ("whey", 4)]),
+            &ingredients,
+            10,
+        );
+        assert!(matches!(
+            err,
+            Err(Error::AtMostBelowTarget { available: 7, .. })
+        ));
+
+        // with at most 2 of 10 pieces of whey, the rest is oats
+        let capped = constraints(&[], &[("whey", 2)]);
+        assert_eq!(
+            unreachable(&target, &capped, &ingredients, 10),
+            vec![
+                "carb can be at least 50% of the macros but the target wants 30%, \
+                 because of at most constraints on whey",
+                "fat can be at least 8% of the macros but the target wants 5%, \
+                 because of at most constraints on whey",
+                "protein can be at most 42% of the macros but the target wants 65%, \
+                 because of at most constraints on whey"
+            ]
+        );
+        let forced = constraints(&[("oats", 8)], &[]);
+        assert!(unreachable(&target, &forced, &ingredients, 10).contains(
+            &"protein can be at most 42% of the macros but the target wants 65%, \
+              because of exact and at least constraints on oats"
+                .to_string()
+        ));
+    }
+}
@@ -0,0 +1,78 @@
+//! Calorie-free flavorings (spices, herbs, vinegar, mustard, sweeteners) that are used to taste
+//! and stay out of the optimizer and the macros.
+use crate::Proposal;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Flavoring {
+    pub name: String,
+    // amount per plan on the shopping list, in g
+    pub g: u64,
+}
+
+/// The whitelist as read from a flavorings file
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Flavorings {
+    #[serde(default)]
+    pub flavoring: Vec<Flavoring>,
+}
+
+impl Flavorings {
+    pub fn contains(&self, name: &str) -> bool {
+        self.flavoring
+            .iter()
+            .any(|flavoring| flavoring.name == name)
+    }
+
+    /// Adds the default amount of every flavoring the plan doesn't already have, e.g. a meal
+    /// that lists how much vinegar it takes
+    pub fn add_to_plan(&self, gram_proposal: &mut Proposal) {
+        for flavoring in &self.flavoring {
+            gram_proposal
+                .0
+                .entry(flavoring.name.clone())
+                .or_insert(flavoring.g);
+        }
+    }
+
+    pub fn print(&self) {
+        if self.flavoring.is_empty() {
+            return;
+        }
+        let names: Vec<&str> = self
+            .flavoring
+            .iter()
+            .map(|flavoring| flavoring.name.as_str())
+            .collect();
+        println!("Season to taste with {}.", names.join(", "));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_add_to_plan() {
+        let flavorings: Flavorings = toml::from_str(
+            "[[flavoring]]\nname = \"cinnamon\"\ng = 5\n\n\
+             [[flavoring]]\nname = \"vinegar\"\ng = 30\n",
+        )
+        .unwrap();
+        assert!(flavorings.contains("vinegar"));
+        assert!(!flavorings.contains("oats"));
+
+        let mut gram_proposal = Proposal(BTreeMap::new());
+        gram_proposal.0.insert("oats".to_string(), 100);
+        gram_proposal.0.insert("vinegar".to_string(), 10);
+        flavorings.add_to_plan(&mut gram_proposal);
+        let expected: BTreeMap<String, u64> = IntoIterator::into_iter([
+            ("cinnamon".to_string(), 5),
+            ("oats".to_string(), 100),
+            ("vinegar".to_string(), 10),
+        ])
+        .collect();
+        assert_eq!(gram_proposal.0, expected);
+    }
+}
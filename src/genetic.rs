@@ -0,0 +1,339 @@
+//! Genetic search: a population of proposals that is improved by crossing and mutating its
+//! best members, which also finds good plans with fewer ingredients along the way.
+use crate::error::Error;
+use crate::exact::{exceeds_limits, Domain};
+use crate::{
+    feasibility, optimize, Ingredients, MixTotals, NormalizedTarget, Proposal, TargetConstraints,
+};
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Proposals in each generation
+const POPULATION: usize = 60;
+const GENERATIONS: usize = 300;
+/// Best proposals that are kept unchanged in the next generation
+const ELITE: usize = 4;
+/// Proposals drawn for each parent, the best of which is the parent
+const TOURNAMENT: usize = 3;
+/// Chance that a child moves pieces from one ingredient to another
+const MUTATION_RATE: f64 = 0.4;
+
+/// A plan the search found, in pieces, and its cost
+pub struct Member {
+    pub proposal: Proposal,
+    pub cost: f64,
+}
+
+/// Plans with different numbers of ingredients, written with `--population`
+#[derive(Serialize)]
+pub struct Population {
+    pub plan: Vec<Plan>,
+}
+
+#[derive(Serialize)]
+pub struct Plan {
+    pub ingredients: usize,
+    pub cost: f64,
+    pub grams: Proposal,
+}
+
+type Pieces = Vec<u64>;
+
+struct Genetic<'a> {
+    target: &'a NormalizedTarget,
+    constraints: &'a TargetConstraints,
+    ingredients: &'a Ingredients,
+    names: Vec<&'a String>,
+    domains: Vec<Domain>,
+    // fewest and most pieces of the result
+    band: (u64, u64),
+    // lowest cost for each number of used ingredients
+    archive: BTreeMap<usize, (Pieces, f64)>,
+}
+
+impl Genetic<'_> {
+    fn proposal(&self, pieces: &[u64]) -> Proposal {
+        Proposal(
+            self.names
+                .iter()
+                .map(|name| name.to_string())
+                .zip(pieces.iter().copied())
+                .collect(),
+        )
+    }
+
+    /// Cost of the proposal, infinite if it breaks a limit
+    fn cost(&self, pieces: &[u64]) -> f64 {
+        let total: u64 = pieces.iter().sum();
+        if total == 0 || total < self.band.0 || total > self.band.1 {
+            return f64::INFINITY;
+        }
+        let proposal = self.proposal(pieces);
+        let totals = MixTotals::new(&proposal, self.ingredients);
+        let exceeds_weight_share = self.names.iter().zip(pieces).any(|(name, pieces)| {
+            self.constraints
+                .max_weight_share
+                .get(*name)
+                .is_some_and(|max_share| {
+                    *pieces as f64 * self.ingredients.0[*name].g > max_share * totals.g
+                })
+        });
+        let used = self
+            .names
+            .iter()
+            .zip(pieces)
+            .filter(|(_, pieces)| **pieces > 0);
+        if exceeds_weight_share
+            || exceeds_limits(self.constraints, &totals)
+            || self
+                .constraints
+                .exceeds_prep_time(used.map(|(name, _)| *name))
+        {
+            return f64::INFINITY;
+        }
+        self.target.evaluate(&proposal, self.ingredients) + self.constraints.evaluate(&proposal)
+    }
+
+    /// Moves every amount to the closest one its ingredient allows, then scales and nudges the
+    /// amounts until the total is in the band
+    fn repair(&self, pieces: &mut [u64], rng: &mut Pcg64) {
+        let snap = |domain: &Domain, pieces: u64| domain.values[domain.nearest(pieces)];
+        for (pieces, domain) in pieces.iter_mut().zip(&self.domains) {
+            *pieces = snap(domain, *pieces);
+        }
+        let total: u64 = pieces.iter().sum();
+        let wanted = total.clamp(self.band.0, self.band.1);
+        if total > 0 && total != wanted {
+            let scale = wanted as f64 / total as f64;
+            for (pieces, domain) in pieces.iter_mut().zip(&self.domains) {
+                *pieces = snap(domain, (*pieces as f64 * scale).round() as u64);
+            }
+        }
+        for _ in 0..self.band.1 {
+            let total: u64 = pieces.iter().sum();
+            let moves: Vec<(usize, u64)> = if total < self.band.0 {
+                (0..pieces.len())
+                    .filter_map(|i| {
+                        let values = &self.domains[i].values;
+                        let next = *values.get(values.binary_search(&pieces[i]).ok()? + 1)?;
+                        Some((i, next)).filter(|_| total - pieces[i] + next <= self.band.1)
+                    })
+                    .collect()
+            } else if total > self.band.1 {
+                (0..pieces.len())
+                    .filter_map(|i| {
+                        let values = &self.domains[i].values;
+                        let index = values.binary_search(&pieces[i]).ok()?;
+                        let previous = values[index.checked_sub(1)?];
+                        Some((i, previous)).filter(|_| total - pieces[i] + previous >= self.band.0)
+                    })
+                    .collect()
+            } else {
+                return;
+            };
+            match moves.choose(rng) {
+                Some((i, value)) => pieces[*i] = *value,
+                None => return,
+            }
+        }
+    }
+
+    fn random(&self, rng: &mut Pcg64) -> Pieces {
+        let mut order: Vec<usize> = (0..self.names.len()).collect();
+        order.shuffle(rng);
+        let used = rng.gen_range(1..=order.len());
+        let mut weights = vec![0.0; order.len()];
+        for i in &order[..used] {
+            weights[*i] = rng.gen::<f64>();
+        }
+        let sum: f64 = weights.iter().sum();
+        let mut pieces = weights
+            .iter()
+            .map(|weight| (weight / sum * self.band.1 as f64).round() as u64)
+            .collect::<Pieces>();
+        self.repair(&mut pieces, rng);
+        pieces
+    }
+
+    /// Best of `TOURNAMENT` members drawn at random
+    fn select<'p>(population: &'p [(Pieces, f64)], rng: &mut Pcg64) -> &'p Pieces {
+        let drawn = (0..TOURNAMENT).map(|_| &population[rng.gen_range(0..population.len())]);
+        &drawn.min_by(|a, b| a.1.total_cmp(&b.1)).unwrap().0
+    }
+
+    /// Each amount is either parent's or their mean
+    fn crossover(a: &[u64], b: &[u64], rng: &mut Pcg64) -> Pieces {
+        a.iter()
+            .zip(b)
+            .map(|(a, b)| match rng.gen_range(0..3) {
+                0 => *a,
+                1 => *b,
+                _ => (a + b) / 2,
+            })
+            .collect()
+    }
+
+    /// Moves a few, some or all pieces of a used ingredient to another one, half of the time
+    /// to one that is used too
+    fn mutate(pieces: &mut [u64], rng: &mut Pcg64) {
+        let used: Vec<usize> = (0..pieces.len()).filter(|i| pieces[*i] > 0).collect();
+        if let Some(from) = used.choose(rng).copied() {
+            let to = match used.choose(rng) {
+                Some(to) if rng.gen_bool(0.5) => *to,
+                _ => rng.gen_range(0..pieces.len()),
+            };
+            let most = match rng.gen_range(0..3) {
+                0 => (pieces[from] / 10).max(1),
+                _ => pieces[from],
+            };
+            let moved = rng.gen_range(1..=most);
+            pieces[from] -= moved;
+            pieces[to] += moved;
+        }
+    }
+
+    fn record(&mut self, pieces: &[u64], cost: f64) {
+        if !cost.is_finite() {
+            return;
+        }
+        let used = pieces.iter().filter(|pieces| **pieces > 0).count();
+        if self.archive.get(&used).is_none_or(|(_, best)| cost < *best) {
+            self.archive.insert(used, (pieces.to_vec(), cost));
+        }
+    }
+}
+
+/// Evolves a population of proposals that starts from the greedy search's result and random
+/// mixes, and returns the best proposal with the plans that are better than any plan with fewer
+/// ingredients, by number of ingredients
+pub fn optimize_genetic(
+    target: &NormalizedTarget,
+    constraints: TargetConstraints,
+    ingredients: &Ingredients,
+    steps: usize,
+    seed: u64,
+) -> Result<(Proposal, Vec<Member>), Error> {
+    feasibility::check(&constraints, ingredients, steps)?;
+    let (min_steps, max_steps) = constraints.step_band(steps);
+    let names: Vec<&String> = ingredients.0.keys().collect();
+    let mut genetic = Genetic {
+        target,
+        constraints: &constraints,
+        ingredients,
+        domains: names
+            .iter()
+            .map(|name| Domain::new(name, &constraints, max_steps as u64))
+            .collect(),
+        names,
+        band: (min_steps as u64, max_steps as u64),
+        archive: BTreeMap::new(),
+    };
+    let mut rng = Pcg64::seed_from_u64(seed);
+    let mut population: Vec<(Pieces, f64)> = Vec::new();
+    if let Ok(greedy) = optimize(target, constraints.clone(), ingredients, steps) {
+        let pieces: Pieces = genetic.names.iter().map(|name| greedy.0[*name]).collect();
+        population.push((pieces.clone(), genetic.cost(&pieces)));
+    }
+    while population.len() < POPULATION {
+        let pieces = genetic.random(&mut rng);
+        population.push((pieces.clone(), genetic.cost(&pieces)));
+    }
+    for (pieces, cost) in &population {
+        genetic.record(pieces, *cost);
+    }
+    for _ in 0..GENERATIONS {
+        population.sort_by(|a, b| a.1.total_cmp(&b.1));
+        let mut next = population[..ELITE].to_vec();
+        while next.len() < POPULATION {
+            let a = Genetic::select(&population, &mut rng);
+            let b = Genetic::select(&population, &mut rng);
+            let mut child = Genetic::crossover(a, b, &mut rng);
+            if rng.gen_bool(MUTATION_RATE) {
+                Genetic::mutate(&mut child, &mut rng);
+            }
+            genetic.repair(&mut child, &mut rng);
+            let cost = genetic.cost(&child);
+            genetic.record(&child, cost);
+            next.push((child, cost));
+        }
+        population = next;
+        // the best plan of each size is improved too, not only the population's
+        let archived: Vec<Pieces> = genetic.archive.values().map(|(p, _)| p.clone()).collect();
+        for mut pieces in archived {
+            Genetic::mutate(&mut pieces, &mut rng);
+            genetic.repair(&mut pieces, &mut rng);
+            let cost = genetic.cost(&pieces);
+            genetic.record(&pieces, cost);
+        }
+    }
+
+    let mut front = Vec::new();
+    let mut best_cost = f64::INFINITY;
+    for (pieces, cost) in genetic.archive.values() {
+        if *cost < best_cost {
+            best_cost = *cost;
+            front.push(Member {
+                proposal: genetic.proposal(pieces),
+                cost: *cost,
+            });
+        }
+    }
+    match front.last() {
+        Some(best) => Ok((best.proposal.clone(), front)),
+        None => Err(Error::Infeasible { assigned: 0, steps }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Ingredient;
+
+    #[test]
+    fn test_optimize_genetic() {
+        let t = NormalizedTarget {
+            carb: 0.4,
+            fat: 0.25,
+            protein: 0.35,
+            ..Default::default()
+        };
+        let ingredient = |carb, fat, protein| {
+            let mut ingredient = Ingredient {
+                g: 100,
+                carb,
+                fat,
+                protein,
+                ..Default::default()
+            };
+            ingredient.kcal = ingredient.macro_kcal();
+            ingredient.normalize()
+        };
+        let mut ingredients = Ingredients(Default::default());
+        for (name, carb, fat, protein) in [
+            ("apple", 20.0, 1.0, 1.0),
+            ("cheese", 1.0, 30.0, 25.0),
+            ("chicken", 0.0, 3.0, 30.0),
+            ("rice", 28.0, 0.5, 3.0),
+        ] {
+            ingredients
+                .0
+                .insert(name.to_string(), ingredient(carb, fat, protein));
+        }
+        let (best, front) =
+            optimize_genetic(&t, TargetConstraints::new(), &ingredients, 50, 0).unwrap();
+        assert_eq!(best.kcal(), 50);
+        let greedy = optimize(&t, TargetConstraints::new(), &ingredients, 50).unwrap();
+        assert!(t.evaluate(&best, &ingredients) <= t.evaluate(&greedy, &ingredients));
+        // each plan of the front uses more ingredients and costs less than the one before
+        for pair in front.windows(2) {
+            let used = |member: &Member| member.proposal.0.values().filter(|p| **p > 0).count();
+            assert!(used(&pair[0]) < used(&pair[1]));
+            assert!(pair[0].cost > pair[1].cost);
+        }
+        assert!(front.len() > 1);
+        assert_eq!(front.last().unwrap().proposal, best);
+    }
+}
@@ -0,0 +1,241 @@
+//! History of the plans that were eaten, one JSON object per line, and weekly statistics of it.
+use crate::error::Error;
+use crate::stock::Date;
+use crate::{read_file, Proposal};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+/// Largest difference from the target's kcal of a day on target, in percent
+const ADHERENCE_KCAL_PCT: f64 = 5.0;
+/// Largest difference from the target's share of carb, fat and protein of a day on target, in
+/// percentage points
+const ADHERENCE_RATIO_POINTS: f64 = 3.0;
+
+/// A day of the history
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Day {
+    pub date: Date,
+    // ingredients eaten in g
+    pub grams: Proposal,
+    pub kcal: f64,
+    // in g
+    pub carb: f64,
+    // in g
+    pub fat: f64,
+    // in g
+    pub protein: f64,
+    // body weight on the day
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weight_kg: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<DayTarget>,
+}
+
+/// kcal and ratio of the day's target, the ratio in percent of the macro grams
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DayTarget {
+    pub kcal: u64,
+    pub carb: f64,
+    pub fat: f64,
+    pub protein: f64,
+}
+
+impl Day {
+    /// Whether the day's kcal and ratio are close to its target's, None without a target
+    fn on_target(&self) -> Option<bool> {
+        let target = self.target.as_ref()?;
+        let sum = self.carb + self.fat + self.protein;
+        let close =
+            |g: f64, pct: f64| sum > 0.0 && (100.0 * g / sum - pct).abs() <= ADHERENCE_RATIO_POINTS;
+        let kcal = target.kcal as f64;
+        Some(
+            (self.kcal - kcal).abs() <= ADHERENCE_KCAL_PCT / 100.0 * kcal
+                && close(self.carb, target.carb)
+                && close(self.fat, target.fat)
+                && close(self.protein, target.protein),
+        )
+    }
+}
+
+/// Appends the day to the history file as a line of JSON, creating the file if it doesn't exist
+pub fn append(path: &Path, day: &Day) -> Result<(), Error> {
+    let line = serde_json::to_string(day)?;
+    let write_error = |source| Error::Write {
+        path: path.to_path_buf(),
+        source,
+    };
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(write_error)?;
+    writeln!(file, "{}", line).map_err(write_error)
+}
+
+/// Days of the history file by date, of a day that was logged several times the last one
+pub fn read(path: &Path) -> Result<Vec<Day>, Error> {
+    let contents = read_file(path)?;
+    let mut days = BTreeMap::new();
+    for day in serde_json::Deserializer::from_str(&contents).into_iter::<Day>() {
+        let day = day.map_err(|source| Error::ParseJson {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        days.insert(day.date, day);
+    }
+    Ok(days.into_values().collect())
+}
+
+/// Averages of the logged days of a week
+#[derive(Debug, PartialEq)]
+pub struct Week {
+    pub monday: Date,
+    pub days: usize,
+    pub kcal: f64,
+    pub carb: f64,
+    pub fat: f64,
+    pub protein: f64,
+    // g of protein per kg of body weight, of the days with a weight
+    pub protein_per_kg: Option<f64>,
+    // days on target and days with a target
+    pub on_target: (usize, usize),
+}
+
+/// Groups the days into weeks from Monday, `weight_kg` is the body weight of the days that
+/// were logged without one
+pub fn weeks(days: &[Day], weight_kg: Option<f64>) -> Vec<Week> {
+    let mut by_week: BTreeMap<Date, Vec<&Day>> = BTreeMap::new();
+    for day in days {
+        by_week.entry(day.date.monday()).or_default().push(day);
+    }
+    by_week
+        .into_iter()
+        .map(|(monday, days)| {
+            let mean = |value: fn(&Day) -> f64| {
+                days.iter().map(|day| value(day)).sum::<f64>() / days.len() as f64
+            };
+            let per_kg: Vec<f64> = days
+                .iter()
+                .filter_map(|day| Some(day.protein / day.weight_kg.or(weight_kg)?))
+                .collect();
+            let checked: Vec<bool> = days.iter().filter_map(|day| day.on_target()).collect();
+            Week {
+                monday,
+                days: days.len(),
+                kcal: mean(|day| day.kcal),
+                carb: mean(|day| day.carb),
+                fat: mean(|day| day.fat),
+                protein: mean(|day| day.protein),
+                protein_per_kg: Some(per_kg.iter().sum::<f64>() / per_kg.len() as f64)
+                    .filter(|_| !per_kg.is_empty()),
+                on_target: (checked.iter().filter(|on| **on).count(), checked.len()),
+            }
+        })
+        .collect()
+}
+
+pub fn print_stats(weeks: &[Week]) {
+    println!("---- HISTORY ----");
+    println!(
+        "{:<10} {:>4} {:>6} {:>6} {:>6} {:>7} {:>5} {:>9}",
+        "week of", "days", "kcal", "carb", "fat", "protein", "g/kg", "on target"
+    );
+    for week in weeks {
+        let per_kg = week
+            .protein_per_kg
+            .map_or("-".to_string(), |g| format!("{:.2}", g));
+        let on_target = match week.on_target {
+            (_, 0) => "-".to_string(),
+            (on, checked) => format!("{}/{}", on, checked),
+        };
+        println!(
+            "{:<10} {:>4} {:>6.0} {:>6.1} {:>6.1} {:>7.1} {:>5} {:>9}",
+            String::from(week.monday),
+            week.days,
+            week.kcal,
+            week.carb,
+            week.fat,
+            week.protein,
+            per_kg,
+            on_target
+        );
+    }
+    let per_kg: Vec<(Date, f64)> = weeks
+        .iter()
+        .filter_map(|week| Some((week.monday, week.protein_per_kg?)))
+        .collect();
+    if let [(first_week, first), .., (last_week, last)] = per_kg[..] {
+        println!(
+            "Protein per kg changed by {:+.2} g/kg from {:.2} in the week of {} to {:.2} in the week of {}.",
+            last - first,
+            first,
+            String::from(first_week),
+            last,
+            String::from(last_week)
+        );
+    }
+    let (on, checked) = weeks.iter().fold((0, 0), |(on, checked), week| {
+        (on + week.on_target.0, checked + week.on_target.1)
+    });
+    if checked > 0 {
+        println!(
+            "{} of {} days with a target were within {}% of its kcal and {} points of its ratio ({:.0}%).",
+            on,
+            checked,
+            ADHERENCE_KCAL_PCT,
+            ADHERENCE_RATIO_POINTS,
+            100.0 * on as f64 / checked as f64
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn test_weeks() {
+        let day = |date: &str, kcal, protein, weight_kg| Day {
+            date: Date::try_from(date.to_string()).unwrap(),
+            grams: Proposal(Default::default()),
+            kcal,
+            carb: 200.0,
+            fat: 50.0,
+            protein,
+            weight_kg,
+            target: Some(DayTarget {
+                kcal: 2000,
+                carb: 50.0,
+                fat: 12.5,
+                protein: 37.5,
+            }),
+        };
+        let line = serde_json::to_string(&day("2024-05-01", 2000.0, 150.0, None)).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Day>(&line).unwrap(),
+            day("2024-05-01", 2000.0, 150.0, None)
+        );
+        // 2024-05-05 is a Sunday
+        let days = vec![
+            day("2024-04-29", 2000.0, 150.0, Some(75.0)),
+            day("2024-05-05", 2300.0, 150.0, None),
+            day("2024-05-06", 2050.0, 180.0, None),
+        ];
+        let weeks = weeks(&days, Some(80.0));
+        assert_eq!(weeks.len(), 2);
+        assert_eq!(String::from(weeks[0].monday), "2024-04-29");
+        assert_eq!(weeks[0].days, 2);
+        assert_eq!(weeks[0].kcal, 2150.0);
+        assert_eq!(weeks[0].protein_per_kg, Some((2.0 + 1.875) / 2.0));
+        // 2300 kcal is more than 5% over the target
+        assert_eq!(weeks[0].on_target, (1, 2));
+        assert_eq!(String::from(weeks[1].monday), "2024-05-06");
+        assert_eq!(weeks[1].protein_per_kg, Some(2.25));
+        // 180g of protein is 41.9% of the macro grams
+        assert_eq!(weeks[1].on_target, (0, 1));
+    }
+}
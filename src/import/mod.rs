@@ -0,0 +1,84 @@
+//! Converting entries of food databases into ingredient files.
+#[cfg(feature = "http")]
+pub mod off;
+pub mod table;
+pub mod usda;
+
+use crate::error::Error;
+use crate::{round_decigram, Ingredient};
+
+/// Nutrition of 100g of a food as found in a database
+#[derive(Debug, PartialEq)]
+pub struct FoodEntry {
+    pub name: String,
+    pub kcal: f64,
+    // in g
+    pub carb: f64,
+    // in g
+    pub fat: f64,
+    // in g
+    pub protein: f64,
+    pub brand: Option<String>,
+    // size of a serving in g
+    pub serving_g: Option<f64>,
+}
+
+impl FoodEntry {
+    pub fn to_ingredient(&self) -> Ingredient {
+        Ingredient {
+            name: self.name.clone(),
+            g: 100,
+            kcal: self.kcal.round(),
+            carb: round_decigram(self.carb),
+            fat: round_decigram(self.fat),
+            protein: round_decigram(self.protein),
+            brand: self.brand.clone(),
+            serving_g: self.serving_g.map(|g| g.round() as u64),
+            ..Default::default()
+        }
+    }
+}
+
+/// Picks the single food matching `query` among the search results `(id, description)`.
+///
+/// A description equal to the query (ignoring case) wins over partial matches.
+fn choose<Id: Copy + std::fmt::Display>(
+    query: &str,
+    candidates: &[(Id, String)],
+) -> Result<Id, Error> {
+    let exact: Vec<&(Id, String)> = candidates
+        .iter()
+        .filter(|(_, description)| description.eq_ignore_ascii_case(query))
+        .collect();
+    match (candidates, exact.as_slice()) {
+        ([], _) => Err(Error::FoodNotFound(query.to_string())),
+        ([(id, _)], _) | (_, [(id, _)]) => Ok(*id),
+        _ => Err(Error::AmbiguousFood {
+            query: query.to_string(),
+            candidates: candidates
+                .iter()
+                .map(|(id, description)| format!("{} {}", id, description))
+                .collect(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_choose() {
+        let candidates = vec![(1, "Oats".to_string()), (2, "Oats, rolled".to_string())];
+        assert_eq!(choose("oats", &candidates).unwrap(), 1);
+        assert_eq!(choose("oats, rolled", &candidates[1..]).unwrap(), 2);
+        assert!(matches!(
+            choose("oat", &candidates),
+            Err(Error::AmbiguousFood { .. })
+        ));
+        assert!(matches!(
+            choose::<u64>("oat", &[]),
+            Err(Error::FoodNotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,108 @@
+//! Open Food Facts product lookup by barcode.
+use super::FoodEntry;
+use crate::error::Error;
+use serde_json::Value;
+
+const API_URL: &str = "https://world.openfoodfacts.org/api/v2/product";
+const KJ_PER_KCAL: f64 = 4.184;
+
+/// Open Food Facts returns numbers as strings for some products
+fn number(value: &Value) -> Option<f64> {
+    value
+        .as_f64()
+        .or_else(|| value.as_str().and_then(|s| s.trim().parse().ok()))
+}
+
+/// Reads the per 100g nutrition of a product in an API response
+fn entry(barcode: &str, response: &Value) -> Result<FoodEntry, Error> {
+    if response["status"].as_u64() != Some(1) {
+        return Err(Error::FoodNotFound(barcode.to_string()));
+    }
+    let product = &response["product"];
+    let name = product["product_name"]
+        .as_str()
+        .filter(|name| !name.is_empty())
+        .unwrap_or(barcode)
+        .to_string();
+    let nutriments = &product["nutriments"];
+    let missing = |nutrient: &str| Error::MissingNutrient {
+        food: name.clone(),
+        nutrient: nutrient.to_string(),
+    };
+    let kcal = number(&nutriments["energy-kcal_100g"])
+        .or_else(|| number(&nutriments["energy_100g"]).map(|kj| kj / KJ_PER_KCAL))
+        .ok_or_else(|| missing("energy"))?;
+    let carb = number(&nutriments["carbohydrates_100g"]).ok_or_else(|| missing("carbohydrate"))?;
+    let fat = number(&nutriments["fat_100g"]).ok_or_else(|| missing("fat"))?;
+    let protein = number(&nutriments["proteins_100g"]).ok_or_else(|| missing("protein"))?;
+    Ok(FoodEntry {
+        brand: product["brands"]
+            .as_str()
+            .filter(|brands| !brands.is_empty())
+            .map(|brands| brands.to_string()),
+        serving_g: number(&product["serving_quantity"]),
+        name,
+        kcal,
+        carb,
+        fat,
+        protein,
+    })
+}
+
+/// Looks up a product through the Open Food Facts API
+pub fn from_api(barcode: &str) -> Result<FoodEntry, Error> {
+    let url = format!(
+        "{}/{}.json?fields=product_name,brands,serving_quantity,nutriments",
+        API_URL, barcode
+    );
+    let http_error = |source| Error::Http {
+        url: url.split('?').next().unwrap_or(&url).to_string(),
+        source,
+    };
+    let response: Value = ureq::get(&url)
+        .header("User-Agent", "natural-bodybuilder")
+        .call()
+        .map_err(http_error)?
+        .body_mut()
+        .read_json()
+        .map_err(http_error)?;
+    entry(barcode, &response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry() {
+        let response: Value = serde_json::from_str(
+            r#"{
+                "status": 1,
+                "product": {
+                    "product_name": "Magerquark",
+                    "brands": "Milbona",
+                    "serving_quantity": "250",
+                    "nutriments": {
+                        "energy_100g": 280,
+                        "carbohydrates_100g": 4,
+                        "fat_100g": 0.2,
+                        "proteins_100g": 12
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+        let entry = entry("4056489000000", &response).unwrap();
+        assert_eq!(entry.name, "Magerquark");
+        assert_eq!(entry.brand.as_deref(), Some("Milbona"));
+        assert_eq!(entry.serving_g, Some(250.0));
+        assert_eq!(entry.kcal.round(), 67.0);
+        assert_eq!(entry.fat, 0.2);
+
+        let not_found: Value = serde_json::from_str(r#"{"status": 0}"#).unwrap();
+        assert!(matches!(
+            super::entry("0", &not_found),
+            Err(Error::FoodNotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,161 @@
+//! National food composition tables such as the German Bundeslebensmittelschlüssel (BLS) or
+//! the Swiss Food Composition Database, exported as a CSV file with one row per food.
+//!
+//! Amounts are per 100g. The columns are mapped to the nutrients of an ingredient, and
+//! semicolon or tab separated files with decimal commas as in German exports are accepted.
+use super::{choose, FoodEntry};
+use crate::error::Error;
+use std::path::Path;
+
+/// Names of the columns that hold a food's ID, name and nutrients
+pub struct Columns {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub kcal: &'static str,
+    pub carb: &'static str,
+    pub fat: &'static str,
+    pub protein: &'static str,
+}
+
+/// Codes of the BLS, e.g. ZE for protein (Eiweiß)
+pub const BLS: Columns = Columns {
+    id: "SBLS",
+    name: "ST",
+    kcal: "GCAL",
+    carb: "ZK",
+    fat: "ZF",
+    protein: "ZE",
+};
+
+/// Headers of the English export of the Swiss Food Composition Database
+pub const SWISS_FCD: Columns = Columns {
+    id: "ID",
+    name: "Name",
+    kcal: "Energy, kilocalories (kcal)",
+    carb: "Carbohydrates, available (g)",
+    fat: "Fat, total (g)",
+    protein: "Protein (g)",
+};
+
+/// The separator that occurs most often in the header
+fn delimiter(header: &str) -> u8 {
+    [b',', b';', b'\t']
+        .iter()
+        .copied()
+        .max_by_key(|sep| header.bytes().filter(|b| b == sep).count())
+        .unwrap()
+}
+
+/// e.g. 12.5 from "12,5", or None for empty cells and markers like "-" or "tr"
+fn amount(cell: &str) -> Option<f64> {
+    cell.trim().replace(',', ".").parse().ok()
+}
+
+/// Looks up a food by ID, or by a part of its name, in the CSV export of a table
+pub fn from_csv(path: &Path, columns: &Columns, query: &str) -> Result<FoodEntry, Error> {
+    let read_error = |source| Error::ReadCsv {
+        path: path.to_path_buf(),
+        source,
+    };
+    let contents = std::fs::read_to_string(path).map_err(|source| Error::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let header = contents.lines().next().unwrap_or("");
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter(header))
+        .from_reader(contents.as_bytes());
+    let headers = reader.headers().map_err(read_error)?.clone();
+    let column = |column: &str| {
+        headers
+            .iter()
+            .position(|header| header.trim() == column)
+            .ok_or_else(|| Error::MissingColumn {
+                path: path.to_path_buf(),
+                column: column.to_string(),
+            })
+    };
+    let id_column = column(columns.id)?;
+    let name_column = column(columns.name)?;
+    let nutrient_columns = [
+        (column(columns.kcal)?, "energy"),
+        (column(columns.carb)?, "carbohydrate"),
+        (column(columns.fat)?, "fat"),
+        (column(columns.protein)?, "protein"),
+    ];
+    let query_lower = query.to_lowercase();
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(read_error)?;
+        let (id, name) = (&record[id_column], &record[name_column]);
+        if id == query || name.to_lowercase().contains(&query_lower) {
+            rows.push(record);
+        }
+    }
+    let candidates: Vec<(&str, String)> = rows
+        .iter()
+        .map(|record| (&record[id_column], record[name_column].to_string()))
+        .collect();
+    let id = choose(query, &candidates)?;
+    let record = rows.iter().find(|record| &record[id_column] == id).unwrap();
+    let name = record[name_column].to_string();
+    let mut nutrients = Vec::new();
+    for (column, nutrient) in nutrient_columns {
+        nutrients.push(
+            amount(&record[column]).ok_or_else(|| Error::MissingNutrient {
+                food: name.clone(),
+                nutrient: nutrient.to_string(),
+            })?,
+        );
+    }
+    Ok(FoodEntry {
+        name,
+        kcal: nutrients[0],
+        carb: nutrients[1],
+        fat: nutrients[2],
+        protein: nutrients[3],
+        brand: None,
+        serving_g: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_csv() {
+        let path = std::env::temp_dir().join(format!("bls-{}.csv", std::process::id()));
+        std::fs::write(
+            &path,
+            "SBLS;ST;GCAL;ZE;ZF;ZK\n\
+             C133000;Haferflocken;368;13,5;7,0;58,7\n\
+             C134000;Haferkleie;;17,3;7,0;48,5\n\
+             M111100;Speisequark mager;67;12,6;0,3;3,2\n",
+        )
+        .unwrap();
+        let by_name = from_csv(&path, &BLS, "haferflocken");
+        let by_id = from_csv(&path, &BLS, "M111100");
+        let ambiguous = from_csv(&path, &BLS, "hafer");
+        let incomplete = from_csv(&path, &BLS, "haferkleie");
+        let swiss = from_csv(&path, &SWISS_FCD, "quark");
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            by_name.unwrap(),
+            FoodEntry {
+                name: "Haferflocken".to_string(),
+                kcal: 368.0,
+                carb: 58.7,
+                fat: 7.0,
+                protein: 13.5,
+                brand: None,
+                serving_g: None,
+            }
+        );
+        assert_eq!(by_id.unwrap().name, "Speisequark mager");
+        assert!(matches!(ambiguous, Err(Error::AmbiguousFood { .. })));
+        assert!(matches!(incomplete, Err(Error::MissingNutrient { .. })));
+        assert!(matches!(swiss, Err(Error::MissingColumn { .. })));
+    }
+}
@@ -0,0 +1,264 @@
+//! USDA FoodData Central, either through its API or its CSV download.
+//!
+//! All FoodData Central nutrient amounts are per 100g.
+use super::{choose, FoodEntry};
+use crate::error::Error;
+#[cfg(feature = "http")]
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+
+#[cfg(feature = "http")]
+const API_URL: &str = "https://api.nal.usda.gov/fdc/v1";
+
+// FoodData Central nutrient ids
+const ENERGY_KCAL: u64 = 1008;
+const ENERGY_ATWATER_SPECIFIC: u64 = 2048;
+const ENERGY_ATWATER_GENERAL: u64 = 2047;
+const PROTEIN: u64 = 1003;
+const FAT: u64 = 1004;
+const CARB: u64 = 1005;
+
+/// A food is looked up by FDC ID if the query is a number and by description otherwise
+#[derive(Debug, PartialEq)]
+pub enum Query {
+    Id(u64),
+    Name(String),
+}
+
+impl Query {
+    pub fn parse(query: &str) -> Query {
+        match query.parse() {
+            Ok(id) => Query::Id(id),
+            Err(_) => Query::Name(query.to_string()),
+        }
+    }
+}
+
+fn entry(name: &str, nutrients: &HashMap<u64, f64>) -> Result<FoodEntry, Error> {
+    let get = |id| nutrients.get(&id).copied();
+    let missing = |nutrient: &str| Error::MissingNutrient {
+        food: name.to_string(),
+        nutrient: nutrient.to_string(),
+    };
+    Ok(FoodEntry {
+        name: name.to_string(),
+        kcal: get(ENERGY_KCAL)
+            .or_else(|| get(ENERGY_ATWATER_SPECIFIC))
+            .or_else(|| get(ENERGY_ATWATER_GENERAL))
+            .ok_or_else(|| missing("energy"))?,
+        carb: get(CARB).ok_or_else(|| missing("carbohydrate"))?,
+        fat: get(FAT).ok_or_else(|| missing("fat"))?,
+        protein: get(PROTEIN).ok_or_else(|| missing("protein"))?,
+        brand: None,
+        serving_g: None,
+    })
+}
+
+fn open_csv(path: &Path) -> Result<csv::Reader<std::fs::File>, Error> {
+    csv::Reader::from_path(path).map_err(|source| Error::ReadCsv {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Index of `column` in the csv file's header
+fn column(path: &Path, headers: &csv::StringRecord, column: &str) -> Result<usize, Error> {
+    headers
+        .iter()
+        .position(|header| header == column)
+        .ok_or_else(|| Error::MissingColumn {
+            path: path.to_path_buf(),
+            column: column.to_string(),
+        })
+}
+
+/// Looks up a food in the `food.csv` and `food_nutrient.csv` files of the CSV download
+pub fn from_csv(dir: &Path, query: &Query) -> Result<FoodEntry, Error> {
+    let food_path = dir.join("food.csv");
+    let read_error = |path: &Path| {
+        let path = path.to_path_buf();
+        move |source| Error::ReadCsv { path, source }
+    };
+    let mut foods = open_csv(&food_path)?;
+    let headers = foods.headers().map_err(read_error(&food_path))?.clone();
+    let id_column = column(&food_path, &headers, "fdc_id")?;
+    let description_column = column(&food_path, &headers, "description")?;
+    let mut candidates = Vec::new();
+    for record in foods.records() {
+        let record = record.map_err(read_error(&food_path))?;
+        let id: u64 = match record[id_column].parse() {
+            Ok(id) => id,
+            Err(_) => continue,
+        };
+        let description = &record[description_column];
+        let matches = match query {
+            Query::Id(query_id) => id == *query_id,
+            Query::Name(name) => description.to_lowercase().contains(&name.to_lowercase()),
+        };
+        if matches {
+            candidates.push((id, description.to_string()));
+        }
+    }
+    let query_name = match query {
+        Query::Id(id) => id.to_string(),
+        Query::Name(name) => name.clone(),
+    };
+    let id = choose(&query_name, &candidates)?;
+    let name = &candidates.iter().find(|(c, _)| *c == id).unwrap().1;
+
+    let nutrient_path = dir.join("food_nutrient.csv");
+    let mut food_nutrients = open_csv(&nutrient_path)?;
+    let headers = food_nutrients
+        .headers()
+        .map_err(read_error(&nutrient_path))?
+        .clone();
+    let id_column = column(&nutrient_path, &headers, "fdc_id")?;
+    let nutrient_column = column(&nutrient_path, &headers, "nutrient_id")?;
+    let amount_column = column(&nutrient_path, &headers, "amount")?;
+    let mut nutrients = HashMap::new();
+    for record in food_nutrients.records() {
+        let record = record.map_err(read_error(&nutrient_path))?;
+        if record[id_column].parse() != Ok(id) {
+            continue;
+        }
+        if let (Ok(nutrient), Ok(amount)) = (
+            record[nutrient_column].parse(),
+            record[amount_column].parse(),
+        ) {
+            nutrients.insert(nutrient, amount);
+        }
+    }
+    entry(name, &nutrients)
+}
+
+#[cfg(feature = "http")]
+fn get_json(url: &str) -> Result<Value, Error> {
+    let http_error = |source| Error::Http {
+        url: url.split('?').next().unwrap_or(url).to_string(),
+        source,
+    };
+    ureq::get(url)
+        .call()
+        .map_err(http_error)?
+        .body_mut()
+        .read_json()
+        .map_err(http_error)
+}
+
+#[cfg(feature = "http")]
+/// Nutrient amounts of a food in the response of the API's food endpoint
+fn food_nutrients(food: &Value) -> HashMap<u64, f64> {
+    let mut nutrients = HashMap::new();
+    for food_nutrient in food["foodNutrients"].as_array().into_iter().flatten() {
+        // the food endpoint nests the nutrient, the search endpoint doesn't
+        let id = food_nutrient["nutrient"]["id"]
+            .as_u64()
+            .or_else(|| food_nutrient["nutrientId"].as_u64());
+        let amount = food_nutrient["amount"]
+            .as_f64()
+            .or_else(|| food_nutrient["value"].as_f64());
+        if let (Some(id), Some(amount)) = (id, amount) {
+            nutrients.insert(id, amount);
+        }
+    }
+    nutrients
+}
+
+/// Looks up a food through the FoodData Central API
+#[cfg(feature = "http")]
+pub fn from_api(api_key: &str, query: &Query) -> Result<FoodEntry, Error> {
+    let food = match query {
+        Query::Id(id) => get_json(&format!("{}/food/{}?api_key={}", API_URL, id, api_key))?,
+        Query::Name(name) => {
+            let url = format!(
+                "{}/foods/search?query={}&pageSize=10&api_key={}",
+                API_URL,
+                url_encode(name),
+                api_key
+            );
+            let results = get_json(&url)?;
+            let foods: Vec<Value> = results["foods"].as_array().cloned().unwrap_or_default();
+            let candidates: Vec<(u64, String)> = foods
+                .iter()
+                .map(|food| {
+                    (
+                        food["fdcId"].as_u64().unwrap_or_default(),
+                        food["description"].as_str().unwrap_or("").to_string(),
+                    )
+                })
+                .collect();
+            let id = choose(name, &candidates)?;
+            foods
+                .into_iter()
+                .find(|food| food["fdcId"].as_u64() == Some(id))
+                .unwrap()
+        }
+    };
+    let name = food["description"].as_str().unwrap_or("").to_string();
+    entry(&name, &food_nutrients(&food))
+}
+
+#[cfg(feature = "http")]
+fn url_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_csv() {
+        let dir = std::env::temp_dir().join(format!("fdc-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("food.csv"),
+            "\"fdc_id\",\"data_type\",\"description\"\n\
+             \"173904\",\"sr_legacy_food\",\"Oats\"\n\
+             \"2346396\",\"foundation_food\",\"Oats, whole grain, rolled, old fashioned\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("food_nutrient.csv"),
+            "\"id\",\"fdc_id\",\"nutrient_id\",\"amount\"\n\
+             \"1\",\"173904\",\"1008\",\"389\"\n\
+             \"2\",\"173904\",\"1003\",\"16.89\"\n\
+             \"3\",\"173904\",\"1004\",\"6.9\"\n\
+             \"4\",\"173904\",\"1005\",\"66.27\"\n\
+             \"5\",\"2346396\",\"1003\",\"13.5\"\n",
+        )
+        .unwrap();
+
+        let by_name = from_csv(&dir, &Query::parse("oats"));
+        let by_id = from_csv(&dir, &Query::parse("173904"));
+        let incomplete = from_csv(&dir, &Query::parse("2346396"));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let oats = FoodEntry {
+            name: "Oats".to_string(),
+            kcal: 389.0,
+            carb: 66.27,
+            fat: 6.9,
+            protein: 16.89,
+            brand: None,
+            serving_g: None,
+        };
+        assert_eq!(by_name.unwrap(), oats);
+        assert_eq!(by_id.unwrap(), oats);
+        assert!(matches!(incomplete, Err(Error::MissingNutrient { .. })));
+    }
+
+    #[test]
+    #[cfg(feature = "http")]
+    fn test_url_encode() {
+        assert_eq!(url_encode("oats, rolled"), "oats%2C%20rolled");
+    }
+}
@@ -0,0 +1,4747 @@
+//! Mixes ingredients to hit a kcal target with a desired macro ratio, the core of the
+//! `natural-bodybuilder` binary and of the WASM build.
+mod adjust;
+mod alternatives;
+#[cfg(any(feature = "server", feature = "wasm"))]
+mod api;
+mod batch;
+mod blend;
+mod calculator;
+pub mod cli;
+mod diff;
+mod document;
+pub mod error;
+mod exact;
+mod examples;
+mod explain;
+mod export;
+mod feasibility;
+mod flavoring;
+mod genetic;
+mod history;
+mod import;
+mod meal;
+mod metrics;
+mod names;
+mod output;
+mod pantry;
+mod portion;
+mod progress;
+mod recipe;
+#[cfg(feature = "server")]
+mod server;
+mod shopping;
+mod stock;
+mod targets;
+#[cfg(feature = "tui")]
+mod tui;
+mod uncertainty;
+mod units;
+mod violations;
+#[cfg(feature = "wasm")]
+mod wasm;
+mod wizard;
+
+use cli::{
+    AddIngredientArgs, Algorithm, BlendArgs, CalcTargetArgs, Command, ConsumeArgs, DiffArgs,
+    ExamplesCommand, ExportArgs, ImportSource, ImportedIngredientArgs, IngredientArgs, InspectArgs,
+    LogArgs, MixArgs, OnDuplicate, OptimizeArgs, OutputFormat, PantryCommand, PortionArgs,
+    ReevalArgs, ShoppingListArgs, StatsArgs, StockArgs,
+};
+use error::Error;
+use explain::{BlockReason, Explanation};
+use flavoring::Flavorings;
+use meal::Meal;
+use metrics::Metric;
+use output::{Report, Rounding};
+use progress::Progress;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_pcg::Pcg64;
+use rayon::prelude::*;
+use recipe::Recipe;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::{BufReader, IsTerminal};
+use std::path::{Path, PathBuf};
+
+/// Energy of the macros according to the Atwater system
+const KCAL_PER_G_CARB: f64 = 4.0;
+const KCAL_PER_G_FAT: f64 = 9.0;
+const KCAL_PER_G_PROTEIN: f64 = 4.0;
+const KCAL_PER_G_ALCOHOL: f64 = 7.0;
+const KCAL_PER_G_POLYOLS: f64 = 2.4;
+// weight of sub-macros above their limit relative to the deviation from the macro ratio
+const SUB_MACRO_PENALTY: f64 = 10.0;
+// how far an ingredient's declared kcal may be from the ones of its macros, in percent
+const KCAL_MISMATCH_PCT: f64 = 10.0;
+// smaller differences of the kcal aren't reported whatever their percentage, e.g. of coffee
+const KCAL_MISMATCH_MIN_KCAL: f64 = 5.0;
+// protein scores from which an ingredient's protein counts as high quality, e.g. eggs or soy
+const HIGH_QUALITY_PROTEIN_SCORE: f64 = 0.9;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+struct Ingredient {
+    name: String,
+    // other names that target constraints can refer to the ingredient by, e.g. chicken
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    aliases: Vec<String>,
+    // amount the values are given for, can be left out if `per` is given
+    #[serde(default)]
+    g: u64,
+    // the values are per 100g or per serving of `serving_g` as on labels
+    per: Option<Per>,
+    // label values may be fractional, e.g. 3.6g protein per 100g
+    // derived from the macros if omitted
+    #[serde(default)]
+    kcal: f64,
+    // in g
+    carb: f64,
+    // in g
+    fat: f64,
+    // in g
+    protein: f64,
+    // quality of the protein as a PDCAAS or DIAAS fraction, e.g. 1.0 for whey or 0.4 for wheat
+    protein_score: Option<f64>,
+    // in g, part of the essential amino acids, which are part of protein
+    leucine_g: Option<f64>,
+    eaa_g: Option<f64>,
+    // in g, part of fat
+    omega3_g: Option<f64>,
+    omega6_g: Option<f64>,
+    // in g, e.g. in beer
+    alcohol: Option<f64>,
+    // sugar alcohols in g, part of carb as on labels
+    polyols: Option<f64>,
+    // saturated fat in g, part of fat
+    sat_fat: Option<f64>,
+    // in g, part of carb
+    sugar: Option<f64>,
+    sodium_mg: Option<f64>,
+    // water the ingredient contains or is drunk with, e.g. in milk
+    water_ml: Option<f64>,
+    caffeine_mg: Option<f64>,
+    // glycemic index of the ingredient's carbs, glucose is 100
+    glycemic_index: Option<f64>,
+    // kind of food that the result is summarized by, e.g. protein sources, grains or treats
+    group: Option<String>,
+    // diets the ingredient fits and allergens it contains, e.g. vegan, gluten or nuts
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    // meals the ingredient is eaten at, e.g. breakfast, or any meal if empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    meals: Vec<String>,
+    // relative uncertainty of the label values in percent
+    tolerance: Option<f64>,
+    // size of the package the ingredient is sold in, in g
+    package_g: Option<u64>,
+    // smallest amount worth using in g, the ingredient is either not used or at least this much
+    min_g: Option<u64>,
+    // largest amount ever used in g
+    max_g: Option<u64>,
+    // the ingredient is only used in multiples of this, e.g. one egg
+    portion_g: Option<u64>,
+    // size of a cooked batch in g, the plan's days use whole or half batches of it
+    batch_g: Option<u64>,
+    // minutes of preparing the ingredient, e.g. cooking rice, counted once if it's used at all
+    prep_minutes: Option<f64>,
+    // unit the ingredient is measured in besides grams, e.g. ml for milk or piece for eggs
+    unit: Option<units::Unit>,
+    // in g per ml
+    density: Option<f64>,
+    // in g, the portion size if omitted
+    piece_weight: Option<f64>,
+    brand: Option<String>,
+    // size of a serving as declared on the label, in g
+    serving_g: Option<u64>,
+}
+
+/// Amount that an ingredient's values are given for
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+enum Per {
+    #[serde(rename = "100g")]
+    Hundred,
+    #[serde(rename = "serving")]
+    Serving,
+}
+
+impl Ingredient {
+    /// Sets `g` from `per`, or fails if neither is given or they conflict
+    fn resolve_per(&mut self) -> Result<(), Error> {
+        let invalid = |reason: String| Error::InvalidAmount {
+            name: self.name.clone(),
+            reason,
+        };
+        let per_g = match (self.per, self.serving_g) {
+            (None, _) if self.g == 0 => return Err(invalid("has neither g nor per".to_string())),
+            (None, _) => return Ok(()),
+            (Some(Per::Hundred), _) => 100,
+            (Some(Per::Serving), Some(serving_g)) if serving_g > 0 => serving_g,
+            (Some(Per::Serving), _) => {
+                return Err(invalid(
+                    "declares per = \"serving\" but no serving_g".to_string(),
+                ))
+            }
+        };
+        if self.g != 0 && self.g != per_g {
+            return Err(invalid(format!(
+                "declares g = {} but its values are per {}g",
+                self.g, per_g
+            )));
+        }
+        self.g = per_g;
+        Ok(())
+    }
+
+    /// Whether the ingredient has no kcal, e.g. water, so it has no kcal pieces and is added to
+    /// the plan in grams after the search
+    fn is_kcal_free(&self) -> bool {
+        self.kcal == 0.0
+    }
+
+    /// Number of kcal pieces of `g` grams of the ingredient if the target is split into `steps`
+    fn pieces(&self, g: u64, target: &Target, steps: usize) -> u64 {
+        let piece_per_kcal = steps as f64 / target.kcal as f64;
+        let kcal_per_gram = self.kcal / self.g as f64;
+        (g as f64 * kcal_per_gram * piece_per_kcal).round() as u64
+    }
+
+    /// kcal of the macros by their Atwater factors
+    fn macro_kcal(&self) -> f64 {
+        let polyols = self.polyols.unwrap_or(0.0);
+        KCAL_PER_G_CARB * (self.carb - polyols)
+            + KCAL_PER_G_POLYOLS * polyols
+            + KCAL_PER_G_FAT * self.fat
+            + KCAL_PER_G_PROTEIN * self.protein
+            + KCAL_PER_G_ALCOHOL * self.alcohol.unwrap_or(0.0)
+    }
+
+    /// Protein of `self.g` grams that comes with a high protein score
+    fn quality_protein(&self) -> f64 {
+        match self.protein_score {
+            Some(score) if score >= HIGH_QUALITY_PROTEIN_SCORE => self.protein,
+            _ => 0.0,
+        }
+    }
+
+    /// Glycemic load of `self.g` grams, from the carbs without polyols
+    fn glycemic_load(&self) -> f64 {
+        let available_carb = self.carb - self.polyols.unwrap_or(0.0);
+        self.glycemic_index
+            .map_or(0.0, |index| index * available_carb / 100.0)
+    }
+
+    /// Describes the difference of the declared kcal to the ones of the macros if it's more than
+    /// `tolerance_pct` percent and `KCAL_MISMATCH_MIN_KCAL`, foods without macros aren't checked
+    fn kcal_mismatch(&self, tolerance_pct: f64) -> Option<String> {
+        let macro_kcal = self.macro_kcal();
+        let difference = (self.kcal - macro_kcal).abs();
+        if macro_kcal == 0.0 || difference < KCAL_MISMATCH_MIN_KCAL {
+            return None;
+        }
+        let mismatch_pct = 100.0 * difference / macro_kcal;
+        if mismatch_pct <= tolerance_pct {
+            return None;
+        }
+        Some(format!(
+            "{} declares {} kcal but its macros have {} kcal ({}% off)",
+            self.name,
+            self.kcal,
+            macro_kcal.round(),
+            mismatch_pct.round()
+        ))
+    }
+
+    fn normalize(&self) -> NormalizedIngredient {
+        let carb = self.carb / self.kcal;
+        let fat = self.fat / self.kcal;
+        let protein = self.protein / self.kcal;
+        let quality_protein = self.quality_protein() / self.kcal;
+        let leucine_g = self.leucine_g.unwrap_or(0.0) / self.kcal;
+        let eaa_g = self.eaa_g.unwrap_or(0.0) / self.kcal;
+        let omega3_g = self.omega3_g.unwrap_or(0.0) / self.kcal;
+        let omega6_g = self.omega6_g.unwrap_or(0.0) / self.kcal;
+        let alcohol = self.alcohol.unwrap_or(0.0) / self.kcal;
+        let sat_fat = self.sat_fat.unwrap_or(0.0) / self.kcal;
+        let sugar = self.sugar.unwrap_or(0.0) / self.kcal;
+        let sodium_mg = self.sodium_mg.unwrap_or(0.0) / self.kcal;
+        let water_ml = self.water_ml.unwrap_or(0.0) / self.kcal;
+        let caffeine_mg = self.caffeine_mg.unwrap_or(0.0) / self.kcal;
+        let glycemic_load = self.glycemic_load() / self.kcal;
+        let g = self.g as f64 / self.kcal;
+        NormalizedIngredient {
+            carb,
+            fat,
+            protein,
+            quality_protein,
+            leucine_g,
+            eaa_g,
+            omega3_g,
+            omega6_g,
+            alcohol,
+            sat_fat,
+            sugar,
+            sodium_mg,
+            water_ml,
+            caffeine_mg,
+            glycemic_load,
+            g,
+        }
+    }
+}
+
+/// carb, fat and protein in grams per kcal
+#[derive(Clone, Debug)]
+struct NormalizedIngredient {
+    carb: f64,
+    fat: f64,
+    protein: f64,
+    // protein with a high protein score
+    quality_protein: f64,
+    leucine_g: f64,
+    eaa_g: f64,
+    omega3_g: f64,
+    omega6_g: f64,
+    alcohol: f64,
+    sat_fat: f64,
+    sugar: f64,
+    sodium_mg: f64,
+    water_ml: f64,
+    caffeine_mg: f64,
+    glycemic_load: f64,
+    // weight of the ingredient
+    g: f64,
+}
+
+/// Running totals of the macros in a proposal, so that adding pieces doesn't require mixing
+/// the whole proposal again
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct MixTotals {
+    carb: f64,
+    fat: f64,
+    protein: f64,
+    quality_protein: f64,
+    leucine_g: f64,
+    eaa_g: f64,
+    omega3_g: f64,
+    omega6_g: f64,
+    alcohol: f64,
+    sat_fat: f64,
+    sugar: f64,
+    sodium_mg: f64,
+    water_ml: f64,
+    caffeine_mg: f64,
+    glycemic_load: f64,
+    g: f64,
+    pieces: u64,
+}
+
+impl MixTotals {
+    fn new(proposal: &Proposal, ingredients: &Ingredients) -> MixTotals {
+        let mut totals = MixTotals::default();
+        for (name, num) in &proposal.0 {
+            totals.add(&ingredients.0[name], *num);
+        }
+        totals
+    }
+
+    /// Adds `pieces` of the ingredient
+    fn add(&mut self, ingredient: &NormalizedIngredient, pieces: u64) {
+        let num = pieces as f64;
+        self.carb += num * ingredient.carb;
+        self.fat += num * ingredient.fat;
+        self.protein += num * ingredient.protein;
+        self.quality_protein += num * ingredient.quality_protein;
+        self.leucine_g += num * ingredient.leucine_g;
+        self.eaa_g += num * ingredient.eaa_g;
+        self.omega3_g += num * ingredient.omega3_g;
+        self.omega6_g += num * ingredient.omega6_g;
+        self.alcohol += num * ingredient.alcohol;
+        self.sat_fat += num * ingredient.sat_fat;
+        self.sugar += num * ingredient.sugar;
+        self.sodium_mg += num * ingredient.sodium_mg;
+        self.water_ml += num * ingredient.water_ml;
+        self.caffeine_mg += num * ingredient.caffeine_mg;
+        self.glycemic_load += num * ingredient.glycemic_load;
+        self.g += num * ingredient.g;
+        self.pieces += pieces;
+    }
+
+    /// Returns the totals after adding `pieces` of the ingredient
+    fn with(&self, ingredient: &NormalizedIngredient, pieces: u64) -> MixTotals {
+        let mut totals = *self;
+        totals.add(ingredient, pieces);
+        totals
+    }
+}
+
+#[derive(Clone)]
+struct Ingredients(BTreeMap<String, NormalizedIngredient>);
+#[derive(Clone)]
+struct RawIngredients(BTreeMap<String, Ingredient>);
+
+impl RawIngredients {
+    /// The ingredients that have kcal and are searched
+    fn searched(&self) -> impl Iterator<Item = (&String, &Ingredient)> {
+        self.0
+            .iter()
+            .filter(|(_, ingredient)| !ingredient.is_kcal_free())
+    }
+
+    /// The ingredients of the search, without the kcal-free ones
+    fn normalize(&self) -> Ingredients {
+        Ingredients(
+            self.searched()
+                .map(|(name, ingredient)| (name.clone(), ingredient.normalize()))
+                .collect(),
+        )
+    }
+
+    /// Name of the ingredient of each alias, or fails if an alias could mean several ingredients
+    fn aliases(&self) -> Result<BTreeMap<&str, &str>, Error> {
+        let mut aliases: BTreeMap<&str, &str> = BTreeMap::new();
+        for (name, ingredient) in &self.0 {
+            for alias in ingredient.aliases.iter().filter(|alias| *alias != name) {
+                let other = match self.0.get_key_value(alias) {
+                    Some((other, _)) => Some(other.as_str()),
+                    None => aliases.get(alias.as_str()).copied(),
+                };
+                if let Some(other) = other {
+                    return Err(Error::AmbiguousAlias {
+                        alias: alias.clone(),
+                        first: other.to_string(),
+                        second: name.clone(),
+                    });
+                }
+                aliases.insert(alias, name);
+            }
+        }
+        Ok(aliases)
+    }
+
+    /// Error for a name that isn't loaded, with the names and aliases it may be a misspelling of
+    fn missing(&self, name: &str) -> Error {
+        let aliases = self
+            .0
+            .values()
+            .flat_map(|ingredient| ingredient.aliases.iter());
+        let candidates = self.0.keys().chain(aliases).map(String::as_str);
+        Error::MissingIngredient {
+            name: name.to_string(),
+            suggestions: names::closest(name, candidates)
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+}
+
+// saved as a table of ingredient names and grams
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+struct Proposal(BTreeMap<String, u64>);
+impl Proposal {
+    fn kcal(&self) -> u64 {
+        self.0.values().sum()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+struct Target {
+    // computed from the grams if they are given instead
+    #[serde(default)]
+    kcal: u64,
+    // in ratio
+    #[serde(default)]
+    carb: u64,
+    // in ratio
+    #[serde(default)]
+    fat: u64,
+    // in ratio
+    #[serde(default)]
+    protein: u64,
+    // constraints
+    constraint_exact: Option<Vec<TargetConstraint>>,
+    // constraints
+    constraint_at_least: Option<Vec<TargetConstraint>>,
+    // constraints
+    constraint_at_most: Option<Vec<TargetConstraint>>,
+    // constraints on the share of the mix's weight
+    constraint_max_weight_pct: Option<Vec<ShareConstraint>>,
+    // constraints on the share of the target's kcal
+    constraint_max_kcal_pct: Option<Vec<ShareConstraint>>,
+    // largest fraction of the kcal any single ingredient may contribute, e.g. 0.35
+    max_single_ingredient_kcal_share: Option<f64>,
+    // limits on sub-macros, penalized in the search
+    sat_fat_at_most: Option<SubMacroLimit>,
+    sugar_at_most: Option<SubMacroLimit>,
+    // sodium and glycemic load of the whole plan that are never exceeded
+    max_sodium_mg: Option<f64>,
+    max_glycemic_load: Option<f64>,
+    // water and caffeine of the whole plan, the maximums are never exceeded and falling short of
+    // the minimums is penalized in the search
+    min_water_ml: Option<f64>,
+    max_water_ml: Option<f64>,
+    min_caffeine_mg: Option<f64>,
+    max_caffeine_mg: Option<f64>,
+    // most minutes of preparing the used ingredients, e.g. of a day or, with `meal`, of a meal,
+    // never exceeded
+    max_prep_minutes: Option<f64>,
+    // least share of the protein in percent from ingredients with a high protein score,
+    // penalized in the search
+    min_quality_protein_pct: Option<f64>,
+    // least leucine and essential amino acids of the plan, e.g. of a meal for muscle protein
+    // synthesis, penalized in the search
+    min_leucine_g: Option<f64>,
+    min_eaa_g: Option<f64>,
+    // most omega-6 per g of omega-3, e.g. 4 for 4:1, penalized in the search
+    max_omega6_to_omega3: Option<f64>,
+    // foods already eaten or planned with known macros, e.g. a restaurant meal, which are
+    // subtracted from the target before the rest is planned
+    fixed: Option<Vec<meal::FixedFood>>,
+    // only ingredients tagged with the diet are used
+    diet: Option<Diet>,
+    // ingredients with any of these tags aren't used, e.g. allergens
+    exclude_tags: Option<Vec<String>>,
+    // derived metrics that are shown for the result, ingredients and meals
+    metric: Option<Vec<Metric>>,
+    // meal the target is for, e.g. breakfast, which leaves out ingredients meant for other meals
+    meal: Option<String>,
+    // number of kcal pieces used by the optimizer
+    resolution: Option<usize>,
+    // how much missing each macro of the ratio counts, e.g. protein = 2
+    macro_weight: Option<MacroWeights>,
+    // how much spreading the kcal across more ingredients counts, e.g. 0.01
+    diversity_weight: Option<f64>,
+    // macros in grams instead of the ratio, a missing one is the rest of the kcal
+    carb_g: Option<f64>,
+    fat_g: Option<f64>,
+    protein_g: Option<f64>,
+    // the result may have between these kcal if that fits the ratio better
+    kcal_min: Option<u64>,
+    kcal_max: Option<u64>,
+    // or up to this many percent more or less than the kcal
+    kcal_tolerance_pct: Option<f64>,
+}
+
+/// Weights of the errors of the macros in the cost, relative to each other
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+struct MacroWeights {
+    carb: f64,
+    fat: f64,
+    protein: f64,
+}
+
+impl Default for MacroWeights {
+    fn default() -> MacroWeights {
+        MacroWeights {
+            carb: 1.0,
+            fat: 1.0,
+            protein: 1.0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct TargetConstraint {
+    name: String,
+    g: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ShareConstraint {
+    name: String,
+    // in percent of the total weight or kcal
+    pct: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Diet {
+    // ingredients tagged vegetarian or vegan
+    Vegetarian,
+    Vegan,
+}
+
+/// Upper limit of a sub-macro such as sugar, in g and/or percent of the kcal
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct SubMacroLimit {
+    g: Option<f64>,
+    pct_kcal: Option<f64>,
+}
+
+impl SubMacroLimit {
+    /// Largest share of the target's kcal, the stricter one if both limits are given
+    fn kcal_share(&self, kcal_per_g: f64, target_kcal: u64) -> f64 {
+        let g_share = self.g.map(|g| g * kcal_per_g / target_kcal as f64);
+        let pct_share = self.pct_kcal.map(|pct| pct / 100.0);
+        match (g_share, pct_share) {
+            (Some(g_share), Some(pct_share)) => g_share.min(pct_share),
+            (Some(share), None) | (None, Some(share)) => share,
+            (None, None) => f64::INFINITY,
+        }
+    }
+}
+
+impl TargetConstraint {
+    /// compute pieces for optimization
+    fn to_pieces(
+        &self,
+        target: &Target,
+        raw_ingredients: &RawIngredients,
+        optimization_steps: usize,
+    ) -> Result<u64, Error> {
+        let ingredient = raw_ingredients
+            .0
+            .get(&self.name)
+            .ok_or_else(|| raw_ingredients.missing(&self.name))?;
+        Ok(ingredient.pieces(self.g, target, optimization_steps))
+    }
+}
+
+/// Number of kcal pieces used when neither the command line nor the target specify it
+const DEFAULT_OPTIMIZATION_STEPS: usize = 2000;
+// relative change of the cost below which the search has reached a plateau and takes larger
+// steps
+const PLATEAU_COST_CHANGE: f64 = 1e-3;
+// the mix has at least this many pieces per piece added in a single step
+const MIN_PIECES_PER_STRIDE: u64 = 100;
+// in randomized restarts, candidates whose cost is within this fraction of the best are tied
+const RESTART_TIE_COST_CHANGE: f64 = 1e-2;
+// kcal per piece above which at most constraints are noticeably imprecise
+const MAX_KCAL_PER_PIECE: f64 = 5.0;
+// number of candidate ingredients from which they are evaluated in parallel
+const MIN_PARALLEL_CANDIDATES: usize = 64;
+// share of the pieces that an earlier plan fills when the search starts from it, the rest is
+// left to the search to adjust the mix to the target
+const WARM_START_SHARE: f64 = 0.9;
+// within a kcal band, the result only moves away from the target's kcal while that lowers the
+// cost by at least this fraction per step
+const KCAL_BAND_MIN_IMPROVEMENT: f64 = 0.05;
+
+impl Target {
+    /// Sets the ratio, and the kcal if they aren't given, from the macros in grams, or fails if
+    /// the target has neither or they conflict
+    fn resolve_grams(&mut self) -> Result<(), Error> {
+        let grams = [self.carb_g, self.fat_g, self.protein_g];
+        let given = grams.iter().flatten().count();
+        if given == 0 {
+            if self.kcal == 0 || self.carb + self.fat + self.protein == 0 {
+                return Err(Error::InvalidTarget(
+                    "needs kcal and a ratio of carb, fat and protein, or the macros in grams"
+                        .to_string(),
+                ));
+            }
+            return Ok(());
+        }
+        if self.carb + self.fat + self.protein != 0 {
+            return Err(Error::InvalidTarget(
+                "gives both a ratio and macros in grams".to_string(),
+            ));
+        }
+        let kcal_per_g = [KCAL_PER_G_CARB, KCAL_PER_G_FAT, KCAL_PER_G_PROTEIN];
+        let given_kcal: f64 = grams
+            .iter()
+            .zip(kcal_per_g.iter())
+            .map(|(g, kcal_per_g)| g.unwrap_or(0.0) * kcal_per_g)
+            .sum();
+        let grams: Vec<f64> = match given {
+            3 => grams.iter().flatten().copied().collect(),
+            2 if self.kcal > 0 && given_kcal <= self.kcal as f64 => grams
+                .iter()
+                .zip(kcal_per_g.iter())
+                .map(|(g, kcal_per_g)| g.unwrap_or((self.kcal as f64 - given_kcal) / kcal_per_g))
+                .collect(),
+            2 if self.kcal > 0 => {
+                return Err(Error::InvalidTarget(format!(
+                    "has {:.0} kcal in the given grams, more than its {} kcal",
+                    given_kcal, self.kcal
+                )))
+            }
+            _ => {
+                return Err(Error::InvalidTarget(
+                    "needs all of carb_g, fat_g and protein_g, or kcal and two of them".to_string(),
+                ))
+            }
+        };
+        if self.kcal == 0 {
+            self.kcal = given_kcal.round() as u64;
+        }
+        // the ratio is rounded to percent, the largest remainders are rounded up so it adds up
+        // to 100
+        let total: f64 = grams.iter().sum();
+        let exact: Vec<f64> = grams.iter().map(|g| g / total * 100.0).collect();
+        let mut pct: Vec<u64> = exact.iter().map(|pct| pct.floor() as u64).collect();
+        let mut by_remainder: Vec<usize> = (0..3).collect();
+        by_remainder.sort_by(|a, b| (exact[*b] % 1.0).total_cmp(&(exact[*a] % 1.0)));
+        let missing = 100 - pct.iter().sum::<u64>() as usize;
+        for i in by_remainder.into_iter().take(missing) {
+            pct[i] += 1;
+        }
+        self.carb = pct[0];
+        self.fat = pct[1];
+        self.protein = pct[2];
+        self.carb_g = None;
+        self.fat_g = None;
+        self.protein_g = None;
+        Ok(())
+    }
+
+    /// Refers to the ingredients of constraints that use an alias by their name, and with
+    /// `ignore_case` also to those that only differ in case from one name or alias. Returns a
+    /// note for each constraint whose name only matched in case.
+    fn resolve_names(
+        &mut self,
+        raw_ingredients: &RawIngredients,
+        ignore_case: bool,
+    ) -> Result<Vec<String>, Error> {
+        let aliases = raw_ingredients.aliases()?;
+        let mut notes = Vec::new();
+        let mut resolve = |name: &mut String| {
+            if let Some(canonical) = aliases.get(name.as_str()) {
+                *name = canonical.to_string();
+            }
+            if !ignore_case || raw_ingredients.0.contains_key(name) {
+                return;
+            }
+            let lowercase = name.to_lowercase();
+            let matches: BTreeSet<&str> = raw_ingredients
+                .0
+                .keys()
+                .map(|name| (name.as_str(), name.as_str()))
+                .chain(aliases.iter().map(|(alias, name)| (*alias, *name)))
+                .filter(|(candidate, _)| candidate.to_lowercase() == lowercase)
+                .map(|(_, name)| name)
+                .collect();
+            if matches.len() == 1 {
+                let canonical = matches.into_iter().next().unwrap();
+                notes.push(format!(
+                    "constraint on \"{}\" is taken to mean \"{}\"",
+                    name, canonical
+                ));
+                *name = canonical.to_string();
+            }
+        };
+        let constraints = [
+            &mut self.constraint_exact,
+            &mut self.constraint_at_least,
+            &mut self.constraint_at_most,
+        ];
+        for constraints in IntoIterator::into_iter(constraints).flatten() {
+            constraints
+                .iter_mut()
+                .for_each(|constraint| resolve(&mut constraint.name));
+        }
+        let shares = [
+            &mut self.constraint_max_weight_pct,
+            &mut self.constraint_max_kcal_pct,
+        ];
+        for constraint in IntoIterator::into_iter(shares).flatten().flatten() {
+            resolve(&mut constraint.name);
+        }
+        Ok(notes)
+    }
+
+    /// Drops the constraints on ingredients that aren't kept, e.g. because they are excluded
+    /// for this run
+    fn retain_constraints<F: Fn(&str) -> bool>(&mut self, keep: F) {
+        let constraints = [
+            &mut self.constraint_exact,
+            &mut self.constraint_at_least,
+            &mut self.constraint_at_most,
+        ];
+        for constraints in IntoIterator::into_iter(constraints).flatten() {
+            constraints.retain(|constraint| keep(&constraint.name));
+        }
+        let shares = [
+            &mut self.constraint_max_weight_pct,
+            &mut self.constraint_max_kcal_pct,
+        ];
+        for constraints in IntoIterator::into_iter(shares).flatten() {
+            constraints.retain(|constraint| keep(&constraint.name));
+        }
+    }
+
+    /// Steps given on the command line take precedence over the target's resolution
+    fn optimization_steps(&self, cli_steps: Option<usize>) -> usize {
+        cli_steps
+            .or(self.resolution)
+            .unwrap_or(DEFAULT_OPTIMIZATION_STEPS)
+    }
+
+    /// The macro ratio in percent as in the result table
+    fn ratio(&self, rounding: Rounding) -> output::Ratio {
+        let normalized = self.normalize();
+        output::Ratio {
+            carb: rounding.pct(100.0 * normalized.carb),
+            fat: rounding.pct(100.0 * normalized.fat),
+            protein: rounding.pct(100.0 * normalized.protein),
+        }
+    }
+
+    fn metrics(&self) -> &[Metric] {
+        self.metric.as_deref().unwrap_or(&[])
+    }
+
+    /// Whether the target's diet, excluded tags or meal rule out the ingredient
+    fn excludes(&self, ingredient: &Ingredient) -> bool {
+        let has = |tag: &str| ingredient.tags.iter().any(|t| t == tag);
+        let off_diet = match self.diet {
+            Some(Diet::Vegetarian) => !has("vegetarian") && !has("vegan"),
+            Some(Diet::Vegan) => !has("vegan"),
+            None => false,
+        };
+        let other_meal = match &self.meal {
+            Some(meal) => !ingredient.meals.is_empty() && !ingredient.meals.contains(meal),
+            None => false,
+        };
+        off_diet || other_meal || self.exclude_tags.iter().flatten().any(|tag| has(tag))
+    }
+
+    fn kcal_per_piece(&self, steps: usize) -> f64 {
+        self.kcal as f64 / steps as f64
+    }
+
+    /// Fewest and most kcal of the result, which always include the target's kcal
+    fn kcal_band(&self) -> (f64, f64) {
+        let kcal = self.kcal as f64;
+        let tolerance = kcal * self.kcal_tolerance_pct.unwrap_or(0.0) / 100.0;
+        let min = self.kcal_min.map_or(kcal - tolerance, |min| min as f64);
+        let max = self.kcal_max.map_or(kcal + tolerance, |max| max as f64);
+        (min.min(kcal), max.max(kcal))
+    }
+
+    /// Describes how imprecise at most constraints are if the pieces are larger than
+    /// `MAX_KCAL_PER_PIECE`, with the steps that would make them small enough
+    fn coarse_resolution(&self, steps: usize) -> Option<String> {
+        let kcal_per_piece = self.kcal_per_piece(steps);
+        if kcal_per_piece <= MAX_KCAL_PER_PIECE {
+            return None;
+        }
+        Some(format!(
+            "{} steps make pieces of {:.1} kcal, so at most constraints may be missed by that \
+             much; use --steps {} or more for pieces of at most {} kcal",
+            steps,
+            kcal_per_piece,
+            (self.kcal as f64 / MAX_KCAL_PER_PIECE).ceil(),
+            MAX_KCAL_PER_PIECE
+        ))
+    }
+
+    fn normalize(&self) -> NormalizedTarget {
+        let kcal_share = |limit: &Option<SubMacroLimit>, kcal_per_g| {
+            limit
+                .as_ref()
+                .map(|limit| limit.kcal_share(kcal_per_g, self.kcal))
+        };
+        // the ratio is scaled to add up to 100 if it doesn't
+        let sum = (self.carb + self.fat + self.protein) as f64;
+        NormalizedTarget {
+            carb: self.carb as f64 / sum,
+            fat: self.fat as f64 / sum,
+            protein: self.protein as f64 / sum,
+            sat_fat_kcal_share: kcal_share(&self.sat_fat_at_most, KCAL_PER_G_FAT),
+            sugar_kcal_share: kcal_share(&self.sugar_at_most, KCAL_PER_G_CARB),
+            min_water_ml_per_kcal: self.min_water_ml.map(|ml| ml / self.kcal as f64),
+            min_caffeine_mg_per_kcal: self.min_caffeine_mg.map(|mg| mg / self.kcal as f64),
+            min_quality_protein_share: self.min_quality_protein_pct.map(|pct| pct / 100.0),
+            min_leucine_g_per_kcal: self.min_leucine_g.map(|g| g / self.kcal as f64),
+            min_eaa_g_per_kcal: self.min_eaa_g.map(|g| g / self.kcal as f64),
+            max_omega6_to_omega3: self.max_omega6_to_omega3,
+            weights: self.macro_weight.unwrap_or_default(),
+            diversity_weight: self.diversity_weight.unwrap_or(0.0),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct TargetConstraints {
+    // constraints
+    exact: Proposal,
+    at_least: Proposal,
+    at_most: Proposal,
+    // ingredients are either not used or at least this much
+    at_least_if_used: Proposal,
+    // ingredients that are added in portions of this many pieces
+    portion: Proposal,
+    // largest share of the mix's weight
+    max_weight_share: BTreeMap<String, f64>,
+    // largest sodium and glycemic load of the mix per kcal times kcal pieces, like the mix
+    // totals
+    max_sodium: Option<f64>,
+    max_glycemic_load: Option<f64>,
+    max_water: Option<f64>,
+    max_caffeine: Option<f64>,
+    // minutes of preparing the ingredients that declare them, and the most of the used ones
+    prep_minutes: BTreeMap<String, f64>,
+    max_prep_minutes: Option<f64>,
+    // pieces of an earlier plan that the search starts from
+    start: Proposal,
+    // plan that the result is kept close to
+    reference: Option<Reference>,
+    // ingredients in stock that expire soon and are preferred
+    expiry: Option<Expiry>,
+    // fewest and most pieces of the result if its kcal may differ from the target's
+    step_band: Option<(usize, usize)>,
+}
+
+/// A plan that the result should stay close to, as each ingredient's share of the pieces
+#[derive(Clone, Debug)]
+struct Reference {
+    shares: BTreeMap<String, f64>,
+    // cost of the squared distance of the shares
+    weight: f64,
+    // sum of the squared shares
+    norm: f64,
+}
+
+impl Reference {
+    fn new(pieces: &[(&String, u64)], weight: f64) -> Reference {
+        let total: u64 = pieces.iter().map(|(_, pieces)| pieces).sum();
+        let shares: BTreeMap<String, f64> = pieces
+            .iter()
+            .map(|(name, pieces)| (name.to_string(), *pieces as f64 / total.max(1) as f64))
+            .collect();
+        let norm = shares.values().map(|share| share * share).sum();
+        Reference {
+            shares,
+            weight,
+            norm,
+        }
+    }
+
+    fn share(&self, name: &str) -> f64 {
+        self.shares.get(name).copied().unwrap_or(0.0)
+    }
+
+    /// Weighted squared distance of the shares of a mix of `pieces` pieces, from the sum of
+    /// its squared pieces and the sum of its pieces times the reference's shares
+    fn cost(&self, square_sum: f64, dot: f64, pieces: f64) -> f64 {
+        if pieces == 0.0 {
+            return 0.0;
+        }
+        self.weight * (square_sum / (pieces * pieces) - 2.0 * dot / pieces + self.norm)
+    }
+
+    fn evaluate(&self, proposal: &Proposal) -> f64 {
+        let pieces = proposal.0.values().sum::<u64>() as f64;
+        let square_sum = proposal.0.values().map(|p| (*p * *p) as f64).sum();
+        let dot = proposal
+            .0
+            .iter()
+            .map(|(name, p)| *p as f64 * self.share(name))
+            .sum();
+        self.cost(square_sum, dot, pieces)
+    }
+}
+
+/// Ingredients that expire soon, which the result should use as much as the ratio allows
+#[derive(Clone, Debug)]
+struct Expiry {
+    // from 1 if the ingredient expires today towards 0
+    urgency: BTreeMap<String, f64>,
+    // cost of the share of the pieces that isn't urgent
+    weight: f64,
+}
+
+impl Expiry {
+    fn urgency(&self, name: &str) -> f64 {
+        self.urgency.get(name).copied().unwrap_or(0.0)
+    }
+
+    /// Weighted share of the non-urgent pieces of a mix of `pieces` pieces, from the sum of its
+    /// pieces times their urgency
+    fn cost(&self, dot: f64, pieces: f64) -> f64 {
+        if pieces == 0.0 {
+            return 0.0;
+        }
+        self.weight * (1.0 - dot / pieces)
+    }
+
+    fn dot(&self, proposal: &Proposal) -> f64 {
+        proposal
+            .0
+            .iter()
+            .map(|(name, p)| *p as f64 * self.urgency(name))
+            .sum()
+    }
+
+    fn evaluate(&self, proposal: &Proposal) -> f64 {
+        self.cost(self.dot(proposal), proposal.kcal() as f64)
+    }
+}
+
+/// Pieces of the loaded ingredients of a plan in grams, and the names of the others
+fn plan_pieces<'a>(
+    gram_proposal: &'a Proposal,
+    target: &Target,
+    raw_ingredients: &RawIngredients,
+    steps: usize,
+) -> (Vec<(&'a String, u64)>, Vec<String>) {
+    let (loaded, missing): (Vec<_>, Vec<_>) = gram_proposal
+        .0
+        .iter()
+        .partition(|(name, _)| raw_ingredients.0.contains_key(*name));
+    let pieces = loaded
+        .into_iter()
+        .filter(|(name, _)| !raw_ingredients.0[*name].is_kcal_free())
+        .map(|(name, g)| (name, raw_ingredients.0[name].pieces(*g, target, steps)))
+        .collect();
+    (
+        pieces,
+        missing.into_iter().map(|(name, _)| name.clone()).collect(),
+    )
+}
+
+impl TargetConstraints {
+    fn new() -> TargetConstraints {
+        TargetConstraints {
+            exact: Proposal(BTreeMap::new()),
+            at_least: Proposal(BTreeMap::new()),
+            at_most: Proposal(BTreeMap::new()),
+            at_least_if_used: Proposal(BTreeMap::new()),
+            portion: Proposal(BTreeMap::new()),
+            max_weight_share: BTreeMap::new(),
+            max_sodium: None,
+            max_glycemic_load: None,
+            max_water: None,
+            max_caffeine: None,
+            prep_minutes: BTreeMap::new(),
+            max_prep_minutes: None,
+            start: Proposal(BTreeMap::new()),
+            reference: None,
+            expiry: None,
+            step_band: None,
+        }
+    }
+
+    /// Cost of the proposal beyond the target's: its distance from the reference plan and its
+    /// pieces of ingredients that don't expire soon
+    fn evaluate(&self, proposal: &Proposal) -> f64 {
+        self.reference
+            .as_ref()
+            .map_or(0.0, |reference| reference.evaluate(proposal))
+            + self
+                .expiry
+                .as_ref()
+                .map_or(0.0, |expiry| expiry.evaluate(proposal))
+    }
+
+    /// Minutes of preparing the ingredients
+    fn prep_time<'a>(&self, used: impl IntoIterator<Item = &'a String>) -> f64 {
+        used.into_iter()
+            .filter_map(|name| self.prep_minutes.get(name))
+            .sum()
+    }
+
+    /// Whether preparing the ingredients takes longer than the target allows
+    fn exceeds_prep_time<'a>(&self, used: impl IntoIterator<Item = &'a String>) -> bool {
+        self.max_prep_minutes
+            .is_some_and(|max| self.prep_time(used) > max)
+    }
+
+    /// Fewest and most pieces of the result, `steps` unless the target has a kcal band
+    fn step_band(&self, steps: usize) -> (usize, usize) {
+        self.step_band.unwrap_or((steps, steps))
+    }
+
+    /// Starts the search from an earlier plan in grams, scaled to `WARM_START_SHARE` of the
+    /// pieces, and returns the ingredients of the plan that aren't loaded
+    fn start_from(
+        &mut self,
+        gram_proposal: &Proposal,
+        target: &Target,
+        raw_ingredients: &RawIngredients,
+        steps: usize,
+    ) -> Vec<String> {
+        let (pieces, missing) = plan_pieces(gram_proposal, target, raw_ingredients, steps);
+        let total: u64 = pieces.iter().map(|(_, pieces)| pieces).sum();
+        let scale = WARM_START_SHARE * steps as f64 / total.max(1) as f64;
+        for (name, pieces) in pieces {
+            let portion = self.portion.0.get(name).copied().unwrap_or(1);
+            let mut pieces = (pieces as f64 * scale) as u64 / portion * portion;
+            if let Some(at_most) = self.at_most.0.get(name) {
+                pieces = pieces.min(*at_most);
+            }
+            self.start.0.insert(name.clone(), pieces);
+        }
+        missing
+    }
+
+    /// Adds ingredients with a batch size in portions such that the plan's `days` use whole or
+    /// half batches of them
+    fn cook_in_batches(
+        &mut self,
+        target: &Target,
+        raw_ingredients: &RawIngredients,
+        steps: usize,
+        days: u64,
+    ) {
+        for (name, ingredient) in raw_ingredients.searched() {
+            if let Some(batch_g) = ingredient.batch_g {
+                let batch_pieces = ingredient.pieces(batch_g, target, steps) as f64;
+                let pieces = (batch_pieces / (2 * days) as f64).round() as u64;
+                self.portion.0.insert(name.clone(), pieces.max(1));
+            }
+        }
+    }
+
+    /// Penalizes results whose shares of the ingredients differ from the ones of a plan in
+    /// grams, and returns the ingredients of the plan that aren't loaded
+    fn keep_close_to(
+        &mut self,
+        gram_proposal: &Proposal,
+        target: &Target,
+        raw_ingredients: &RawIngredients,
+        steps: usize,
+        weight: f64,
+    ) -> Vec<String> {
+        let (pieces, missing) = plan_pieces(gram_proposal, target, raw_ingredients, steps);
+        self.reference = Some(Reference::new(&pieces, weight));
+        missing
+    }
+
+    /// Collects the target's constraints and the bounds declared by the ingredients themselves
+    fn from_target(
+        target: &Target,
+        raw_ingredients: &RawIngredients,
+        optimization_steps: usize,
+    ) -> Result<TargetConstraints, Error> {
+        let mut constraints = TargetConstraints::new();
+
+        let insert_pieces = |constraints: &Option<Vec<TargetConstraint>>,
+                             insert_target: &mut Proposal|
+         -> Result<(), Error> {
+            for constraint in constraints.iter().flatten() {
+                let constraint_pieces =
+                    constraint.to_pieces(target, raw_ingredients, optimization_steps)?;
+                if raw_ingredients.0[&constraint.name].is_kcal_free() {
+                    continue;
+                }
+                insert_target
+                    .0
+                    .insert(constraint.name.to_string(), constraint_pieces);
+            }
+            Ok(())
+        };
+        insert_pieces(&target.constraint_exact, &mut constraints.exact)?;
+        insert_pieces(&target.constraint_at_least, &mut constraints.at_least)?;
+        insert_pieces(&target.constraint_at_most, &mut constraints.at_most)?;
+        for constraint in target.constraint_max_weight_pct.iter().flatten() {
+            match raw_ingredients.0.get(&constraint.name) {
+                None => return Err(raw_ingredients.missing(&constraint.name)),
+                Some(ingredient) if ingredient.is_kcal_free() => continue,
+                Some(_) => {}
+            }
+            constraints
+                .max_weight_share
+                .insert(constraint.name.clone(), constraint.pct / 100.0);
+        }
+        // kcal are pieces, so a share of them is at most as many of the pieces
+        for constraint in target.constraint_max_kcal_pct.iter().flatten() {
+            match raw_ingredients.0.get(&constraint.name) {
+                None => return Err(raw_ingredients.missing(&constraint.name)),
+                Some(ingredient) if ingredient.is_kcal_free() => continue,
+                Some(_) => {}
+            }
+            let pieces = (constraint.pct / 100.0 * optimization_steps as f64).floor() as u64;
+            let at_most = constraints
+                .at_most
+                .0
+                .entry(constraint.name.clone())
+                .or_insert(pieces);
+            *at_most = (*at_most).min(pieces);
+        }
+        // converts a limit of the whole plan to the units of the mix totals
+        let limit = |max: Option<f64>,
+                     nutrient: &'static str,
+                     amount: fn(&Ingredient) -> f64|
+         -> Result<Option<f64>, Error> {
+            let max = match max {
+                Some(max) => max,
+                None => return Ok(None),
+            };
+            // the constrained amounts are in the mix no matter what
+            let constrained = target
+                .constraint_exact
+                .iter()
+                .chain(&target.constraint_at_least)
+                .flatten();
+            let constrained_amount: f64 = constrained
+                .map(|constraint| {
+                    let ingredient = &raw_ingredients.0[&constraint.name];
+                    constraint.g as f64 * amount(ingredient) / ingredient.g as f64
+                })
+                .sum();
+            if constrained_amount > max {
+                return Err(Error::ConstraintsExceedLimit {
+                    nutrient,
+                    amount: constrained_amount.round(),
+                    max,
+                });
+            }
+            Ok(Some(max / target.kcal_per_piece(optimization_steps)))
+        };
+        constraints.max_sodium = limit(target.max_sodium_mg, "mg sodium", |ingredient| {
+            ingredient.sodium_mg.unwrap_or(0.0)
+        })?;
+        constraints.max_glycemic_load = limit(
+            target.max_glycemic_load,
+            "glycemic load",
+            Ingredient::glycemic_load,
+        )?;
+        constraints.max_water = limit(target.max_water_ml, "ml water", |ingredient| {
+            ingredient.water_ml.unwrap_or(0.0)
+        })?;
+        constraints.max_caffeine = limit(target.max_caffeine_mg, "mg caffeine", |ingredient| {
+            ingredient.caffeine_mg.unwrap_or(0.0)
+        })?;
+        let (min_kcal, max_kcal) = target.kcal_band();
+        if min_kcal < target.kcal as f64 || max_kcal > target.kcal as f64 {
+            let kcal_per_piece = target.kcal_per_piece(optimization_steps);
+            constraints.step_band = Some((
+                (min_kcal / kcal_per_piece).ceil() as usize,
+                (max_kcal / kcal_per_piece).floor() as usize,
+            ));
+        }
+
+        for (name, ingredient) in raw_ingredients.searched() {
+            if let Some(share) = target.max_single_ingredient_kcal_share {
+                let pieces = (share * optimization_steps as f64).floor() as u64;
+                let at_most = constraints
+                    .at_most
+                    .0
+                    .entry(name.to_string())
+                    .or_insert(pieces);
+                *at_most = (*at_most).min(pieces);
+            }
+            if let Some(portion_g) = ingredient.portion_g {
+                let pieces = ingredient.pieces(portion_g, target, optimization_steps);
+                constraints
+                    .portion
+                    .0
+                    .insert(name.to_string(), pieces.max(1));
+            }
+            if let Some(max_g) = ingredient.max_g {
+                let pieces = ingredient.pieces(max_g, target, optimization_steps);
+                let at_most = constraints
+                    .at_most
+                    .0
+                    .entry(name.to_string())
+                    .or_insert(pieces);
+                *at_most = (*at_most).min(pieces);
+            }
+            if let Some(min_g) = ingredient.min_g {
+                let pieces = ingredient.pieces(min_g, target, optimization_steps);
+                constraints
+                    .at_least_if_used
+                    .0
+                    .insert(name.to_string(), pieces);
+                // an at least constraint already uses the ingredient
+                if let Some(at_least) = constraints.at_least.0.get_mut(name) {
+                    *at_least = (*at_least).max(pieces);
+                }
+            }
+            if let Some(minutes) = ingredient.prep_minutes {
+                constraints.prep_minutes.insert(name.to_string(), minutes);
+            }
+        }
+        if let Some(max) = target.max_prep_minutes {
+            // the constrained ingredients are prepared no matter what
+            let constrained: BTreeSet<&String> = target
+                .constraint_exact
+                .iter()
+                .chain(&target.constraint_at_least)
+                .flatten()
+                .filter(|constraint| constraint.g > 0)
+                .map(|constraint| &constraint.name)
+                .collect();
+            let minutes = constraints.prep_time(constrained);
+            if minutes > max {
+                return Err(Error::ConstraintsExceedLimit {
+                    nutrient: "prep minutes",
+                    amount: minutes.round(),
+                    max,
+                });
+            }
+            constraints.max_prep_minutes = Some(max);
+        }
+        Ok(constraints)
+    }
+}
+
+#[derive(Debug, Default)]
+struct NormalizedTarget {
+    // in ratio
+    carb: f64,
+    // in ratio
+    fat: f64,
+    // in ratio
+    protein: f64,
+    // largest share of the kcal from saturated fat
+    sat_fat_kcal_share: Option<f64>,
+    // largest share of the kcal from sugar
+    sugar_kcal_share: Option<f64>,
+    // least water and caffeine per kcal of the mix
+    min_water_ml_per_kcal: Option<f64>,
+    min_caffeine_mg_per_kcal: Option<f64>,
+    // least share of the protein from ingredients with a high protein score
+    min_quality_protein_share: Option<f64>,
+    // least leucine and essential amino acids per kcal of the mix
+    min_leucine_g_per_kcal: Option<f64>,
+    min_eaa_g_per_kcal: Option<f64>,
+    // most omega-6 per g of omega-3 of the mix
+    max_omega6_to_omega3: Option<f64>,
+    weights: MacroWeights,
+    // cost of the concentration of the mix's kcal in few ingredients
+    diversity_weight: f64,
+}
+
+fn square(x: f64) -> f64 {
+    x * x
+}
+impl NormalizedTarget {
+    /// Using squared difference, lower is better.
+    ///
+    /// Each macro's squared error is multiplied by its weight. Alcohol counts towards the macro
+    /// grams, so it's penalized like a macro the target has none of. Sub-macros above their limit
+    /// are penalized by the excess share of the kcal, water, caffeine and amino acids below their
+    /// minimum by the missing share of it, high-quality protein by the missing share of the
+    /// protein and omega-6 above its ratio to omega-3 by the excess share of the omega-6,
+    /// weighted so that they are only missed if the ratio can't be met otherwise.
+    ///
+    /// With a diversity weight, the concentration of the pieces in few ingredients is penalized
+    /// too.
+    fn evaluate(&self, proposal: &Proposal, ingredients: &Ingredients) -> f64 {
+        let square_sum = proposal.0.values().map(|p| (*p * *p) as f64).sum();
+        self.evaluate_totals(&MixTotals::new(proposal, ingredients))
+            + self.concentration_cost(square_sum, proposal.kcal() as f64)
+    }
+
+    /// Weighted chance that two pieces of the mix drawn at random are of the same ingredient
+    /// (the Simpson index), from the sum of the squared pieces. It's 1 for a single ingredient
+    /// and 1/n for n ingredients in equal amounts.
+    fn concentration_cost(&self, square_sum: f64, pieces: f64) -> f64 {
+        if self.diversity_weight == 0.0 || pieces == 0.0 {
+            return 0.0;
+        }
+        self.diversity_weight * square_sum / (pieces * pieces)
+    }
+
+    /// Same as `evaluate` without the concentration, in O(1) from the running totals of a
+    /// proposal
+    fn evaluate_totals(&self, totals: &MixTotals) -> f64 {
+        let sum = totals.carb + totals.fat + totals.protein + totals.alcohol;
+        let excess = |limit: Option<f64>, g: f64, kcal_per_g: f64| match limit {
+            Some(limit) => (g * kcal_per_g / totals.pieces as f64 - limit).max(0.0),
+            None => 0.0,
+        };
+        // relative to the minimum, so that water, caffeine and amino acids count alike
+        let shortfall = |min: Option<f64>, amount: f64| match min {
+            Some(min) if min > 0.0 => (1.0 - amount / totals.pieces as f64 / min).max(0.0),
+            _ => 0.0,
+        };
+        let quality_protein_shortfall = match self.min_quality_protein_share {
+            Some(min) if totals.protein > 0.0 => {
+                (min - totals.quality_protein / totals.protein).max(0.0)
+            }
+            _ => 0.0,
+        };
+        let omega6_excess = match self.max_omega6_to_omega3 {
+            Some(max) if totals.omega6_g > 0.0 => {
+                (1.0 - max * totals.omega3_g / totals.omega6_g).max(0.0)
+            }
+            _ => 0.0,
+        };
+        let weights = &self.weights;
+        weights.carb * square(self.carb - totals.carb / sum)
+            + weights.fat * square(self.fat - totals.fat / sum)
+            + weights.protein * square(self.protein - totals.protein / sum)
+            + square(totals.alcohol / sum)
+            + SUB_MACRO_PENALTY
+                * (square(excess(
+                    self.sat_fat_kcal_share,
+                    totals.sat_fat,
+                    KCAL_PER_G_FAT,
+                )) + square(excess(self.sugar_kcal_share, totals.sugar, KCAL_PER_G_CARB))
+                    + square(shortfall(self.min_water_ml_per_kcal, totals.water_ml))
+                    + square(shortfall(self.min_caffeine_mg_per_kcal, totals.caffeine_mg))
+                    + square(quality_protein_shortfall)
+                    + square(shortfall(self.min_leucine_g_per_kcal, totals.leucine_g))
+                    + square(shortfall(self.min_eaa_g_per_kcal, totals.eaa_g))
+                    + square(omega6_excess))
+    }
+}
+
+fn optimize(
+    target: &NormalizedTarget,
+    constraints: TargetConstraints,
+    ingredients: &Ingredients,
+    steps: usize,
+) -> Result<Proposal, Error> {
+    optimize_explained(target, constraints, ingredients, steps, None, None, None)
+}
+
+/// How often the search runs, the runs after the first shuffle the ingredients with an RNG
+/// seeded with `seed`
+#[derive(Clone, Copy, Debug)]
+struct Restarts {
+    count: usize,
+    seed: u64,
+}
+
+/// Runs the search `restarts.count` times and returns the best proposal.
+///
+/// The first run is the same as `optimize`, the others shuffle the ingredients and break near
+/// ties in that order.
+fn optimize_restarts(
+    target: &NormalizedTarget,
+    constraints: TargetConstraints,
+    ingredients: &Ingredients,
+    steps: usize,
+    restarts: Restarts,
+    explanation: Option<&mut Explanation>,
+    progress: Option<&Progress>,
+) -> Result<Proposal, Error> {
+    let mut rng = Pcg64::seed_from_u64(restarts.seed);
+    let mut best: Option<(Proposal, f64, Explanation)> = None;
+    let mut first_error = None;
+    for restart in 0..restarts.count.max(1) {
+        let mut run_explanation = Explanation::default();
+        if let Some(progress) = progress {
+            progress.start_run(restart, steps);
+        }
+        let result = optimize_explained(
+            target,
+            constraints.clone(),
+            ingredients,
+            steps,
+            explanation.as_ref().map(|_| &mut run_explanation),
+            if restart == 0 { None } else { Some(&mut rng) },
+            progress,
+        );
+        let proposal = match result {
+            Ok(proposal) => proposal,
+            Err(e) => {
+                first_error.get_or_insert(e);
+                continue;
+            }
+        };
+        let cost = target.evaluate(&proposal, ingredients) + constraints.evaluate(&proposal);
+        if best
+            .as_ref()
+            .is_none_or(|(_, best_cost, _)| cost < *best_cost)
+        {
+            best = Some((proposal, cost, run_explanation));
+        }
+    }
+    match best {
+        Some((proposal, _, run_explanation)) => {
+            if let Some(explanation) = explanation {
+                *explanation = run_explanation;
+            }
+            Ok(proposal)
+        }
+        None => Err(first_error.unwrap()),
+    }
+}
+
+/// Like `optimize`, but records every step of the search in `explanation`.
+///
+/// With an `rng`, the ingredients are considered in random order and candidates whose cost is
+/// almost the best are tied, the first in that order wins.
+fn optimize_explained(
+    target: &NormalizedTarget,
+    constraints: TargetConstraints,
+    ingredients: &Ingredients,
+    steps: usize,
+    mut explanation: Option<&mut Explanation>,
+    rng: Option<&mut Pcg64>,
+    progress: Option<&Progress>,
+) -> Result<Proposal, Error> {
+    let mut proposal = Proposal(BTreeMap::new());
+    let mut assigned_pieces = 0;
+
+    for name in ingredients.0.keys() {
+        proposal.0.insert(name.to_string(), 0);
+    }
+    let mut insert_constraints = |constraints: &Proposal| {
+        for (name, pieces) in &constraints.0 {
+            proposal.0.insert(name.to_string(), *pieces);
+            assigned_pieces += *pieces;
+        }
+    };
+    feasibility::check(&constraints, ingredients, steps)?;
+    insert_constraints(&constraints.at_least);
+    insert_constraints(&constraints.exact);
+    // an earlier plan fills what the constraints leave
+    for (name, pieces) in &constraints.start.0 {
+        let current = proposal.0[name];
+        if constraints.exact.0.contains_key(name) || *pieces <= current {
+            continue;
+        }
+        let added = (*pieces - current).min((steps as u64).saturating_sub(assigned_pieces));
+        proposal.0.insert(name.to_string(), current + added);
+        assigned_pieces += added;
+    }
+    let mut assigned = assigned_pieces as usize;
+    let (min_steps, max_steps) = constraints.step_band(steps);
+    // weight of the proposal in grams per kcal piece
+    let mut weight: f64 = proposal
+        .0
+        .iter()
+        .map(|(name, pieces)| *pieces as f64 * ingredients.0[name].g)
+        .sum();
+    // once adding a step barely changes the cost, ingredients are added several steps at a time
+    let mut stride = 1;
+    let mut last_cost = None;
+    let mut totals = MixTotals::new(&proposal, ingredients);
+    // the sum of the squared pieces and of the pieces times the reference's shares
+    let reference = constraints.reference.as_ref();
+    let mut square_sum: f64 = proposal.0.values().map(|p| (*p * *p) as f64).sum();
+    let mut reference_dot = reference.map_or(0.0, |reference| {
+        proposal
+            .0
+            .iter()
+            .map(|(name, p)| *p as f64 * reference.share(name))
+            .sum()
+    });
+    let reference_cost = |square_sum: f64, dot: f64, assigned: usize| {
+        reference.map_or(0.0, |reference| {
+            reference.cost(square_sum, dot, assigned as f64)
+        })
+    };
+    // the sum of the pieces times their urgency
+    let expiry = constraints.expiry.as_ref();
+    let mut expiry_dot = expiry.map_or(0.0, |expiry| expiry.dot(&proposal));
+    let expiry_cost =
+        |dot: f64, assigned: usize| expiry.map_or(0.0, |expiry| expiry.cost(dot, assigned as f64));
+    let mut cost_before = if assigned > 0 {
+        Some(
+            target.evaluate_totals(&totals)
+                + reference_cost(square_sum, reference_dot, assigned)
+                + expiry_cost(expiry_dot, assigned)
+                + target.concentration_cost(square_sum, assigned as f64),
+        )
+    } else {
+        None
+    };
+    // don't consider ingredients in the initial_proposal
+    let mut names: Vec<&String> = ingredients
+        .0
+        .keys()
+        .filter(|name| !constraints.exact.0.contains_key(*name))
+        .collect();
+    // the least of a limited nutrient any piece can add
+    let least = |amount: fn(&NormalizedIngredient) -> f64| {
+        names
+            .iter()
+            .map(|name| amount(&ingredients.0[*name]))
+            .fold(f64::INFINITY, f64::min)
+    };
+    let least_sodium = least(|ingredient| ingredient.sodium_mg);
+    let least_glycemic_load = least(|ingredient| ingredient.glycemic_load);
+    let least_water = least(|ingredient| ingredient.water_ml);
+    let least_caffeine = least(|ingredient| ingredient.caffeine_mg);
+    let tie_cost_change = match rng {
+        Some(rng) => {
+            names.shuffle(rng);
+            RESTART_TIE_COST_CHANGE
+        }
+        None => 0.0,
+    };
+    while assigned < max_steps {
+        let prep_time = constraints.prep_time(
+            proposal
+                .0
+                .iter()
+                .filter(|(_, pieces)| **pieces > 0)
+                .map(|(name, _)| name),
+        );
+        let candidate = |name: &&String| {
+            let name: &String = name;
+            let blocked_by = |pieces: u64| {
+                if constraints.at_most.0.contains_key(name)
+                    && proposal.0[name] + pieces > constraints.at_most.0[name]
+                {
+                    return Some(BlockReason::AtMost);
+                }
+                // the rest of the pieces add to the limited nutrients too, at least as much as
+                // the ingredient with the least of them
+                let rest = min_steps.saturating_sub(assigned + pieces as usize) as f64;
+                let exceeds = |max: Option<f64>, total: f64, amount: f64, least: f64| {
+                    max.is_some_and(|max| total + pieces as f64 * amount + rest * least > max)
+                };
+                let ingredient = &ingredients.0[name];
+                if exceeds(
+                    constraints.max_sodium,
+                    totals.sodium_mg,
+                    ingredient.sodium_mg,
+                    least_sodium,
+                ) {
+                    return Some(BlockReason::Sodium);
+                }
+                if exceeds(
+                    constraints.max_glycemic_load,
+                    totals.glycemic_load,
+                    ingredient.glycemic_load,
+                    least_glycemic_load,
+                ) {
+                    return Some(BlockReason::GlycemicLoad);
+                }
+                if exceeds(
+                    constraints.max_water,
+                    totals.water_ml,
+                    ingredient.water_ml,
+                    least_water,
+                ) {
+                    return Some(BlockReason::Water);
+                }
+                if exceeds(
+                    constraints.max_caffeine,
+                    totals.caffeine_mg,
+                    ingredient.caffeine_mg,
+                    least_caffeine,
+                ) {
+                    return Some(BlockReason::Caffeine);
+                }
+                // an unused ingredient adds its prep time
+                if proposal.0[name] == 0
+                    && constraints
+                        .max_prep_minutes
+                        .is_some_and(|max| prep_time + constraints.prep_time([name]) > max)
+                {
+                    return Some(BlockReason::PrepTime);
+                }
+                if let Some(max_share) = constraints.max_weight_share.get(name) {
+                    let g = ingredients.0[name].g;
+                    let ingredient_weight = (proposal.0[name] + pieces) as f64 * g;
+                    if ingredient_weight > max_share * (weight + pieces as f64 * g) {
+                        return Some(BlockReason::MaxWeightShare);
+                    }
+                }
+                None
+            };
+            let portion = constraints.portion.0.get(name).copied().unwrap_or(1);
+            // an unused ingredient with a minimum amount is added all at once
+            let mut pieces = match constraints.at_least_if_used.0.get(name) {
+                Some(min_pieces) if proposal.0[name] == 0 => {
+                    portion * min_pieces.div_ceil(portion).max(1)
+                }
+                _ => portion,
+            };
+            if assigned + pieces as usize > max_steps {
+                return Err(BlockReason::Remaining);
+            }
+            if let Some(reason) = blocked_by(pieces) {
+                return Err(reason);
+            }
+            // a minimum amount is already a large step
+            if pieces == portion {
+                let strided = pieces * stride;
+                if assigned + strided as usize <= max_steps && blocked_by(strided).is_none() {
+                    pieces = strided;
+                }
+            }
+            let candidate_totals = totals.with(&ingredients.0[name], pieces);
+            let (current, added) = (proposal.0[name] as f64, pieces as f64);
+            let candidate_square_sum = square_sum + 2.0 * added * current + added * added;
+            let candidate_assigned = assigned + pieces as usize;
+            let candidate_reference_cost = reference_cost(
+                candidate_square_sum,
+                reference_dot + added * reference.map_or(0.0, |r| r.share(name)),
+                candidate_assigned,
+            );
+            let candidate_expiry_cost = expiry_cost(
+                expiry_dot + added * expiry.map_or(0.0, |e| e.urgency(name)),
+                candidate_assigned,
+            );
+            Ok((
+                pieces,
+                target.evaluate_totals(&candidate_totals)
+                    + candidate_reference_cost
+                    + candidate_expiry_cost
+                    + target.concentration_cost(candidate_square_sum, candidate_assigned as f64),
+            ))
+        };
+        // spreading the candidates over threads only pays off for large pantries
+        let candidates: Vec<_> = if names.len() >= MIN_PARALLEL_CANDIDATES {
+            names.par_iter().map(candidate).collect()
+        } else {
+            names.iter().map(candidate).collect()
+        };
+
+        let mut blocked = Vec::new();
+        // whether an ingredient could only not be added because its portion doesn't fit anymore
+        let mut portion_too_large = false;
+        for (&name, candidate) in names.iter().zip(&candidates) {
+            if let Err(reason) = candidate {
+                portion_too_large |= *reason == BlockReason::Remaining;
+                blocked.push((name.to_string(), *reason));
+            }
+        }
+        // optimize greedily
+        let min_cost = candidates
+            .iter()
+            .filter_map(|candidate| candidate.as_ref().ok())
+            .map(|(_, cost)| *cost)
+            .fold(f64::INFINITY, f64::min);
+        let best_ingredient = names
+            .iter()
+            .zip(&candidates)
+            .find_map(|(&name, candidate)| match candidate {
+                Ok((pieces, cost)) if *cost <= min_cost + tie_cost_change * min_cost => {
+                    Some((name, *pieces, *cost))
+                }
+                _ => None,
+            });
+        let (best_ingredient, pieces, cost) = match best_ingredient {
+            Some(best_ingredient) => best_ingredient,
+            // the rest is smaller than a portion
+            None if portion_too_large || assigned >= min_steps => break,
+            None => return Err(Error::Infeasible { assigned, steps }),
+        };
+        // in a kcal band, the result stops short of the target's kcal if adding makes the fit
+        // noticeably worse, and only goes beyond it while that noticeably improves the fit
+        if let Some(cost_before) = cost_before {
+            let stop = if assigned >= steps {
+                cost >= cost_before * (1.0 - KCAL_BAND_MIN_IMPROVEMENT)
+            } else {
+                assigned >= min_steps && cost > cost_before * (1.0 + KCAL_BAND_MIN_IMPROVEMENT)
+            };
+            if stop {
+                break;
+            }
+        }
+        let current = proposal.0[best_ingredient] as f64;
+        square_sum += 2.0 * pieces as f64 * current + (pieces * pieces) as f64;
+        reference_dot += pieces as f64 * reference.map_or(0.0, |r| r.share(best_ingredient));
+        expiry_dot += pieces as f64 * expiry.map_or(0.0, |e| e.urgency(best_ingredient));
+        *proposal.0.get_mut(best_ingredient).unwrap() += pieces;
+        totals.add(&ingredients.0[best_ingredient], pieces);
+        assigned += pieces as usize;
+        weight += pieces as f64 * ingredients.0[best_ingredient].g;
+        if let Some(progress) = progress {
+            progress.update(assigned);
+        }
+
+        if let Some(explanation) = explanation.as_deref_mut() {
+            explanation.steps.push(explain::Step {
+                ingredient: best_ingredient.to_string(),
+                pieces,
+                cost_before,
+                cost_after: cost,
+                blocked,
+            });
+        }
+        cost_before = Some(cost);
+        let plateau = last_cost.is_some_and(|last_cost: f64| {
+            (last_cost - cost).abs() <= PLATEAU_COST_CHANGE * last_cost
+        });
+        // a stride only moves the mix by a small fraction, and the last pieces are added one
+        // step at a time again to fine tune the mix
+        let max_stride =
+            (assigned.min(steps.saturating_sub(assigned)) as u64 / MIN_PIECES_PER_STRIDE).max(1);
+        stride = if plateau {
+            (stride * 2).min(max_stride)
+        } else {
+            1
+        };
+        last_cost = Some(cost);
+    }
+    Ok(proposal)
+}
+
+/// Reads a whole file into a string
+pub fn read_file(filepath: &Path) -> Result<String, Error> {
+    let read_error = |source| Error::Read {
+        path: filepath.to_path_buf(),
+        source,
+    };
+    let file = File::open(filepath).map_err(read_error)?;
+    let mut buffered_reader = BufReader::new(file);
+    let mut contents = String::new();
+    buffered_reader
+        .read_to_string(&mut contents)
+        .map_err(read_error)?;
+
+    Ok(contents)
+}
+
+/// Reads a target file and resolves its macros in grams
+fn read_target_file(path: &Path) -> Result<Target, Error> {
+    let mut target: Target = read_data(path)?;
+    target.resolve_grams()?;
+    Ok(target)
+}
+
+/// Reads and parses a toml file, or a json file if it has a .json extension
+fn read_data<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T, Error> {
+    let contents = read_file(path)?;
+    if path.extension().is_some_and(|ext| ext == "json") {
+        serde_json::from_str(&contents).map_err(|source| Error::ParseJson {
+            path: path.to_path_buf(),
+            source,
+        })
+    } else {
+        toml::from_str(&contents).map_err(|source| Error::Parse {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+}
+
+/// Writes json if the file ends in .json, otherwise toml
+fn write_data<T: Serialize>(path: &Path, value: &T) -> Result<(), Error> {
+    if path.extension().is_some_and(|ext| ext == "json") {
+        let contents = serde_json::to_string_pretty(value)?;
+        std::fs::write(path, contents + "\n").map_err(|source| Error::Write {
+            path: path.to_path_buf(),
+            source,
+        })
+    } else {
+        write_toml(path, value)
+    }
+}
+
+fn write_toml<T: Serialize>(path: &Path, value: &T) -> Result<(), Error> {
+    let contents = toml::to_string(value).map_err(|source| Error::Serialize {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    std::fs::write(path, contents).map_err(|source| Error::Write {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Whether a file in an ingredients directory is loaded as an ingredient
+fn is_data_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext == "toml" || ext == "json")
+}
+
+/// Recursively collects the data files below `dir`
+fn data_files(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<(), Error> {
+    let read_error = |source| Error::Read {
+        path: dir.to_path_buf(),
+        source,
+    };
+    for entry in std::fs::read_dir(dir).map_err(read_error)? {
+        let path = entry.map_err(read_error)?.path();
+        if path.is_dir() {
+            data_files(&path, paths)?;
+        } else if path.is_file() && is_data_file(&path) {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Collects the ingredient files given on the command line and in the ingredients directory
+fn ingredient_paths(args: &IngredientArgs) -> Result<Vec<PathBuf>, Error> {
+    Ok(pantry_sources(args)?.into_iter().flatten().collect())
+}
+
+/// The ingredient files of each ingredients directory, and then the files given on the command line
+fn pantry_sources(args: &IngredientArgs) -> Result<Vec<Vec<PathBuf>>, Error> {
+    let mut sources = Vec::new();
+    for dir in &args.ingredients_dir {
+        let mut dir_paths = Vec::new();
+        data_files(dir, &mut dir_paths)?;
+        dir_paths.sort();
+        sources.push(dir_paths);
+    }
+    sources.push(args.files.clone());
+    Ok(sources)
+}
+
+/// An ingredient file either describes an ingredient or a recipe made of other ingredients
+enum PantryItem {
+    Ingredient(Box<Ingredient>),
+    Recipe(Recipe),
+}
+
+impl PantryItem {
+    fn name(&self) -> &str {
+        match self {
+            PantryItem::Ingredient(ingredient) => &ingredient.name,
+            PantryItem::Recipe(recipe) => &recipe.name,
+        }
+    }
+
+    fn rename(&mut self, name: String) {
+        match self {
+            PantryItem::Ingredient(ingredient) => ingredient.name = name,
+            PantryItem::Recipe(recipe) => recipe.name = name,
+        }
+    }
+}
+
+/// Item of the pantry with the files that define it, of which the last one is used
+struct OverlaidItem {
+    paths: Vec<PathBuf>,
+    item: PantryItem,
+}
+
+/// Reads the ingredient files, where an item replaces those of the same name in earlier
+/// directories, and items of the same name in one directory are handled by `--on-duplicate`
+fn overlay_pantry(args: &IngredientArgs) -> Result<BTreeMap<String, OverlaidItem>, Error> {
+    let mut items: BTreeMap<String, OverlaidItem> = BTreeMap::new();
+    for paths in pantry_sources(args)? {
+        // file of each name in this directory
+        let mut named: BTreeMap<String, PathBuf> = BTreeMap::new();
+        for path in paths {
+            let mut item = read_pantry_item(&path)?;
+            if let Some(first) = named.get(item.name()) {
+                match args.on_duplicate {
+                    OnDuplicate::Error => {
+                        return Err(Error::DuplicateName {
+                            name: item.name().to_string(),
+                            first: first.clone(),
+                            second: path,
+                        })
+                    }
+                    OnDuplicate::First => continue,
+                    OnDuplicate::Last => {}
+                    OnDuplicate::Rename => {
+                        let name = (2..)
+                            .map(|n| format!("{} {}", item.name(), n))
+                            .find(|name| !named.contains_key(name) && !items.contains_key(name))
+                            .unwrap();
+                        eprintln!(
+                            "warning: \"{}\" of {} is also the name of {}, it's called \"{}\"",
+                            item.name(),
+                            path.display(),
+                            first.display(),
+                            name
+                        );
+                        item.rename(name);
+                    }
+                }
+            }
+            named.insert(item.name().to_string(), path.clone());
+            match items.get_mut(item.name()) {
+                Some(overlaid) => {
+                    overlaid.paths.push(path);
+                    overlaid.item = item;
+                }
+                None => {
+                    let paths = vec![path];
+                    items.insert(item.name().to_string(), OverlaidItem { paths, item });
+                }
+            }
+        }
+    }
+    Ok(items)
+}
+
+#[derive(Deserialize)]
+struct PantryItemKind {
+    components: Option<serde::de::IgnoredAny>,
+}
+
+fn read_pantry_item(path: &Path) -> Result<PantryItem, Error> {
+    let kind: PantryItemKind = read_data(path)?;
+    if kind.components.is_some() {
+        Ok(PantryItem::Recipe(read_data(path)?))
+    } else {
+        let mut ingredient: Ingredient = read_data(path)?;
+        ingredient.resolve_per()?;
+        if ingredient.kcal == 0.0 {
+            ingredient.kcal = ingredient.macro_kcal();
+        }
+        Ok(PantryItem::Ingredient(Box::new(ingredient)))
+    }
+}
+
+fn read_flavorings(args: &IngredientArgs) -> Result<Flavorings, Error> {
+    match &args.flavorings {
+        Some(path) => read_data(path),
+        None => Ok(Flavorings::default()),
+    }
+}
+
+fn load_ingredients(args: &IngredientArgs) -> Result<(Ingredients, RawIngredients), Error> {
+    let flavorings = read_flavorings(args)?;
+    let mut raw_ingredients = RawIngredients(BTreeMap::new());
+    let mut recipes = Vec::new();
+    for overlaid in overlay_pantry(args)?.into_values() {
+        match overlaid.item {
+            PantryItem::Ingredient(ingredient) => {
+                if let Some(mismatch) = ingredient.kcal_mismatch(KCAL_MISMATCH_PCT) {
+                    eprintln!("warning: {}", mismatch);
+                }
+                raw_ingredients
+                    .0
+                    .insert(ingredient.name.clone(), *ingredient);
+            }
+            PantryItem::Recipe(recipe) => recipes.push(recipe),
+        }
+    }
+    // recipes may use flavorings, which add weight but no macros
+    for flavoring in &flavorings.flavoring {
+        let ingredient = Ingredient {
+            name: flavoring.name.clone(),
+            g: 100,
+            ..Default::default()
+        };
+        raw_ingredients.0.insert(flavoring.name.clone(), ingredient);
+    }
+    recipe::resolve(recipes, &mut raw_ingredients)?;
+    // after resolving, so recipes can use ingredients that aren't used on their own
+    raw_ingredients
+        .0
+        .retain(|name, _| args.is_selected(name) && !flavorings.contains(name));
+
+    Ok((raw_ingredients.normalize(), raw_ingredients))
+}
+
+/// Leaves out the ingredients that the target's diet, excluded tags or meal rule out, together with
+/// the target's constraints on them, and returns their names
+fn exclude_tagged(
+    target: &mut Target,
+    ingredients: &mut Ingredients,
+    raw_ingredients: &mut RawIngredients,
+) -> Vec<String> {
+    let excluded: Vec<String> = raw_ingredients
+        .0
+        .values()
+        .filter(|ingredient| target.excludes(ingredient))
+        .map(|ingredient| ingredient.name.clone())
+        .collect();
+    for name in &excluded {
+        ingredients.0.remove(name);
+        raw_ingredients.0.remove(name);
+    }
+    target.retain_constraints(|name| !excluded.iter().any(|excluded| excluded == name));
+    if !excluded.is_empty() {
+        eprintln!(
+            "warning: the target's diet, excluded tags and meal leave out {}",
+            excluded.join(", ")
+        );
+    }
+    excluded
+}
+
+/// Computes grams for each ingredient because the proposal is only in kcal pieces.
+///
+/// Ingredients with a portion size are rounded to whole portions. The mix is scaled to the
+/// target's kcal, unless the target has a kcal band that the `steps` pieces may end up anywhere in.
+fn to_grams(
+    proposal: &Proposal,
+    target: &Target,
+    raw_ingredients: &RawIngredients,
+    steps: usize,
+) -> Proposal {
+    let kcal = match target.kcal_band() {
+        (min, max) if min == max => target.kcal as f64,
+        _ => proposal.kcal() as f64 * target.kcal_per_piece(steps),
+    };
+    let mut gram_proposal = Proposal(BTreeMap::new());
+    for (name, n) in &proposal.0 {
+        let ingredient = &raw_ingredients.0[name];
+        let ingredient_kcal = *n as f64 * (kcal / proposal.kcal() as f64);
+        let g = ingredient_kcal * (ingredient.g as f64 / ingredient.kcal);
+        let g = match ingredient.portion_g {
+            Some(portion_g) if portion_g > 0 => (g / portion_g as f64).round() as u64 * portion_g,
+            _ => g.round() as u64,
+        };
+        gram_proposal.0.insert(name.to_string(), g);
+    }
+    add_kcal_free(&mut gram_proposal, target, raw_ingredients);
+    gram_proposal
+}
+
+/// Adds the kcal-free ingredients, which the search leaves out: the grams of their exact or at
+/// least constraints, and then the ones with the most water per g until the plan has the target's
+/// minimum of water, without exceeding its maximums of water and caffeine or their at most
+/// constraints
+fn add_kcal_free(gram_proposal: &mut Proposal, target: &Target, raw_ingredients: &RawIngredients) {
+    let constrained = |constraints: &Option<Vec<TargetConstraint>>, name: &str| {
+        constraints
+            .iter()
+            .flatten()
+            .find(|constraint| constraint.name == name)
+            .map(|constraint| constraint.g)
+    };
+    let kcal_free: Vec<&Ingredient> = raw_ingredients
+        .0
+        .values()
+        .filter(|ingredient| ingredient.is_kcal_free())
+        .collect();
+    for ingredient in &kcal_free {
+        let g = constrained(&target.constraint_exact, &ingredient.name)
+            .or_else(|| constrained(&target.constraint_at_least, &ingredient.name))
+            .unwrap_or(0);
+        gram_proposal.0.insert(ingredient.name.clone(), g);
+    }
+    let min_water = match target.min_water_ml {
+        Some(min_water) => min_water,
+        None => return,
+    };
+    let per_g =
+        |ingredient: &Ingredient, amount: Option<f64>| amount.unwrap_or(0.0) / ingredient.g as f64;
+    let mut sources: Vec<&Ingredient> = kcal_free
+        .into_iter()
+        .filter(|ingredient| {
+            per_g(ingredient, ingredient.water_ml) > 0.0
+                && constrained(&target.constraint_exact, &ingredient.name).is_none()
+        })
+        .collect();
+    sources.sort_by(|a, b| per_g(b, b.water_ml).total_cmp(&per_g(a, a.water_ml)));
+    for ingredient in sources {
+        let macros = Macros::from_grams(gram_proposal, raw_ingredients);
+        if macros.water_ml >= min_water {
+            return;
+        }
+        // grams of the ingredient until a maximum is reached
+        let room = |max: Option<f64>, total: f64, per_g: f64| match max {
+            Some(max) if per_g > 0.0 => ((max - total) / per_g).max(0.0),
+            _ => f64::INFINITY,
+        };
+        let g = gram_proposal.0[&ingredient.name];
+        let most = room(
+            target.max_water_ml,
+            macros.water_ml,
+            per_g(ingredient, ingredient.water_ml),
+        )
+        .min(room(
+            target.max_caffeine_mg,
+            macros.caffeine_mg,
+            per_g(ingredient, ingredient.caffeine_mg),
+        ))
+        .min(
+            constrained(&target.constraint_at_most, &ingredient.name)
+                .map_or(f64::INFINITY, |at_most| at_most.saturating_sub(g) as f64),
+        );
+        let needed = (min_water - macros.water_ml) / per_g(ingredient, ingredient.water_ml);
+        let portion_g = ingredient.portion_g.filter(|g| *g > 0).unwrap_or(1) as f64;
+        let mut added = (needed / portion_g).ceil() * portion_g;
+        if added > most {
+            added = (most / portion_g).floor() * portion_g;
+        }
+        gram_proposal
+            .0
+            .insert(ingredient.name.clone(), g + added as u64);
+    }
+}
+
+/// The target of the search, which leaves the minimum of water to the kcal-free ingredients with
+/// water if there are any
+fn search_target(target: &Target, raw_ingredients: &RawIngredients) -> NormalizedTarget {
+    let mut normalized = target.normalize();
+    let kcal_free_water = raw_ingredients.0.values().any(|ingredient| {
+        ingredient.is_kcal_free() && ingredient.water_ml.is_some_and(|water_ml| water_ml > 0.0)
+    });
+    if kcal_free_water {
+        normalized.min_water_ml_per_kcal = None;
+    }
+    normalized
+}
+
+/// Minutes of preparing the ingredients that a plan in grams uses
+fn prep_minutes(gram_proposal: &Proposal, raw_ingredients: &RawIngredients) -> f64 {
+    gram_proposal
+        .0
+        .iter()
+        .filter(|(_, g)| **g > 0)
+        .filter_map(|(name, _)| raw_ingredients.0.get(name)?.prep_minutes)
+        // an empty sum of floats is -0
+        .fold(0.0, |sum, minutes| sum + minutes)
+}
+
+fn round_decigram(g: f64) -> f64 {
+    (g * 10.0).round() / 10.0
+}
+
+/// Absolute amounts of a mix
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Macros {
+    kcal: f64,
+    // in g
+    carb: f64,
+    // in g
+    fat: f64,
+    // in g
+    protein: f64,
+    // in g, part of protein
+    quality_protein: f64,
+    // in g, part of protein
+    leucine_g: f64,
+    eaa_g: f64,
+    // in g, part of fat
+    omega3_g: f64,
+    omega6_g: f64,
+    // in g
+    alcohol: f64,
+    // in g, part of carb
+    polyols: f64,
+    // in g, part of fat
+    sat_fat: f64,
+    // in g, part of carb
+    sugar: f64,
+    sodium_mg: f64,
+    water_ml: f64,
+    caffeine_mg: f64,
+    glycemic_load: f64,
+}
+
+impl Macros {
+    fn from_grams(gram_proposal: &Proposal, raw_ingredients: &RawIngredients) -> Macros {
+        let mut macros = Macros::default();
+        for (name, g) in &gram_proposal.0 {
+            let ingredient = &raw_ingredients.0[name];
+            let factor = *g as f64 / ingredient.g as f64;
+            macros.kcal += factor * ingredient.kcal;
+            macros.carb += factor * ingredient.carb;
+            macros.fat += factor * ingredient.fat;
+            macros.protein += factor * ingredient.protein;
+            macros.quality_protein += factor * ingredient.quality_protein();
+            macros.leucine_g += factor * ingredient.leucine_g.unwrap_or(0.0);
+            macros.eaa_g += factor * ingredient.eaa_g.unwrap_or(0.0);
+            macros.omega3_g += factor * ingredient.omega3_g.unwrap_or(0.0);
+            macros.omega6_g += factor * ingredient.omega6_g.unwrap_or(0.0);
+            macros.alcohol += factor * ingredient.alcohol.unwrap_or(0.0);
+            macros.polyols += factor * ingredient.polyols.unwrap_or(0.0);
+            macros.sat_fat += factor * ingredient.sat_fat.unwrap_or(0.0);
+            macros.sugar += factor * ingredient.sugar.unwrap_or(0.0);
+            macros.sodium_mg += factor * ingredient.sodium_mg.unwrap_or(0.0);
+            macros.water_ml += factor * ingredient.water_ml.unwrap_or(0.0);
+            macros.caffeine_mg += factor * ingredient.caffeine_mg.unwrap_or(0.0);
+            macros.glycemic_load += factor * ingredient.glycemic_load();
+        }
+        macros
+    }
+
+    /// Macros of `g` grams of a mix that weighs `total_g` grams
+    fn per_g(&self, g: f64, total_g: f64) -> Macros {
+        let factor = g / total_g;
+        Macros {
+            kcal: self.kcal * factor,
+            carb: self.carb * factor,
+            fat: self.fat * factor,
+            protein: self.protein * factor,
+            quality_protein: self.quality_protein * factor,
+            leucine_g: self.leucine_g * factor,
+            eaa_g: self.eaa_g * factor,
+            omega3_g: self.omega3_g * factor,
+            omega6_g: self.omega6_g * factor,
+            alcohol: self.alcohol * factor,
+            polyols: self.polyols * factor,
+            sat_fat: self.sat_fat * factor,
+            sugar: self.sugar * factor,
+            sodium_mg: self.sodium_mg * factor,
+            water_ml: self.water_ml * factor,
+            caffeine_mg: self.caffeine_mg * factor,
+            glycemic_load: self.glycemic_load * factor,
+        }
+    }
+
+    fn add(&mut self, other: &Macros) {
+        self.kcal += other.kcal;
+        self.carb += other.carb;
+        self.fat += other.fat;
+        self.protein += other.protein;
+        self.quality_protein += other.quality_protein;
+        self.leucine_g += other.leucine_g;
+        self.eaa_g += other.eaa_g;
+        self.omega3_g += other.omega3_g;
+        self.omega6_g += other.omega6_g;
+        self.alcohol += other.alcohol;
+        self.polyols += other.polyols;
+        self.sat_fat += other.sat_fat;
+        self.sugar += other.sugar;
+        self.sodium_mg += other.sodium_mg;
+        self.water_ml += other.water_ml;
+        self.caffeine_mg += other.caffeine_mg;
+        self.glycemic_load += other.glycemic_load;
+    }
+
+    /// e.g. "120g carb, 88g fat, 73g protein in 1500 kcal (43:31:26)"
+    ///
+    /// Alcohol, sugar, polyols and saturated fat are only mentioned if the mix contains them.
+    fn summary(&self, kcal: u64) -> String {
+        let sum = self.carb + self.fat + self.protein + self.alcohol;
+        let of_which = |parts: &[(f64, &str)]| {
+            let parts: Vec<String> = parts
+                .iter()
+                .filter(|(g, _)| *g > 0.0)
+                .map(|(g, name)| format!("{}g {}", g.round(), name))
+                .collect();
+            if parts.is_empty() {
+                String::new()
+            } else {
+                format!(" (of which {})", parts.join(", "))
+            }
+        };
+        let polyols = of_which(&[(self.sugar, "sugar"), (self.polyols, "polyols")]);
+        let sat_fat = of_which(&[(self.sat_fat, "saturated")]);
+        let alcohol = if self.alcohol > 0.0 {
+            format!(", {}g alcohol", self.alcohol.round())
+        } else {
+            String::new()
+        };
+        format!(
+            "{}g carb{}, {}g fat{}, {}g protein{} in {} kcal ({}:{}:{})",
+            self.carb.round(),
+            polyols,
+            self.fat.round(),
+            sat_fat,
+            self.protein.round(),
+            alcohol,
+            kcal,
+            (100.0 * self.carb / sum).round(),
+            (100.0 * self.fat / sum).round(),
+            (100.0 * self.protein / sum).round()
+        )
+    }
+}
+
+fn print_result(
+    gram_proposal: &Proposal,
+    target: &Target,
+    raw_ingredients: &RawIngredients,
+    rounding: Rounding,
+) {
+    println!();
+    println!("---- RESULT ----");
+    println!("Mix the following together (in grams)");
+    write_result(
+        std::io::stdout(),
+        gram_proposal,
+        target,
+        raw_ingredients,
+        rounding,
+    )
+    .unwrap();
+}
+
+/// Prints each alternative like the result
+fn print_alternatives(
+    alternatives: &[Proposal],
+    target: &Target,
+    raw_ingredients: &RawIngredients,
+    rounding: Rounding,
+) {
+    for (i, alternative) in alternatives.iter().enumerate() {
+        println!();
+        println!("---- ALTERNATIVE {} ----", i + 1);
+        write_result(
+            std::io::stdout(),
+            alternative,
+            target,
+            raw_ingredients,
+            rounding,
+        )
+        .unwrap();
+    }
+}
+
+/// Writes the table of the mix and its macros in total and per 100g
+fn write_result<W: Write>(
+    mut writer: W,
+    gram_proposal: &Proposal,
+    target: &Target,
+    raw_ingredients: &RawIngredients,
+    rounding: Rounding,
+) -> std::io::Result<()> {
+    let mut report = Report::new(gram_proposal, raw_ingredients, rounding);
+    add_nutrients(
+        &mut report,
+        gram_proposal,
+        target,
+        raw_ingredients,
+        rounding,
+    );
+    report.write_table(&mut writer, Some(&target.ratio(rounding)))?;
+    let gram_proposal = &rounding.grams(gram_proposal, raw_ingredients);
+    let macros = Macros::from_grams(gram_proposal, raw_ingredients);
+    // in a kcal band the result's kcal may differ from the target's
+    let kcal = match target.kcal_band() {
+        (min, max) if min == max => target.kcal,
+        _ => macros.kcal.round() as u64,
+    };
+    writeln!(writer, "Results in {}.", macros.summary(kcal))?;
+    report.write_nutrients(&mut writer)?;
+    let total_g = gram_proposal.0.values().sum::<u64>() as f64;
+    let per_100g = macros.per_g(100.0, total_g);
+    writeln!(
+        writer,
+        "Per 100g of the mix {}g carb, {}g fat, {}g protein in {} kcal.",
+        rounding.macro_g(per_100g.carb),
+        rounding.macro_g(per_100g.fat),
+        rounding.macro_g(per_100g.protein),
+        per_100g.kcal.round()
+    )?;
+    if !report.groups.is_empty() {
+        writeln!(writer)?;
+        report.write_groups(&mut writer)?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "tui")]
+fn run_interactive(args: &OptimizeArgs, target: &Target) -> Result<(), Error> {
+    let (mut ingredients, mut raw_ingredients) = load_ingredients(&args.ingredients)?;
+    let mut target = target.clone();
+    for note in target.resolve_names(&raw_ingredients, args.ingredients.ignore_case)? {
+        eprintln!("warning: {}", note);
+    }
+    exclude_tagged(&mut target, &mut ingredients, &mut raw_ingredients);
+    let target = &target;
+    let names = raw_ingredients.0.keys().cloned().collect();
+    let app = tui::App::new(names, target.kcal);
+    let optimize_app = |app: &tui::App| {
+        let enabled = app.enabled_names();
+        let mut target = target.clone();
+        target.kcal = app.kcal;
+        target.retain_constraints(|name| enabled.contains(&name));
+        let raw_ingredients = RawIngredients(
+            raw_ingredients
+                .0
+                .iter()
+                .filter(|(name, _)| enabled.contains(&name.as_str()))
+                .map(|(name, ingredient)| (name.clone(), ingredient.clone()))
+                .collect(),
+        );
+        let ingredients = Ingredients(
+            ingredients
+                .0
+                .iter()
+                .filter(|(name, _)| enabled.contains(&name.as_str()))
+                .map(|(name, ingredient)| (name.clone(), ingredient.clone()))
+                .collect(),
+        );
+        let steps = target.optimization_steps(args.steps);
+        let proposal = TargetConstraints::from_target(&target, &raw_ingredients, steps).and_then(
+            |constraints| {
+                optimize_restarts(
+                    &search_target(&target, &raw_ingredients),
+                    constraints,
+                    &ingredients,
+                    steps,
+                    Restarts {
+                        count: args.restarts,
+                        seed: args.seed,
+                    },
+                    None,
+                    None,
+                )
+            },
+        );
+        let proposal = match proposal {
+            Ok(proposal) => proposal,
+            Err(e) => return e.to_string(),
+        };
+        let gram_proposal = to_grams(&proposal, &target, &raw_ingredients, steps);
+        let mut result = Vec::new();
+        write_result(
+            &mut result,
+            &gram_proposal,
+            &target,
+            &raw_ingredients,
+            result_rounding(args),
+        )
+        .unwrap();
+        String::from_utf8(result).unwrap()
+    };
+    tui::run(app, optimize_app).map_err(|source| Error::Write {
+        path: PathBuf::from("-"),
+        source,
+    })
+}
+
+fn result_rounding(args: &OptimizeArgs) -> Rounding {
+    Rounding {
+        g: args.round_g.max(1),
+        macro_decimals: args.macro_decimals,
+        pct_decimals: args.pct_decimals,
+    }
+}
+
+fn print_meals(
+    meals: &[Meal],
+    fixed: &[meal::FixedFood],
+    gram_proposal: &Proposal,
+    raw_ingredients: &RawIngredients,
+    metrics: &[Metric],
+) {
+    let eaten = match (meals.is_empty(), fixed.is_empty()) {
+        (true, true) => return,
+        (false, true) => "imported meals",
+        (true, false) => "fixed foods",
+        (false, false) => "imported meals and fixed foods",
+    };
+    // e.g. "; P:E ratio 0.52" if the target defines metrics
+    let with_metrics = |macros: &Macros| {
+        if metrics.is_empty() {
+            String::new()
+        } else {
+            format!("; {}", metrics::summary(metrics, macros))
+        }
+    };
+    let mut day = Macros::from_grams(gram_proposal, raw_ingredients);
+    for meal in meals {
+        let macros = meal.macros();
+        println!(
+            "Imported meal {}: {}{}.",
+            meal.name,
+            macros.summary(meal.kcal as u64),
+            with_metrics(&macros)
+        );
+        day.add(&macros);
+    }
+    for food in fixed {
+        let macros = food.meal().macros();
+        println!(
+            "Fixed food {}: {}{}.",
+            food.name,
+            macros.summary(food.kcal as u64),
+            with_metrics(&macros)
+        );
+        day.add(&macros);
+    }
+    println!(
+        "Together with {} {}{}.",
+        eaten,
+        day.summary(day.kcal.round() as u64),
+        with_metrics(&day)
+    );
+}
+
+/// Reads the target file or preset and applies the overrides of the command line
+fn read_target(args: &OptimizeArgs) -> Result<Target, Error> {
+    let mut target = match (&args.target, args.preset) {
+        (Some(path), _) => read_target_file(path)?,
+        (None, Some(preset)) => preset.target(),
+        (None, None) => unreachable!("clap requires a target or a preset"),
+    };
+    target.kcal = args.kcal.unwrap_or(target.kcal);
+    target.carb = args.carb.unwrap_or(target.carb);
+    target.fat = args.fat.unwrap_or(target.fat);
+    target.protein = args.protein.unwrap_or(target.protein);
+    Ok(target)
+}
+
+fn run_optimize(args: &OptimizeArgs) -> Result<(), Error> {
+    let meals = args
+        .import_meal
+        .iter()
+        .map(|path| read_data(path))
+        .collect::<Result<Vec<Meal>, Error>>()?;
+    let mut given_target = read_target(args)?;
+    let mut target = meal::reconcile(&given_target, &meals)?;
+    let flavorings = read_flavorings(&args.ingredients)?;
+    target.retain_constraints(|name| {
+        args.ingredients.is_selected(name) && !flavorings.contains(name)
+    });
+    #[cfg(feature = "tui")]
+    if args.interactive {
+        return run_interactive(args, &target);
+    }
+    // the search trace would break structured output
+    let trace = args.output == OutputFormat::Text && args.report.is_none() && !args.quiet;
+    let (mut ingredients, mut raw_ingredients) = load_ingredients(&args.ingredients)?;
+    // the given target only refers to the same ingredients, for the adjustments
+    given_target.resolve_names(&raw_ingredients, args.ingredients.ignore_case)?;
+    for note in target.resolve_names(&raw_ingredients, args.ingredients.ignore_case)? {
+        eprintln!("warning: {}", note);
+    }
+    let inventory = match &args.inventory {
+        Some(path) => Some(read_data::<stock::Inventory>(path)?),
+        None => None,
+    };
+    if let Some(inventory) = &inventory {
+        // the given target too, so that the stock isn't an adjustment
+        inventory.limit(&mut given_target, &raw_ingredients, args.days);
+        inventory.limit(&mut target, &raw_ingredients, args.days);
+    }
+    exclude_tagged(&mut target, &mut ingredients, &mut raw_ingredients);
+    let adjustments = adjust::adjustments(&given_target, &target);
+    let target_normalized = search_target(&target, &raw_ingredients);
+    if trace {
+        println!("Starting search with");
+        println!("\tTarget {:?}", target_normalized);
+        println!(
+            "\tconstraints exact: {:?}, at least: {:?}, at most {:?}",
+            target.constraint_exact, target.constraint_at_least, target.constraint_at_most
+        );
+        if args.verbose >= 1 {
+            for (name, normalized) in &ingredients.0 {
+                println!("\tIngredient {} {:?}", name, normalized);
+            }
+        }
+        for adjustment in &adjustments {
+            println!(
+                "\tAdjusted {} from {} to {} ({})",
+                adjustment.field, adjustment.original, adjustment.effective, adjustment.reason
+            );
+        }
+    }
+
+    let steps = target.optimization_steps(args.steps);
+    let mut constraints = TargetConstraints::from_target(&target, &raw_ingredients, steps)?;
+    constraints.cook_in_batches(&target, &raw_ingredients, steps, args.days);
+    if let Some(inventory) = &inventory {
+        let urgency = inventory.urgency(stock::Date::today());
+        if !urgency.is_empty() {
+            constraints.expiry = Some(Expiry {
+                urgency,
+                weight: args.expiry_weight,
+            });
+        }
+    }
+    let warn_missing = |missing: Vec<String>, path: &Path| {
+        if !missing.is_empty() {
+            eprintln!(
+                "warning: {} of {} aren't used in this run",
+                missing.join(", "),
+                path.display()
+            );
+        }
+    };
+    if let Some(path) = &args.start_from {
+        let start: Proposal = read_data(path)?;
+        let missing = constraints.start_from(&start, &target, &raw_ingredients, steps);
+        warn_missing(missing, path);
+    }
+    if let Some(path) = &args.reference {
+        let reference: Proposal = read_data(path)?;
+        let missing = constraints.keep_close_to(
+            &reference,
+            &target,
+            &raw_ingredients,
+            steps,
+            args.reference_weight,
+        );
+        warn_missing(missing, path);
+    }
+    if trace {
+        println!(
+            "\t{} steps of {:.2} kcal",
+            steps,
+            target.kcal_per_piece(steps)
+        );
+    }
+    if !constraints.at_most.0.is_empty() {
+        if let Some(coarse) = target.coarse_resolution(steps) {
+            eprintln!("warning: {}", coarse);
+        }
+    }
+    for unreachable in
+        feasibility::unreachable(&target_normalized, &constraints, &ingredients, steps)
+    {
+        eprintln!("warning: {}", unreachable);
+    }
+    let mut explanation = Explanation::default();
+    // -vv shows the search like --explain without a file
+    let explain = match &args.explain {
+        None if trace && args.verbose >= 2 => Some(None),
+        explain => explain.clone(),
+    };
+    // the bar is only drawn for a person watching it
+    let progress = Progress::new(steps, args.restarts);
+    let show_progress = !args.quiet && std::io::stderr().is_terminal();
+    let mut population = Vec::new();
+    let alternative_constraints = constraints.clone();
+    let proposal = match args.algorithm {
+        Algorithm::Greedy => optimize_restarts(
+            &target_normalized,
+            constraints,
+            &ingredients,
+            steps,
+            Restarts {
+                count: args.restarts,
+                seed: args.seed,
+            },
+            explain.as_ref().map(|_| &mut explanation),
+            Some(&progress).filter(|_| show_progress),
+        ),
+        Algorithm::Exact => {
+            exact::optimize_exact(&target_normalized, constraints, &ingredients, steps)
+        }
+        Algorithm::Genetic => genetic::optimize_genetic(
+            &target_normalized,
+            constraints,
+            &ingredients,
+            steps,
+            args.seed,
+        )
+        .map(|(proposal, front)| {
+            population = front;
+            proposal
+        }),
+    };
+    progress.finish();
+    let proposal = proposal?;
+    if args.population.is_some() && args.algorithm != Algorithm::Genetic {
+        eprintln!("warning: only the genetic search has a population, --population is ignored");
+    }
+    let alternatives: Vec<Proposal> = alternatives::alternatives(
+        &target_normalized,
+        &alternative_constraints,
+        &ingredients,
+        steps,
+        &proposal,
+        args.alternatives,
+        args.min_difference / target.kcal_per_piece(steps),
+    )
+    .iter()
+    .map(|alternative| to_grams(alternative, &target, &raw_ingredients, steps))
+    .collect();
+    if let Some(explain_path) = &explain {
+        let path = explain_path.clone().unwrap_or_else(|| PathBuf::from("-"));
+        let write_error = |source| Error::Write {
+            path: path.clone(),
+            source,
+        };
+        let writer: Box<dyn Write> = match explain_path {
+            Some(path) => Box::new(std::io::BufWriter::new(
+                File::create(path).map_err(write_error)?,
+            )),
+            None if trace => Box::new(std::io::stdout()),
+            // structured output on stdout stays parseable
+            None => Box::new(std::io::stderr()),
+        };
+        let kcal_per_piece = target.kcal_per_piece(steps);
+        explanation
+            .write(writer, kcal_per_piece)
+            .map_err(write_error)?;
+    }
+    if trace {
+        println!(
+            "\tFound {:?} with cost {}",
+            proposal,
+            target_normalized.evaluate(&proposal, &ingredients)
+        );
+    }
+
+    let gram_proposal = to_grams(&proposal, &target, &raw_ingredients, steps);
+    let intervals = uncertainty::intervals(&gram_proposal, &raw_ingredients, args.tolerance);
+    let batches = batch::plan(&gram_proposal, &raw_ingredients, args.days);
+    let rounding = result_rounding(args);
+    let full_report = || {
+        let mut report = Report::new(&gram_proposal, &raw_ingredients, rounding);
+        report.intervals = intervals.clone();
+        report.imported_meals = meals.clone();
+        report.flavorings = flavorings.flavoring.clone();
+        add_nutrients(
+            &mut report,
+            &gram_proposal,
+            &target,
+            &raw_ingredients,
+            rounding,
+        );
+        report.adjustments = adjustments.clone();
+        report.batches = batches.clone();
+        report.alternatives = alternatives
+            .iter()
+            .map(|alternative| Report::new(alternative, &raw_ingredients, rounding))
+            .collect();
+        report
+    };
+    match args.output {
+        _ if args.report.is_some() => {
+            let document = document::render(
+                &full_report(),
+                &target,
+                &target.ratio(rounding),
+                args.report.unwrap(),
+            );
+            print!("{}", document);
+        }
+        OutputFormat::Text => {
+            print_result(&gram_proposal, &target, &raw_ingredients, rounding);
+            units::print_measures(
+                &rounding.grams(&gram_proposal, &raw_ingredients),
+                &raw_ingredients,
+                args.units,
+            );
+            if let Some(intervals) = &intervals {
+                uncertainty::print_intervals(intervals);
+            }
+            batch::print_batches(&batches, args.days);
+            flavorings.print();
+            print_meals(
+                &meals,
+                target.fixed.as_deref().unwrap_or(&[]),
+                &gram_proposal,
+                &raw_ingredients,
+                target.metrics(),
+            );
+            print_alternatives(&alternatives, &target, &raw_ingredients, rounding);
+        }
+        OutputFormat::Json | OutputFormat::Csv => {
+            let report = full_report();
+            let stdout = std::io::stdout();
+            if args.output == OutputFormat::Json {
+                report.write_json(stdout.lock())?;
+                println!();
+            } else {
+                report.write_csv(stdout.lock())?;
+            }
+        }
+    }
+
+    if let Some(path) = &args.export_meal {
+        let mut exported = Meal::from_grams(
+            args.meal_name.as_deref().unwrap_or("meal"),
+            &gram_proposal,
+            &raw_ingredients,
+        );
+        exported.tags = args.meal_tag.clone();
+        exported.notes = args.meal_notes.clone();
+        write_toml(path, &exported)?;
+    }
+    if let Some(path) = &args.save {
+        let mut saved = gram_proposal.clone();
+        saved.0.retain(|_, g| *g > 0);
+        write_toml(path, &saved)?;
+    }
+    if let Some(path) = &args.population {
+        let mut plan: Vec<genetic::Plan> = Vec::new();
+        for member in &population {
+            let mut grams = to_grams(&member.proposal, &target, &raw_ingredients, steps);
+            grams.0.retain(|_, g| *g > 0);
+            // an ingredient can round to 0g, the cheaper plan of the same size is kept
+            if plan
+                .last()
+                .is_some_and(|last| last.ingredients == grams.0.len())
+            {
+                plan.pop();
+            }
+            plan.push(genetic::Plan {
+                ingredients: grams.0.len(),
+                cost: member.cost,
+                grams,
+            });
+        }
+        write_data(path, &genetic::Population { plan })?;
+    }
+    Ok(())
+}
+
+/// Prints whether the file could be read and returns whether it could
+fn report_validation<T>(path: &Path, result: Result<T, Error>) -> bool {
+    match result {
+        Ok(_) => {
+            println!("{}: ok", path.display());
+            true
+        }
+        Err(err) => {
+            println!("{}", err);
+            false
+        }
+    }
+}
+
+fn run_validate(args: &InspectArgs) -> Result<(), Error> {
+    let mut failed = 0;
+    if let Some(target_path) = &args.target {
+        if !report_validation(target_path, read_target_file(target_path)) {
+            failed += 1;
+        }
+    }
+    for ingredient_path in ingredient_paths(&args.ingredients)? {
+        let item = read_pantry_item(&ingredient_path);
+        if let Ok(PantryItem::Ingredient(ingredient)) = &item {
+            if let Some(mismatch) = ingredient.kcal_mismatch(KCAL_MISMATCH_PCT) {
+                println!("{}: warning: {}", ingredient_path.display(), mismatch);
+            }
+        }
+        if !report_validation(&ingredient_path, item) {
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        return Err(Error::ValidationFailed(failed));
+    }
+    Ok(())
+}
+
+fn run_show(args: &InspectArgs) -> Result<(), Error> {
+    let mut metrics = Vec::new();
+    if let Some(target_path) = &args.target {
+        let target = read_target_file(target_path)?;
+        metrics = target.metrics().to_vec();
+        println!("Target {:?}", target.normalize());
+        println!(
+            "\tconstraints exact: {:?}, at least: {:?}, at most {:?}",
+            target.constraint_exact, target.constraint_at_least, target.constraint_at_most
+        );
+    }
+    let (ingredients, raw_ingredients) = load_ingredients(&args.ingredients)?;
+    for (name, normalized) in &ingredients.0 {
+        println!("Ingredient {} {:?}", name, normalized);
+        if !metrics.is_empty() {
+            let per_100g = Proposal(vec![(name.clone(), 100)].into_iter().collect());
+            let macros = Macros::from_grams(&per_100g, &raw_ingredients);
+            println!("\tper 100g {}", metrics::summary(&metrics, &macros));
+        }
+    }
+    for (name, ingredient) in &raw_ingredients.0 {
+        if ingredient.is_kcal_free() {
+            println!(
+                "Ingredient {} has no kcal and is added in grams after the search",
+                name
+            );
+        }
+    }
+    Ok(())
+}
+
+fn run_portion(args: &PortionArgs) -> Result<(), Error> {
+    if !args.cooked_g.is_empty() && args.cooked_g.len() != args.meals.len() {
+        return Err(Error::CookedWeightCount {
+            meals: args.meals.len(),
+            weights: args.cooked_g.len(),
+        });
+    }
+    let shares = match args.containers {
+        Some(containers) => vec![1.0; containers as usize],
+        None => args.shares.clone(),
+    };
+    portion::check_shares(&shares)?;
+    println!("---- PORTIONS ----");
+    for (i, meal_path) in args.meals.iter().enumerate() {
+        let meal: Meal = read_data(meal_path)?;
+        let cooked_g = match args.cooked_g.get(i) {
+            Some(cooked_g) => *cooked_g,
+            None => meal.ingredients.iter().map(|ingredient| ingredient.g).sum(),
+        };
+        let containers = portion::split(&meal, cooked_g, &shares);
+        portion::print_portions(&meal, cooked_g, &containers);
+    }
+    Ok(())
+}
+
+fn run_shopping_list(args: &ShoppingListArgs) -> Result<(), Error> {
+    let (ingredients, raw_ingredients) = load_ingredients(&args.ingredients)?;
+    let flavorings = read_flavorings(&args.ingredients)?;
+    let mut gram_proposals = Vec::new();
+    for meal_path in &args.meal {
+        gram_proposals.push(read_data::<Meal>(meal_path)?.grams());
+    }
+    for target_path in &args.target {
+        let mut target = read_target_file(target_path)?;
+        target.retain_constraints(|name| {
+            args.ingredients.is_selected(name) && !flavorings.contains(name)
+        });
+        let mut ingredients = ingredients.clone();
+        let mut raw_ingredients = raw_ingredients.clone();
+        for note in target.resolve_names(&raw_ingredients, args.ingredients.ignore_case)? {
+            eprintln!("warning: {}", note);
+        }
+        exclude_tagged(&mut target, &mut ingredients, &mut raw_ingredients);
+        let steps = target.optimization_steps(args.steps);
+        let constraints = TargetConstraints::from_target(&target, &raw_ingredients, steps)?;
+        let proposal = optimize(
+            &search_target(&target, &raw_ingredients),
+            constraints,
+            &ingredients,
+            steps,
+        )?;
+        gram_proposals.push(to_grams(&proposal, &target, &raw_ingredients, steps));
+    }
+    for gram_proposal in &mut gram_proposals {
+        flavorings.add_to_plan(gram_proposal);
+    }
+    let total = shopping::aggregate(&gram_proposals);
+    shopping::print_shopping_list(&shopping::shopping_list(&total, &raw_ingredients));
+    Ok(())
+}
+
+fn run_mix(args: &MixArgs) -> Result<(), Error> {
+    let target = match &args.target {
+        Some(path) => Some(read_target_file(path)?),
+        None => None,
+    };
+    let (_, raw_ingredients) = load_ingredients(&IngredientArgs {
+        ingredients_dir: args.ingredients_dir.clone(),
+        ..Default::default()
+    })?;
+    let mut gram_proposal = Proposal(BTreeMap::new());
+    for (name, g) in &args.amounts {
+        if !raw_ingredients.0.contains_key(name) {
+            return Err(Error::UnknownIngredient(name.clone()));
+        }
+        *gram_proposal.0.entry(name.clone()).or_insert(0) += g;
+    }
+    println!("---- MIX ----");
+    print_mix(&gram_proposal, &raw_ingredients, target.as_ref());
+    Ok(())
+}
+
+fn run_reeval(args: &ReevalArgs) -> Result<(), Error> {
+    let mut target = meal::reconcile(&read_target_file(&args.target)?, &[])?;
+    let gram_proposal: Proposal = read_data(&args.proposal)?;
+    let (ingredients, raw_ingredients) = load_ingredients(&args.ingredients)?;
+    for note in target.resolve_names(&raw_ingredients, args.ingredients.ignore_case)? {
+        eprintln!("warning: {}", note);
+    }
+    let steps = target.optimization_steps(None);
+    let mut proposal = Proposal(BTreeMap::new());
+    for (name, g) in &gram_proposal.0 {
+        let ingredient = raw_ingredients
+            .0
+            .get(name)
+            .ok_or_else(|| Error::UnknownIngredient(name.clone()))?;
+        if !ingredient.is_kcal_free() {
+            proposal
+                .0
+                .insert(name.clone(), ingredient.pieces(*g, &target, steps));
+        }
+    }
+    println!("---- RE-EVALUATION ----");
+    print_mix(&gram_proposal, &raw_ingredients, Some(&target));
+    println!(
+        "Cost against the target {:.6}.",
+        search_target(&target, &raw_ingredients).evaluate(&proposal, &ingredients)
+    );
+    let violations = violations::violations(&gram_proposal, &target, &raw_ingredients);
+    if violations.is_empty() {
+        println!("All constraints of the target are met.");
+    } else {
+        println!("Constraints of the target that aren't met:");
+        for violation in &violations {
+            println!("\t{}", violation);
+        }
+    }
+    Ok(())
+}
+
+/// Fails if a saved proposal contains an ingredient that isn't loaded
+fn check_loaded(gram_proposal: &Proposal, raw_ingredients: &RawIngredients) -> Result<(), Error> {
+    match gram_proposal
+        .0
+        .keys()
+        .find(|name| !raw_ingredients.0.contains_key(*name))
+    {
+        Some(name) => Err(Error::UnknownIngredient(name.clone())),
+        None => Ok(()),
+    }
+}
+
+fn run_diff(args: &DiffArgs) -> Result<(), Error> {
+    let old: Proposal = read_data(&args.old)?;
+    let new: Proposal = read_data(&args.new)?;
+    let (_, raw_ingredients) = load_ingredients(&args.ingredients)?;
+    check_loaded(&old, &raw_ingredients)?;
+    check_loaded(&new, &raw_ingredients)?;
+    diff::print_diff(&old, &new, &raw_ingredients);
+    Ok(())
+}
+
+fn run_export(args: &ExportArgs) -> Result<(), Error> {
+    let gram_proposal: Proposal = read_data(&args.proposal)?;
+    let (_, raw_ingredients) = load_ingredients(&args.ingredients)?;
+    check_loaded(&gram_proposal, &raw_ingredients)?;
+    let report = Report::new(&gram_proposal, &raw_ingredients, Rounding::default());
+    let meal = args
+        .meal
+        .as_deref()
+        .unwrap_or_else(|| args.tracker.default_meal());
+    let writer: Box<dyn Write> = match &args.out {
+        Some(path) => Box::new(File::create(path).map_err(|source| Error::Write {
+            path: path.clone(),
+            source,
+        })?),
+        None => Box::new(std::io::stdout()),
+    };
+    export::write_csv(&report.ingredients, args.tracker, &args.date, meal, writer)?;
+    Ok(())
+}
+
+/// Adds the nutrients that the target limits or any ingredient declares and the target's metrics
+/// to the report of a plan, of its rounded grams like the report's table
+fn add_nutrients(
+    report: &mut Report,
+    gram_proposal: &Proposal,
+    target: &Target,
+    raw_ingredients: &RawIngredients,
+    rounding: Rounding,
+) {
+    let gram_proposal = &rounding.grams(gram_proposal, raw_ingredients);
+    let macros = Macros::from_grams(gram_proposal, raw_ingredients);
+    report.sodium = output::Limited::of(macros.sodium_mg, target.max_sodium_mg);
+    report.glycemic_load = output::Limited::of(macros.glycemic_load, target.max_glycemic_load);
+    report.water =
+        output::Limited::between(macros.water_ml, target.min_water_ml, target.max_water_ml);
+    report.caffeine = output::Limited::between(
+        macros.caffeine_mg,
+        target.min_caffeine_mg,
+        target.max_caffeine_mg,
+    );
+    report.quality_protein = output::QualityProtein::of(&macros, target.min_quality_protein_pct);
+    report.leucine = output::Limited::between(macros.leucine_g, target.min_leucine_g, None);
+    report.eaa = output::Limited::between(macros.eaa_g, target.min_eaa_g, None);
+    report.omega = output::OmegaRatio::of(&macros, target.max_omega6_to_omega3);
+    report.prep_minutes = output::Limited::of(
+        prep_minutes(gram_proposal, raw_ingredients),
+        target.max_prep_minutes,
+    );
+    report.metrics = metrics::values(target.metrics(), &macros);
+}
+
+/// Prints the table and macros of a mix that isn't scaled to a target, and how it compares to
+/// the target if there is one
+fn print_mix(gram_proposal: &Proposal, raw_ingredients: &RawIngredients, target: Option<&Target>) {
+    let rounding = Rounding::default();
+    Report::new(gram_proposal, raw_ingredients, rounding)
+        .write_table(
+            std::io::stdout(),
+            target.map(|target| target.ratio(rounding)).as_ref(),
+        )
+        .unwrap();
+    let macros = Macros::from_grams(gram_proposal, raw_ingredients);
+    let kcal = macros.kcal.round() as u64;
+    println!("Results in {}.", macros.summary(kcal));
+    if let Some(target) = target {
+        println!(
+            "That is {}% of the target's {} kcal.",
+            (100.0 * macros.kcal / target.kcal as f64).round(),
+            target.kcal
+        );
+        if !target.metrics().is_empty() {
+            println!("Metrics {}.", metrics::summary(target.metrics(), &macros));
+        }
+    }
+}
+
+fn run_stock(args: &StockArgs) -> Result<(), Error> {
+    let mut gram_proposals = Vec::new();
+    for meal_path in &args.meals {
+        gram_proposals.push(read_data::<Meal>(meal_path)?.grams());
+    }
+    let total = shopping::aggregate(&gram_proposals);
+    let inventory = stock::recommend(&total, args.weeks, args.margin_pct);
+    stock::print_inventory(&inventory);
+    if let Some(path) = &args.out {
+        write_toml(path, &inventory)?;
+    }
+    Ok(())
+}
+
+fn run_consume(args: &ConsumeArgs) -> Result<(), Error> {
+    let gram_proposal: Proposal = read_data(&args.proposal)?;
+    let mut inventory: stock::Inventory = read_data(&args.inventory)?;
+    for note in stock::consume(&mut inventory, &gram_proposal, args.days) {
+        eprintln!("warning: {}", note);
+    }
+    stock::print_inventory(&inventory);
+    write_data(&args.inventory, &inventory)
+}
+
+fn run_log(args: &LogArgs) -> Result<(), Error> {
+    let gram_proposal: Proposal = read_data(&args.proposal)?;
+    let (_, raw_ingredients) = load_ingredients(&args.ingredients)?;
+    check_loaded(&gram_proposal, &raw_ingredients)?;
+    let macros = Macros::from_grams(&gram_proposal, &raw_ingredients);
+    let target = match &args.target {
+        Some(path) => {
+            // the plan was optimized for what's left after the target's meals
+            let target = meal::reconcile(&read_target_file(path)?, &[])?;
+            let ratio = target.ratio(Rounding::default());
+            Some(history::DayTarget {
+                kcal: target.kcal,
+                carb: ratio.carb,
+                fat: ratio.fat,
+                protein: ratio.protein,
+            })
+        }
+        None => None,
+    };
+    let day = history::Day {
+        date: args.date.unwrap_or_else(stock::Date::today),
+        grams: gram_proposal,
+        kcal: macros.kcal.round(),
+        carb: round_decigram(macros.carb),
+        fat: round_decigram(macros.fat),
+        protein: round_decigram(macros.protein),
+        weight_kg: args.weight_kg,
+        target,
+    };
+    history::append(&args.history, &day)?;
+    println!(
+        "Logged {} kcal on {} to {}.",
+        day.kcal,
+        String::from(day.date),
+        args.history.display()
+    );
+    Ok(())
+}
+
+fn run_stats(args: &StatsArgs) -> Result<(), Error> {
+    let days = history::read(&args.history)?;
+    history::print_stats(&history::weeks(&days, args.weight_kg));
+    Ok(())
+}
+
+fn run_blend(args: &BlendArgs) -> Result<(), Error> {
+    let (ingredients, raw_ingredients) = load_ingredients(&args.ingredients)?;
+    let sum = args.carb + args.fat + args.protein;
+    let target = NormalizedTarget {
+        carb: args.carb / sum,
+        fat: args.fat / sum,
+        protein: args.protein / sum,
+        ..Default::default()
+    };
+    // the blend's size is only known in grams, so bounds of the ingredients don't apply
+    let mut constraints = TargetConstraints::new();
+    for (name, pct) in &args.max_weight_pct {
+        if !raw_ingredients.0.contains_key(name) {
+            return Err(raw_ingredients.missing(name));
+        }
+        constraints
+            .max_weight_share
+            .insert(name.clone(), pct / 100.0);
+    }
+    let proposal = optimize(&target, constraints, &ingredients, args.steps)?;
+    let batch = blend::batch_grams(&proposal, &raw_ingredients, args.batch_g);
+    let profile = Macros {
+        carb: args.carb,
+        fat: args.fat,
+        protein: args.protein,
+        ..Default::default()
+    };
+    blend::print_blend(&batch, &raw_ingredients, &profile, args.scoop_g);
+    Ok(())
+}
+
+/// Writes the imported food as an ingredient file or prints it
+fn write_imported(entry: &import::FoodEntry, args: &ImportedIngredientArgs) -> Result<(), Error> {
+    let mut ingredient = entry.to_ingredient();
+    if let Some(name) = &args.name {
+        ingredient.name = name.clone();
+    }
+    match &args.out {
+        Some(path) => write_toml(path, &ingredient),
+        None => {
+            let contents = toml::to_string(&ingredient).map_err(|source| Error::Serialize {
+                path: PathBuf::from("-"),
+                source,
+            })?;
+            print!("{}", contents);
+            Ok(())
+        }
+    }
+}
+
+fn run_add_ingredient(args: &AddIngredientArgs) -> Result<(), Error> {
+    let ingredient =
+        wizard::ask(std::io::stdin().lock(), std::io::stdout()).map_err(|source| Error::Read {
+            path: PathBuf::from("-"),
+            source,
+        })?;
+    let path = pantry::file_path(&args.ingredients_dir, &ingredient.name)?;
+    if path.exists() {
+        return Err(Error::FileExists(path));
+    }
+    write_toml(&path, &ingredient)?;
+    println!("Wrote {}", path.display());
+    Ok(())
+}
+
+fn run_import(source: &ImportSource) -> Result<(), Error> {
+    match source {
+        ImportSource::Usda(args) => {
+            let query = import::usda::Query::parse(&args.query);
+            let entry = match &args.csv_dir {
+                Some(dir) => import::usda::from_csv(dir, &query)?,
+                #[cfg(feature = "http")]
+                None => import::usda::from_api(&args.api_key, &query)?,
+                #[cfg(not(feature = "http"))]
+                None => unreachable!("--csv-dir is required without the http feature"),
+            };
+            write_imported(&entry, &args.ingredient)
+        }
+        #[cfg(feature = "http")]
+        ImportSource::Off(args) => {
+            write_imported(&import::off::from_api(&args.barcode)?, &args.ingredient)
+        }
+        ImportSource::Bls(args) => write_imported(
+            &import::table::from_csv(&args.csv, &import::table::BLS, &args.query)?,
+            &args.ingredient,
+        ),
+        ImportSource::SwissFcd(args) => write_imported(
+            &import::table::from_csv(&args.csv, &import::table::SWISS_FCD, &args.query)?,
+            &args.ingredient,
+        ),
+    }
+}
+
+fn run_calc_target(args: &CalcTargetArgs) -> Result<(), Error> {
+    let stats = calculator::BodyStats {
+        weight_kg: args.weight_kg,
+        height_cm: args.height_cm,
+        age: args.age,
+        sex: args.sex,
+        activity: args.activity,
+    };
+    let protein_per_kg = args
+        .protein_per_kg
+        .unwrap_or_else(|| args.goal.protein_g_per_kg());
+    let macros = calculator::DailyMacros::new(&stats, args.goal, protein_per_kg, args.fat_pct)?;
+    let summary = format!(
+        "# BMR {} kcal, TDEE {} kcal\n# {}g carb, {}g fat, {}g protein in {} kcal\n",
+        stats.bmr().round(),
+        stats.tdee().round(),
+        macros.carb.round(),
+        macros.fat.round(),
+        macros.protein.round(),
+        macros.kcal
+    );
+    let target = macros.target();
+    match &args.out {
+        Some(path) => {
+            print!("{}", summary);
+            write_toml(path, &target)
+        }
+        None => {
+            let contents = toml::to_string(&target).map_err(|source| Error::Serialize {
+                path: PathBuf::from("-"),
+                source,
+            })?;
+            print!("{}{}", summary, contents);
+            Ok(())
+        }
+    }
+}
+
+fn run_pantry(command: &PantryCommand) -> Result<(), Error> {
+    match command {
+        PantryCommand::Diff(args) => {
+            let pantry = pantry::Pantry::load(&args.ingredients_dir)?;
+            let other = pantry::Pantry::load(&args.other_dir)?;
+            // as a value, so tables are written after the other fields
+            let patch = toml::Value::try_from(pantry::diff(&pantry, &other)).map_err(|source| {
+                Error::Serialize {
+                    path: args.out.clone().unwrap_or_else(|| PathBuf::from("-")),
+                    source,
+                }
+            })?;
+            match &args.out {
+                Some(path) => write_toml(path, &patch),
+                None => {
+                    print!("{}", patch);
+                    Ok(())
+                }
+            }
+        }
+        PantryCommand::Effective(args) => {
+            for (name, overlaid) in overlay_pantry(args)? {
+                if !args.is_selected(&name) {
+                    continue;
+                }
+                let (path, replaced) = overlaid.paths.split_last().unwrap();
+                print!("{}: {}", name, path.display());
+                if let PantryItem::Recipe(_) = overlaid.item {
+                    print!(" (recipe)");
+                }
+                if !replaced.is_empty() {
+                    let replaced: Vec<String> = replaced
+                        .iter()
+                        .map(|path| path.display().to_string())
+                        .collect();
+                    print!(", replaces {}", replaced.join(", "));
+                }
+                println!();
+            }
+            Ok(())
+        }
+        PantryCommand::Apply(args) => {
+            let pantry = pantry::Pantry::load(&args.ingredients_dir)?;
+            let patch: pantry::Patch = read_data(&args.patch)?;
+            let edits = match pantry::apply(&pantry, &patch, &args.ingredients_dir) {
+                Ok(edits) => edits,
+                Err(conflicts) => {
+                    for conflict in &conflicts {
+                        println!("conflict: {}", conflict);
+                    }
+                    return Err(Error::PatchConflicts(conflicts.len()));
+                }
+            };
+            for edit in &edits {
+                match edit {
+                    pantry::Edit::Write { path, fields } => {
+                        // as a value, so tables are written after the other fields
+                        write_data(path, &toml::Value::Table(fields.clone()))?;
+                        println!("wrote {}", path.display());
+                    }
+                    pantry::Edit::Remove(path) => {
+                        std::fs::remove_file(path).map_err(|source| Error::Write {
+                            path: path.clone(),
+                            source,
+                        })?;
+                        println!("removed {}", path.display());
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Runs the subcommand
+pub fn run(command: &Command) -> Result<(), Error> {
+    match command {
+        Command::Optimize(args) => run_optimize(args),
+        Command::Validate(args) => run_validate(args),
+        Command::Show(args) => run_show(args),
+        Command::ShoppingList(args) => run_shopping_list(args),
+        Command::Portion(args) => run_portion(args),
+        Command::Mix(args) => run_mix(args),
+        Command::Reeval(args) => run_reeval(args),
+        Command::Diff(args) => run_diff(args),
+        Command::Export(args) => run_export(args),
+        Command::Stock(args) => run_stock(args),
+        Command::Consume(args) => run_consume(args),
+        Command::Log(args) => run_log(args),
+        Command::Stats(args) => run_stats(args),
+        Command::AddIngredient(args) => run_add_ingredient(args),
+        Command::Blend(args) => run_blend(args),
+        Command::CalcTarget(args) => run_calc_target(args),
+        Command::Import(source) => run_import(source),
+        Command::Ingredients(command) => run_pantry(command),
+        Command::Examples(ExamplesCommand::Generate { dir }) => examples::generate(dir),
+        #[cfg(feature = "server")]
+        Command::Serve { addr } => server::serve(addr),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_normalize() {
+        let i = Ingredient {
+            name: "foo".to_string(),
+            g: 1000,
+            kcal: 100.0,
+            carb: 300.0,
+            fat: 200.0,
+            protein: 100.0,
+            ..Default::default()
+        };
+        let normalized = i.normalize();
+        assert_eq!(normalized.carb.round() as u64, 3);
+        assert_eq!(normalized.fat.round() as u64, 2);
+        assert_eq!(normalized.protein.round() as u64, 1);
+    }
+
+    #[test]
+    fn test_saved_proposal() {
+        let mut gram_proposal = Proposal(BTreeMap::new());
+        gram_proposal.0.insert("olive oil".to_string(), 12);
+        gram_proposal.0.insert("oats".to_string(), 125);
+        let saved = toml::to_string(&gram_proposal).unwrap();
+        assert_eq!(saved, "oats = 125\n\"olive oil\" = 12\n");
+        assert_eq!(toml::from_str::<Proposal>(&saved).unwrap(), gram_proposal);
+    }
+
+    #[test]
+    fn test_start_from() {
+        let target: Target =
+            toml::from_str("kcal = 1000\ncarb = 50\nfat = 25\nprotein = 25").unwrap();
+        let mut raw_ingredients = RawIngredients(BTreeMap::new());
+        let mut ingredients = Ingredients(BTreeMap::new());
+        // two brands of the same muesli, the search alone only uses the first
+        for name in ["muesli a", "muesli b"] {
+            let mut ingredient = Ingredient {
+                name: name.to_string(),
+                g: 100,
+                carb: 50.0,
+                fat: 11.1,
+                protein: 25.0,
+                ..Default::default()
+            };
+            ingredient.kcal = ingredient.macro_kcal();
+            ingredients
+                .0
+                .insert(ingredient.name.clone(), ingredient.normalize());
+            raw_ingredients
+                .0
+                .insert(ingredient.name.clone(), ingredient);
+        }
+        let constraints = TargetConstraints::from_target(&target, &raw_ingredients, 100).unwrap();
+        let proposal = optimize(&target.normalize(), constraints.clone(), &ingredients, 100);
+        assert_eq!(proposal.unwrap().0["muesli b"], 0);
+
+        let mut start = Proposal(BTreeMap::new());
+        start.0.insert("muesli b".to_string(), 300);
+        start.0.insert("granola".to_string(), 50);
+        let mut constraints = constraints;
+        let missing = constraints.start_from(&start, &target, &raw_ingredients, 100);
+        assert_eq!(missing, vec!["granola"]);
+        let proposal = optimize(&target.normalize(), constraints, &ingredients, 100).unwrap();
+        assert_eq!(proposal.0["muesli b"], 90);
+        assert_eq!(proposal.kcal(), 100);
+    }
+
+    #[test]
+    fn test_keep_close_to() {
+        let target: Target = toml::from_str(
+            "kcal = 1000
+carb = 50
+fat = 25
+protein = 25",
+        )
+        .unwrap();
+        let mut raw_ingredients = RawIngredients(BTreeMap::new());
+        let mut ingredients = Ingredients(BTreeMap::new());
+        for name in ["muesli a", "muesli b"] {
+            let mut ingredient = Ingredient {
+                name: name.to_string(),
+                g: 100,
+                carb: 50.0,
+                fat: 11.1,
+                protein: 25.0,
+                ..Default::default()
+            };
+            ingredient.kcal = ingredient.macro_kcal();
+            ingredients
+                .0
+                .insert(ingredient.name.clone(), ingredient.normalize());
+            raw_ingredients
+                .0
+                .insert(ingredient.name.clone(), ingredient);
+        }
+        let mut constraints =
+            TargetConstraints::from_target(&target, &raw_ingredients, 100).unwrap();
+        let mut reference = Proposal(BTreeMap::new());
+        reference.0.insert("muesli a".to_string(), 100);
+        reference.0.insert("muesli b".to_string(), 300);
+        let missing = constraints.keep_close_to(&reference, &target, &raw_ingredients, 100, 0.01);
+        assert!(missing.is_empty());
+        let proposal = optimize(&target.normalize(), constraints, &ingredients, 100).unwrap();
+        assert_eq!(proposal.0["muesli a"], 25);
+        assert_eq!(proposal.0["muesli b"], 75);
+    }
+
+    #[test]
+    fn test_resolve_grams() {
+        let read = |values: &str| {
+            let mut target: Target = toml::from_str(values).unwrap();
+            target
+                .resolve_grams()
+                .map(|_| (target.kcal, target.carb, target.fat, target.protein))
+        };
+        // 180g protein and 70g fat leave 305g carbs
+        assert_eq!(
+            read("kcal = 2570\nprotein_g = 180\nfat_g = 70").unwrap(),
+            (2570, 55, 13, 32)
+        );
+        assert_eq!(
+            read("carb_g = 200\nfat_g = 50\nprotein_g = 150").unwrap(),
+            (1850, 50, 13, 37)
+        );
+        assert_eq!(
+            read("kcal = 2000\ncarb = 40\nfat = 30\nprotein = 30").unwrap(),
+            (2000, 40, 30, 30)
+        );
+        assert!(read("kcal = 2000").is_err());
+        assert!(read("protein_g = 180\nfat_g = 70").is_err());
+        assert!(read("kcal = 1000\nprotein_g = 180\nfat_g = 70").is_err());
+        assert!(read("kcal = 2000\ncarb = 40\nfat = 30\nprotein = 30\nprotein_g = 150").is_err());
+    }
+
+    #[test]
+    fn test_resolve_per() {
+        let read = |values: &str| {
+            let mut ingredient: Ingredient = toml::from_str(&format!(
+                "name = \"bar\"\ncarb = 20\nfat = 9\nprotein = 20\n{}",
+                values
+            ))
+            .unwrap();
+            ingredient.resolve_per().map(|_| ingredient.g)
+        };
+        assert_eq!(read("g = 50").unwrap(), 50);
+        assert_eq!(read("per = \"100g\"").unwrap(), 100);
+        assert_eq!(read("g = 100\nper = \"100g\"").unwrap(), 100);
+        assert_eq!(read("per = \"serving\"\nserving_g = 60").unwrap(), 60);
+        assert!(read("").is_err());
+        assert!(read("g = 50\nper = \"100g\"").is_err());
+        assert!(read("per = \"serving\"").is_err());
+        assert!(read("g = 100\nper = \"serving\"\nserving_g = 60").is_err());
+    }
+
+    #[test]
+    fn test_kcal_mismatch() {
+        let mut i = Ingredient {
+            name: "oats".to_string(),
+            g: 100,
+            kcal: 380.0,
+            carb: 60.0,
+            fat: 7.0,
+            protein: 13.0,
+            ..Default::default()
+        };
+        assert_eq!(i.macro_kcal(), 355.0);
+        assert_eq!(i.kcal_mismatch(10.0), None);
+        i.kcal = 250.0;
+        assert_eq!(
+            i.kcal_mismatch(10.0).unwrap(),
+            "oats declares 250 kcal but its macros have 355 kcal (30% off)"
+        );
+        // 0.4 kcal of protein is a lot less than 2 kcal but too little to matter
+        let coffee = Ingredient {
+            name: "coffee".to_string(),
+            g: 100,
+            kcal: 2.0,
+            protein: 0.1,
+            ..Default::default()
+        };
+        assert_eq!(coffee.kcal_mismatch(10.0), None);
+        let water = Ingredient {
+            name: "water".to_string(),
+            g: 100,
+            ..Default::default()
+        };
+        assert_eq!(water.kcal_mismatch(10.0), None);
+    }
+
+    #[test]
+    fn test_alcohol_polyols() {
+        let beer = Ingredient {
+            name: "beer".to_string(),
+            g: 500,
+            carb: 18.0,
+            protein: 2.5,
+            alcohol: Some(20.0),
+            ..Default::default()
+        };
+        assert_eq!(beer.macro_kcal(), 222.0);
+        let chocolate = Ingredient {
+            name: "sugar-free chocolate".to_string(),
+            g: 100,
+            carb: 50.0,
+            fat: 30.0,
+            protein: 10.0,
+            polyols: Some(40.0),
+            ..Default::default()
+        };
+        assert_eq!(chocolate.macro_kcal(), 40.0 + 96.0 + 270.0 + 40.0);
+
+        // the same macros with alcohol are further from the target
+        let target = NormalizedTarget {
+            carb: 0.5,
+            fat: 0.25,
+            protein: 0.25,
+            ..Default::default()
+        };
+        let mut ingredients = Ingredients(BTreeMap::new());
+        for (name, alcohol) in &[("bread", 0.0), ("beer", 0.1)] {
+            ingredients.0.insert(
+                name.to_string(),
+                NormalizedIngredient {
+                    carb: 0.1,
+                    fat: 0.05,
+                    protein: 0.05,
+                    quality_protein: 0.0,
+                    leucine_g: 0.0,
+                    eaa_g: 0.0,
+                    omega3_g: 0.0,
+                    omega6_g: 0.0,
+                    alcohol: *alcohol,
+                    sat_fat: 0.0,
+                    sugar: 0.0,
+                    sodium_mg: 0.0,
+                    water_ml: 0.0,
+                    caffeine_mg: 0.0,
+                    glycemic_load: 0.0,
+                    g: 1.0,
+                },
+            );
+        }
+        let mut bread = Proposal(BTreeMap::new());
+        bread.0.insert("bread".to_string(), 1);
+        let mut beer = Proposal(BTreeMap::new());
+        beer.0.insert("beer".to_string(), 1);
+        assert_eq!(target.evaluate(&bread, &ingredients), 0.0);
+        assert!(target.evaluate(&beer, &ingredients) > 0.1);
+    }
+
+    #[test]
+    fn test_sub_macro_limit() {
+        let target: Target = toml::from_str(
+            "kcal = 2000\ncarb = 50\nfat = 25\nprotein = 25\n\
+             [sugar_at_most]\npct_kcal = 10\ng = 25",
+        )
+        .unwrap();
+        let normalized = target.normalize();
+        assert_eq!(normalized.sugar_kcal_share, Some(0.05));
+        assert_eq!(normalized.sat_fat_kcal_share, None);
+
+        // the same macros, but the cereal is mostly sugar
+        let bread = Ingredient {
+            name: "bread".to_string(),
+            g: 100,
+            carb: 50.0,
+            fat: 11.0,
+            protein: 25.0,
+            ..Default::default()
+        };
+        let cereal = Ingredient {
+            name: "cereal".to_string(),
+            sugar: Some(30.0),
+            ..bread.clone()
+        };
+        let mut raw_ingredients = RawIngredients(BTreeMap::new());
+        let mut ingredients = Ingredients(BTreeMap::new());
+        for mut ingredient in [bread, cereal] {
+            ingredient.kcal = ingredient.macro_kcal();
+            ingredients
+                .0
+                .insert(ingredient.name.clone(), ingredient.normalize());
+            raw_ingredients
+                .0
+                .insert(ingredient.name.clone(), ingredient);
+        }
+        let proposal = optimize(&normalized, TargetConstraints::new(), &ingredients, 100).unwrap();
+        assert_eq!((proposal.0["bread"], proposal.0["cereal"]), (100, 0));
+
+        let mut gram_proposal = Proposal(BTreeMap::new());
+        gram_proposal.0.insert("cereal".to_string(), 100);
+        let macros = Macros::from_grams(&gram_proposal, &raw_ingredients);
+        assert_eq!(
+            macros.summary(399),
+            "50g carb (of which 30g sugar), 11g fat, 25g protein in 399 kcal (58:13:29)"
+        );
+    }
+
+    #[test]
+    fn test_water_and_caffeine() {
+        let base = "kcal = 1000\ncarb = 0\nfat = 30\nprotein = 70";
+        let raw_ingredients = test_raw_ingredients(vec![
+            test_ingredient("chicken", 0.0, 2.0, 23.0),
+            Ingredient {
+                water_ml: Some(90.0),
+                ..test_ingredient("protein milk", 0.0, 1.5, 10.0)
+            },
+            test_ingredient("olive oil", 0.0, 100.0, 0.0),
+            Ingredient {
+                caffeine_mg: Some(100.0),
+                ..test_ingredient("coffee shake", 0.0, 3.0, 20.0)
+            },
+        ]);
+        let optimize_macros = |target_toml: &str| {
+            let gram_proposal = test_plan(target_toml, &raw_ingredients, 200).unwrap();
+            Macros::from_grams(&gram_proposal, &raw_ingredients)
+        };
+        let limited = optimize_macros(&format!(
+            "{}\nmin_water_ml = 1000\nmax_caffeine_mg = 50",
+            base
+        ));
+        assert!(limited.caffeine_mg <= 50.0, "{}", limited.caffeine_mg);
+        assert!(limited.water_ml >= 900.0, "{}", limited.water_ml);
+        assert!(optimize_macros(base).water_ml < limited.water_ml);
+        assert_eq!(
+            output::Limited::between(800.0, Some(1000.0), Some(4000.0))
+                .unwrap()
+                .summary("Water", " ml"),
+            "Water 800 ml, 80% of the minimum of 1000 ml and 20% of the limit of 4000 ml"
+        );
+    }
+
+    #[test]
+    fn test_kcal_free_water() {
+        let water = |name: &str, caffeine_mg| Ingredient {
+            water_ml: Some(100.0),
+            caffeine_mg,
+            ..test_ingredient(name, 0.0, 0.0, 0.0)
+        };
+        let raw_ingredients = test_raw_ingredients(vec![
+            test_ingredient("rice", 28.0, 1.0, 3.0),
+            test_ingredient("chicken", 0.0, 1.0, 30.0),
+            water("water", None),
+            water("cold brew", Some(50.0)),
+        ]);
+        // kcal-free ingredients have no kcal pieces
+        assert_eq!(raw_ingredients.normalize().0.len(), 2);
+        let target_toml = "kcal = 1000\ncarb = 50\nfat = 10\nprotein = 40\n\
+             min_water_ml = 2000\nmax_caffeine_mg = 100\n\
+             [[constraint_at_least]]\nname = \"cold brew\"\ng = 200";
+        let target: Target = toml::from_str(target_toml).unwrap();
+        assert_eq!(
+            search_target(&target, &raw_ingredients).min_water_ml_per_kcal,
+            None
+        );
+        let gram_proposal = test_plan(target_toml, &raw_ingredients, 200).unwrap();
+        // more cold brew would exceed the caffeine, so the rest is water
+        assert_eq!(gram_proposal.0["cold brew"], 200);
+        assert_eq!(gram_proposal.0["water"], 1800);
+        let macros = Macros::from_grams(&gram_proposal, &raw_ingredients);
+        assert!((macros.kcal - 1000.0).abs() < 10.0, "{}", macros.kcal);
+        assert!(macros.water_ml.is_finite() && macros.water_ml >= 2000.0);
+    }
+
+    #[test]
+    fn test_prep_time() {
+        let base = "kcal = 1000\ncarb = 45\nfat = 20\nprotein = 35";
+        let raw_ingredients = test_raw_ingredients(vec![
+            Ingredient {
+                prep_minutes: Some(20.0),
+                ..test_ingredient("chicken", 0.0, 3.0, 30.0)
+            },
+            Ingredient {
+                prep_minutes: Some(25.0),
+                ..test_ingredient("rice", 28.0, 0.5, 3.0)
+            },
+            test_ingredient("cheese", 1.0, 30.0, 25.0),
+        ]);
+        let unlimited = test_plan(base, &raw_ingredients, 200).unwrap();
+        assert_eq!(prep_minutes(&unlimited, &raw_ingredients), 45.0);
+        let limited = test_plan(
+            &format!("{}\nmax_prep_minutes = 30", base),
+            &raw_ingredients,
+            200,
+        )
+        .unwrap();
+        assert!(prep_minutes(&limited, &raw_ingredients) <= 30.0);
+        let constrained = format!(
+            "{}\nmax_prep_minutes = 30\n\
+             [[constraint_at_least]]\nname = \"chicken\"\ng = 50\n\
+             [[constraint_exact]]\nname = \"rice\"\ng = 100",
+            base
+        );
+        assert!(matches!(
+            test_plan(&constrained, &raw_ingredients, 200),
+            Err(Error::ConstraintsExceedLimit { amount, .. }) if amount == 45.0
+        ));
+    }
+
+    #[test]
+    fn test_quality_protein() {
+        // the same macros, but gluten is missing lysine
+        let protein = |name: &str, protein_score| Ingredient {
+            protein_score: Some(protein_score),
+            ..test_ingredient(name, 14.0, 3.0, 75.0)
+        };
+        let raw_ingredients =
+            test_raw_ingredients(vec![protein("gluten", 0.25), protein("whey", 1.0)]);
+        let gram_proposal = test_plan(
+            "kcal = 1000\ncarb = 20\nfat = 10\nprotein = 70\nmin_quality_protein_pct = 80",
+            &raw_ingredients,
+            100,
+        )
+        .unwrap();
+        let macros = Macros::from_grams(&gram_proposal, &raw_ingredients);
+        let quality = output::QualityProtein::of(&macros, Some(80.0)).unwrap();
+        assert!(quality.pct >= 80.0, "{:?}", quality);
+        assert_eq!(
+            output::QualityProtein {
+                g: 120.0,
+                pct: 80.0,
+                min_pct: Some(60.0)
+            }
+            .summary(),
+            "High-quality protein 120g, 80% of the protein, at least 60% wanted"
+        );
+    }
+
+    #[test]
+    fn test_leucine() {
+        let base = "kcal = 500\ncarb = 20\nfat = 10\nprotein = 70\nmeal = \"dinner\"";
+        // the same macros, but rice protein has less leucine than whey
+        let protein = |name: &str, leucine_g: f64| Ingredient {
+            leucine_g: Some(leucine_g),
+            eaa_g: Some(leucine_g * 4.0),
+            ..test_ingredient(name, 14.0, 3.0, 75.0)
+        };
+        let raw_ingredients =
+            test_raw_ingredients(vec![protein("rice protein", 6.0), protein("whey", 10.0)]);
+        let optimize_macros = |target_toml: &str| {
+            let gram_proposal = test_plan(target_toml, &raw_ingredients, 100).unwrap();
+            Macros::from_grams(&gram_proposal, &raw_ingredients)
+        };
+        // about 115g of powder, so whey alone reaches the threshold
+        let macros = optimize_macros(&format!("{}\nmin_leucine_g = 11", base));
+        assert!(macros.leucine_g >= 10.5, "{}", macros.leucine_g);
+        assert!(optimize_macros(base).leucine_g < macros.leucine_g);
+        assert!((macros.eaa_g - macros.leucine_g * 4.0).abs() < 1e-6);
+        assert_eq!(
+            output::Limited::between(2.0, Some(2.5), None)
+                .unwrap()
+                .summary("Leucine", "g"),
+            "Leucine 2g, 80% of the minimum of 2.5g"
+        );
+    }
+
+    #[test]
+    fn test_omega_ratio() {
+        let oil = |name: &str, omega3_g, omega6_g| Ingredient {
+            omega3_g,
+            omega6_g: Some(omega6_g),
+            ..test_ingredient(name, 0.0, 100.0, 0.0)
+        };
+        let raw_ingredients = test_raw_ingredients(vec![
+            Ingredient {
+                omega6_g: Some(0.5),
+                ..test_ingredient("chicken", 0.0, 2.0, 23.0)
+            },
+            oil("sunflower oil", None, 65.0),
+            oil("linseed oil", Some(53.0), 13.0),
+        ]);
+        let gram_proposal = test_plan(
+            "kcal = 1000\ncarb = 0\nfat = 30\nprotein = 70\nmax_omega6_to_omega3 = 4",
+            &raw_ingredients,
+            200,
+        )
+        .unwrap();
+        let macros = Macros::from_grams(&gram_proposal, &raw_ingredients);
+        assert!(
+            macros.omega6_g <= 4.0 * macros.omega3_g,
+            "{} {}",
+            macros.omega6_g,
+            macros.omega3_g
+        );
+        let omega = output::OmegaRatio {
+            omega3_g: 3.0,
+            omega6_g: 12.0,
+            ratio: Some(4.0),
+            max_ratio: Some(5.0),
+        };
+        assert_eq!(
+            omega.summary(),
+            "Omega-6 12g and omega-3 3g, a ratio of 4:1, at most 5:1 wanted"
+        );
+    }
+
+    #[test]
+    fn test_sodium_budget() {
+        let target_toml = "kcal = 1000\ncarb = 0\nfat = 30\nprotein = 70\nmax_sodium_mg = 1500";
+        // ham matches the ratio but is salty
+        let raw_ingredients = test_raw_ingredients(vec![
+            Ingredient {
+                sodium_mg: Some(1000.0),
+                ..test_ingredient("ham", 0.0, 4.0, 21.0)
+            },
+            Ingredient {
+                sodium_mg: Some(70.0),
+                ..test_ingredient("chicken", 0.0, 2.0, 23.0)
+            },
+        ]);
+        let gram_proposal = test_plan(target_toml, &raw_ingredients, 200).unwrap();
+        let sodium_mg = Macros::from_grams(&gram_proposal, &raw_ingredients).sodium_mg;
+        assert!(sodium_mg <= 1500.0, "{}", sodium_mg);
+        assert!(gram_proposal.0["ham"] > 0);
+        assert_eq!(
+            output::Limited::of(1200.4, Some(1500.0))
+                .unwrap()
+                .summary("Sodium", " mg"),
+            "Sodium 1200 mg, 80% of the limit of 1500 mg"
+        );
+
+        let at_least = format!(
+            "{}\n[[constraint_at_least]]\nname = \"ham\"\ng = 200",
+            target_toml
+        );
+        let target: Target = toml::from_str(&at_least).unwrap();
+        assert!(matches!(
+            TargetConstraints::from_target(&target, &raw_ingredients, 200),
+            Err(Error::ConstraintsExceedLimit { .. })
+        ));
+    }
+
+    #[test]
+    fn test_glycemic_load() {
+        // the same macros, but white rice raises blood sugar faster
+        let white_rice = Ingredient {
+            glycemic_index: Some(73.0),
+            ..test_ingredient("white rice", 80.0, 4.5, 10.0)
+        };
+        assert_eq!(white_rice.glycemic_load(), 58.4);
+        let lentils = Ingredient {
+            name: "lentils".to_string(),
+            glycemic_index: Some(32.0),
+            ..white_rice.clone()
+        };
+        let raw_ingredients = test_raw_ingredients(vec![white_rice, lentils]);
+        let gram_proposal = test_plan(
+            "kcal = 1000\ncarb = 80\nfat = 10\nprotein = 10\nmax_glycemic_load = 80",
+            &raw_ingredients,
+            200,
+        )
+        .unwrap();
+        let load = Macros::from_grams(&gram_proposal, &raw_ingredients).glycemic_load;
+        assert!(load <= 80.0, "{}", load);
+        assert_eq!(
+            output::Limited::of(load, None)
+                .unwrap()
+                .summary("Glycemic load", ""),
+            format!("Glycemic load {}", load.round())
+        );
+    }
+
+    #[test]
+    fn test_fractional_label() {
+        let milk: Ingredient = toml::from_str(
+            "name = \"milk\"\ng = 100\nkcal = 64\ncarb = 4.8\nfat = 3.5\nprotein = 3.4",
+        )
+        .unwrap();
+        assert_eq!(milk.kcal, 64.0);
+        assert_eq!(milk.protein, 3.4);
+        let normalized = milk.normalize();
+        assert!((normalized.carb - 4.8 / 64.0).abs() < 1e-12);
+    }
+
+    /// 100g of an ingredient with the macros and their kcal
+    fn test_ingredient(name: &str, carb: f64, fat: f64, protein: f64) -> Ingredient {
+        let mut ingredient = Ingredient {
+            name: name.to_string(),
+            g: 100,
+            carb,
+            fat,
+            protein,
+            ..Default::default()
+        };
+        ingredient.kcal = ingredient.macro_kcal();
+        ingredient
+    }
+
+    fn test_raw_ingredients(ingredients: Vec<Ingredient>) -> RawIngredients {
+        RawIngredients(
+            ingredients
+                .into_iter()
+                .map(|ingredient| (ingredient.name.clone(), ingredient))
+                .collect(),
+        )
+    }
+
+    /// Grams of the plan for the target, searched like the optimize command does
+    fn test_plan(
+        target_toml: &str,
+        raw_ingredients: &RawIngredients,
+        steps: usize,
+    ) -> Result<Proposal, Error> {
+        let target: Target = toml::from_str(target_toml).unwrap();
+        let constraints = TargetConstraints::from_target(&target, raw_ingredients, steps)?;
+        let proposal = optimize(
+            &search_target(&target, raw_ingredients),
+            constraints,
+            &raw_ingredients.normalize(),
+            steps,
+        )?;
+        Ok(to_grams(&proposal, &target, raw_ingredients, steps))
+    }
+
+    fn test_ingredients() -> Ingredients {
+        let mut ingredients = Ingredients(BTreeMap::new());
+        ingredients.0.insert(
+            "apple".to_string(),
+            NormalizedIngredient {
+                carb: 20.0,
+                fat: 30.0,
+                protein: 50.0,
+                quality_protein: 0.0,
+                leucine_g: 0.0,
+                eaa_g: 0.0,
+                omega3_g: 0.0,
+                omega6_g: 0.0,
+                alcohol: 0.0,
+                sat_fat: 0.0,
+                sugar: 0.0,
+                sodium_mg: 0.0,
+                water_ml: 0.0,
+                caffeine_mg: 0.0,
+                glycemic_load: 0.0,
+                g: 1.0,
+            },
+        );
+        ingredients.0.insert(
+            "banana".to_string(),
+            NormalizedIngredient {
+                carb: 40.0,
+                fat: 50.0,
+                protein: 60.0,
+                quality_protein: 0.0,
+                leucine_g: 0.0,
+                eaa_g: 0.0,
+                omega3_g: 0.0,
+                omega6_g: 0.0,
+                alcohol: 0.0,
+                sat_fat: 0.0,
+                sugar: 0.0,
+                sodium_mg: 0.0,
+                water_ml: 0.0,
+                caffeine_mg: 0.0,
+                glycemic_load: 0.0,
+                g: 2.0,
+            },
+        );
+        ingredients
+    }
+
+    #[test]
+    fn test_mix_totals() {
+        let ingredients = test_ingredients();
+        let mut proposal = Proposal(BTreeMap::new());
+        proposal.0.insert("apple".to_string(), 1);
+        let totals = MixTotals::new(&proposal, &ingredients);
+        assert_eq!(totals.carb as u64, 20);
+        assert_eq!(totals.fat as u64, 30);
+        assert_eq!(totals.protein as u64, 50);
+
+        proposal.0.clear();
+        proposal.0.insert("apple".to_string(), 2);
+        let totals = MixTotals::new(&proposal, &ingredients);
+        assert_eq!(totals.carb as u64, 40);
+        assert_eq!(totals.fat as u64, 60);
+        assert_eq!(totals.protein as u64, 100);
+        assert_eq!(totals.pieces, 2);
+
+        proposal.0.clear();
+        proposal.0.insert("apple".to_string(), 2);
+        proposal.0.insert("banana".to_string(), 1);
+        let totals = MixTotals::new(&proposal, &ingredients);
+        assert_eq!((totals.carb / 3.0).round() as u64, 27);
+        assert_eq!((totals.fat / 3.0).round() as u64, 37);
+        assert_eq!((totals.protein / 3.0).round() as u64, 53);
+
+        // adding pieces to the totals is the same as mixing the larger proposal
+        let t = NormalizedTarget {
+            carb: 0.3,
+            fat: 0.3,
+            protein: 0.4,
+            ..Default::default()
+        };
+        let added = totals.with(&ingredients.0["banana"], 3);
+        *proposal.0.get_mut("banana").unwrap() += 3;
+        let mixed = MixTotals::new(&proposal, &ingredients);
+        assert_eq!(added.pieces, mixed.pieces);
+        assert!((added.protein - mixed.protein).abs() < 1e-9);
+        assert!((t.evaluate_totals(&added) - t.evaluate(&proposal, &ingredients)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let t = NormalizedTarget {
+            carb: 0.20,
+            fat: 0.30,
+            protein: 0.50,
+            ..Default::default()
+        };
+        let ingredients = test_ingredients();
+        let mut proposal = Proposal(BTreeMap::new());
+        proposal.0.insert("apple".to_string(), 1);
+        assert_eq!(t.evaluate(&proposal, &ingredients).round() as u64, 0);
+        proposal.0.insert("apple".to_string(), 2);
+        assert_eq!(t.evaluate(&proposal, &ingredients).round() as u64, 0);
+
+        let t = NormalizedTarget {
+            carb: 0.3,
+            fat: 0.5,
+            protein: 0.2,
+            ..Default::default()
+        };
+        assert_eq!(
+            t.evaluate(&proposal, &ingredients),
+            0.1 * 0.1 + 0.2 * 0.2 + 0.3 * 0.3
+        );
+
+        let t = NormalizedTarget {
+            carb: 0.20,
+            fat: 0.30,
+            protein: 0.50,
+            ..Default::default()
+        };
+        let mut proposal = Proposal(BTreeMap::new());
+        proposal.0.insert("banana".to_string(), 1);
+        assert_eq!(t.evaluate(&proposal, &ingredients).round() as u64, 0);
+    }
+
+    #[test]
+    fn test_evaluate_macro_weight() {
+        let mut proposal = Proposal(BTreeMap::new());
+        proposal.0.insert("apple".to_string(), 2);
+        // missing protein counts twice, the weights that aren't given stay 1
+        let weighted: Target = toml::from_str(
+            "kcal = 1000\ncarb = 30\nfat = 50\nprotein = 20\n[macro_weight]\nprotein = 2",
+        )
+        .unwrap();
+        let cost = weighted
+            .normalize()
+            .evaluate(&proposal, &test_ingredients());
+        assert!((cost - (0.1 * 0.1 + 0.2 * 0.2 + 2.0 * 0.3 * 0.3)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_ingredient_paths() {
+        let dir = std::env::temp_dir().join(format!("pantry-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("dairy")).unwrap();
+        for file in &["oats.toml", "notes.txt", "dairy/quark.json"] {
+            std::fs::write(dir.join(file), "").unwrap();
+        }
+        let args = IngredientArgs {
+            ingredients_dir: vec![dir.clone()],
+            files: vec![PathBuf::from("banana.toml")],
+            exclude: Vec::new(),
+            only: Vec::new(),
+            flavorings: None,
+            on_duplicate: OnDuplicate::Error,
+            ignore_case: false,
+        };
+        let paths = ingredient_paths(&args).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            paths,
+            vec![
+                dir.join("dairy/quark.json"),
+                dir.join("oats.toml"),
+                PathBuf::from("banana.toml")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_overlay_pantry() {
+        let dir = std::env::temp_dir().join(format!("overlay-{}", std::process::id()));
+        let (base, travel) = (dir.join("base"), dir.join("travel"));
+        std::fs::create_dir_all(&base).unwrap();
+        std::fs::create_dir_all(&travel).unwrap();
+        let oats = "name = \"oats\"\ng = 100\ncarb = 60\nfat = 7\nprotein = 13";
+        std::fs::write(base.join("oats.toml"), oats).unwrap();
+        std::fs::write(travel.join("oats.toml"), format!("{}\nmax_g = 80", oats)).unwrap();
+        std::fs::write(
+            travel.join("pancake.toml"),
+            "name = \"pancake\"\n[[components]]\nname = \"oats\"\ng = 50",
+        )
+        .unwrap();
+        let args = IngredientArgs {
+            ingredients_dir: vec![base.clone(), travel.clone()],
+            files: Vec::new(),
+            exclude: Vec::new(),
+            only: Vec::new(),
+            flavorings: None,
+            on_duplicate: OnDuplicate::Error,
+            ignore_case: false,
+        };
+        let items = overlay_pantry(&args).unwrap();
+
+        // a second file of oats in the same directory
+        std::fs::write(
+            travel.join("rolled-oats.toml"),
+            format!("{}\nmax_g = 120", oats),
+        )
+        .unwrap();
+        let max_g = |on_duplicate, name: &str| {
+            let args = IngredientArgs {
+                on_duplicate,
+                ingredients_dir: args.ingredients_dir.clone(),
+                ..Default::default()
+            };
+            match &overlay_pantry(&args).unwrap()[name].item {
+                PantryItem::Ingredient(ingredient) => ingredient.max_g,
+                PantryItem::Recipe(_) => panic!("{} is an ingredient", name),
+            }
+        };
+        let duplicate = overlay_pantry(&args);
+        assert_eq!(max_g(OnDuplicate::First, "oats"), Some(80));
+        assert_eq!(max_g(OnDuplicate::Last, "oats"), Some(120));
+        assert_eq!(max_g(OnDuplicate::Rename, "oats"), Some(80));
+        assert_eq!(max_g(OnDuplicate::Rename, "oats 2"), Some(120));
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(duplicate, Err(Error::DuplicateName { name, .. }) if name == "oats"));
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(
+            items["oats"].paths,
+            vec![base.join("oats.toml"), travel.join("oats.toml")]
+        );
+        match &items["oats"].item {
+            PantryItem::Ingredient(ingredient) => assert_eq!(ingredient.max_g, Some(80)),
+            PantryItem::Recipe(_) => panic!("oats is an ingredient"),
+        }
+        assert!(matches!(items["pancake"].item, PantryItem::Recipe(_)));
+    }
+
+    #[test]
+    fn test_selected_ingredients() {
+        let args = IngredientArgs {
+            ingredients_dir: Vec::new(),
+            files: Vec::new(),
+            exclude: vec!["oats".to_string()],
+            only: vec!["oats".to_string(), "banana".to_string()],
+            flavorings: None,
+            on_duplicate: OnDuplicate::Error,
+            ignore_case: false,
+        };
+        assert!(args.is_selected("banana"));
+        assert!(!args.is_selected("oats"));
+        assert!(!args.is_selected("quark"));
+
+        let mut target: Target = toml::from_str(
+            "kcal = 2000\ncarb = 40\nfat = 30\nprotein = 30\n\
+             [[constraint_at_least]]\nname = \"oats\"\ng = 50\n\
+             [[constraint_at_least]]\nname = \"banana\"\ng = 100",
+        )
+        .unwrap();
+        target.retain_constraints(|name| args.is_selected(name));
+        let at_least = target.constraint_at_least.unwrap();
+        assert_eq!(at_least.len(), 1);
+        assert_eq!(at_least[0].name, "banana");
+    }
+
+    #[test]
+    fn test_exclude_tagged() {
+        let mut target: Target = toml::from_str(
+            "kcal = 2000\ncarb = 40\nfat = 30\nprotein = 30\n\
+             diet = \"vegetarian\"\nexclude_tags = [\"nuts\"]\nmeal = \"dinner\"\n\
+             [[constraint_at_least]]\nname = \"almonds\"\ng = 30",
+        )
+        .unwrap();
+        let mut raw_ingredients = RawIngredients(BTreeMap::new());
+        for (name, tags, meals) in [
+            ("tofu", &["vegan"][..], &["lunch", "dinner"][..]),
+            ("quark", &["vegetarian", "dairy"], &[]),
+            ("almonds", &["vegan", "nuts"], &[]),
+            ("chicken", &[], &[]),
+            ("oats", &["vegan"], &["breakfast"]),
+        ] {
+            let ingredient = Ingredient {
+                name: name.to_string(),
+                g: 100,
+                kcal: 100.0,
+                tags: tags.iter().map(|tag| tag.to_string()).collect(),
+                meals: meals.iter().map(|meal| meal.to_string()).collect(),
+                ..Default::default()
+            };
+            raw_ingredients.0.insert(name.to_string(), ingredient);
+        }
+        let mut ingredients = Ingredients(
+            raw_ingredients
+                .0
+                .iter()
+                .map(|(name, ingredient)| (name.clone(), ingredient.normalize()))
+                .collect(),
+        );
+        let excluded = exclude_tagged(&mut target, &mut ingredients, &mut raw_ingredients);
+        assert_eq!(excluded, vec!["almonds", "chicken", "oats"]);
+        let names: Vec<&String> = ingredients.0.keys().collect();
+        assert_eq!(names, vec!["quark", "tofu"]);
+        assert_eq!(raw_ingredients.0.len(), 2);
+        assert!(target.constraint_at_least.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_resolve_names() {
+        let mut target: Target = toml::from_str(
+            "kcal = 2000\ncarb = 40\nfat = 30\nprotein = 30\n\
+             [[constraint_at_least]]\nname = \"chicken\"\ng = 150\n\
+             [[constraint_max_weight_pct]]\nname = \"oats\"\npct = 20",
+        )
+        .unwrap();
+        let mut raw_ingredients = RawIngredients(BTreeMap::new());
+        for (name, aliases) in [
+            ("chicken breast", &["chicken", "chicken breast"][..]),
+            ("oats", &["rolled oats"]),
+        ] {
+            let ingredient = Ingredient {
+                name: name.to_string(),
+                aliases: aliases.iter().map(|alias| alias.to_string()).collect(),
+                ..Default::default()
+            };
+            raw_ingredients.0.insert(name.to_string(), ingredient);
+        }
+        target.resolve_names(&raw_ingredients, false).unwrap();
+        assert_eq!(
+            target.constraint_at_least.unwrap()[0].name,
+            "chicken breast"
+        );
+        assert_eq!(target.constraint_max_weight_pct.unwrap()[0].name, "oats");
+
+        let mut target: Target = toml::from_str(
+            "kcal = 2000\ncarb = 40\nfat = 30\nprotein = 30\n\
+             [[constraint_exact]]\nname = \"Rolled Oats\"\ng = 80",
+        )
+        .unwrap();
+        let mut ignoring_case = target.clone();
+        assert!(target
+            .resolve_names(&raw_ingredients, false)
+            .unwrap()
+            .is_empty());
+        let notes = ignoring_case.resolve_names(&raw_ingredients, true).unwrap();
+        assert_eq!(ignoring_case.constraint_exact.unwrap()[0].name, "oats");
+        assert_eq!(notes.len(), 1);
+        let steps = DEFAULT_OPTIMIZATION_STEPS;
+        match TargetConstraints::from_target(&target, &raw_ingredients, steps) {
+            Err(Error::MissingIngredient { name, suggestions }) => {
+                assert_eq!(name, "Rolled Oats");
+                assert_eq!(suggestions, vec!["rolled oats"]);
+            }
+            _ => panic!("Rolled Oats isn't loaded"),
+        }
+
+        let mut turkey = raw_ingredients.0["oats"].clone();
+        turkey.name = "turkey".to_string();
+        turkey.aliases = vec!["chicken".to_string()];
+        raw_ingredients.0.insert(turkey.name.clone(), turkey);
+        assert!(matches!(
+            raw_ingredients.aliases(),
+            Err(Error::AmbiguousAlias { alias, .. }) if alias == "chicken"
+        ));
+    }
+
+    #[test]
+    fn test_optimization_steps() {
+        let target: Target =
+            toml::from_str("kcal = 3000\ncarb = 40\nfat = 30\nprotein = 30").unwrap();
+        assert_eq!(target.optimization_steps(None), DEFAULT_OPTIMIZATION_STEPS);
+        assert_eq!(target.optimization_steps(Some(100)), 100);
+
+        let target: Target =
+            toml::from_str("kcal = 3000\ncarb = 40\nfat = 30\nprotein = 30\nresolution = 6000")
+                .unwrap();
+        assert_eq!(target.optimization_steps(None), 6000);
+        assert_eq!(target.optimization_steps(Some(100)), 100);
+
+        assert_eq!(target.coarse_resolution(6000), None);
+        assert_eq!(
+            target.coarse_resolution(400).unwrap(),
+            "400 steps make pieces of 7.5 kcal, so at most constraints may be missed by that \
+             much; use --steps 600 or more for pieces of at most 5 kcal"
+        );
+    }
+
+    #[test]
+    fn test_cook_in_batches() {
+        let target: Target =
+            toml::from_str("kcal = 1000\ncarb = 40\nfat = 30\nprotein = 30").unwrap();
+        let mut raw_ingredients = RawIngredients(BTreeMap::new());
+        raw_ingredients.0.insert(
+            "chili".to_string(),
+            Ingredient {
+                name: "chili".to_string(),
+                g: 100,
+                kcal: 100.0,
+                batch_g: Some(2800),
+                ..Default::default()
+            },
+        );
+        // half a batch is 1400 kcal, 200 a day over a week
+        let mut constraints = TargetConstraints::new();
+        constraints.cook_in_batches(&target, &raw_ingredients, 1000, 7);
+        assert_eq!(constraints.portion.0["chili"], 200);
+    }
+
+    #[test]
+    fn test_to_grams() {
+        let target: Target =
+            toml::from_str("kcal = 1000\ncarb = 40\nfat = 30\nprotein = 30").unwrap();
+        let mut raw_ingredients = RawIngredients(BTreeMap::new());
+        for (name, portion_g) in &[("oats", None), ("egg", Some(60))] {
+            raw_ingredients.0.insert(
+                name.to_string(),
+                Ingredient {
+                    name: name.to_string(),
+                    g: 100,
+                    kcal: 150.0,
+                    portion_g: *portion_g,
+                    ..Default::default()
+                },
+            );
+        }
+        let mut proposal = Proposal(BTreeMap::new());
+        proposal.0.insert("oats".to_string(), 1);
+        proposal.0.insert("egg".to_string(), 1);
+
+        let gram_proposal = to_grams(&proposal, &target, &raw_ingredients, 2);
+        // 500 kcal are 333g, or 5.6 eggs
+        assert_eq!(gram_proposal.0["oats"], 333);
+        assert_eq!(gram_proposal.0["egg"], 360);
+        // in a kcal band, 1 of 4 pieces of 250 kcal is less than the target's kcal
+        let mut target = target;
+        target.kcal_tolerance_pct = Some(50.0);
+        let gram_proposal = to_grams(&proposal, &target, &raw_ingredients, 4);
+        assert_eq!(gram_proposal.0["oats"], 167);
+    }
+
+    #[test]
+    fn test_max_single_ingredient_kcal_share() {
+        // apple target, but no ingredient may have more than half of the kcal
+        let target: Target = toml::from_str(
+            "kcal = 1000\ncarb = 20\nfat = 30\nprotein = 50\nmax_single_ingredient_kcal_share = 0.5",
+        )
+        .unwrap();
+        let mut raw_ingredients = RawIngredients(BTreeMap::new());
+        for name in &["apple", "banana"] {
+            raw_ingredients.0.insert(
+                name.to_string(),
+                Ingredient {
+                    name: name.to_string(),
+                    g: 100,
+                    kcal: 100.0,
+                    ..Default::default()
+                },
+            );
+        }
+        let constraints = TargetConstraints::from_target(&target, &raw_ingredients, 10).unwrap();
+        let proposal = optimize(&target.normalize(), constraints, &test_ingredients(), 10).unwrap();
+        assert_eq!(proposal.0["apple"], 5);
+        assert_eq!(proposal.0["banana"], 5);
+
+        // the same for apple alone
+        let target: Target = toml::from_str(
+            "kcal = 1000\ncarb = 20\nfat = 30\nprotein = 50\n\
+             [[constraint_max_kcal_pct]]\nname = \"apple\"\npct = 30",
+        )
+        .unwrap();
+        let constraints = TargetConstraints::from_target(&target, &raw_ingredients, 10).unwrap();
+        assert_eq!(
+            constraints.at_most.0,
+            BTreeMap::from([("apple".to_string(), 3)])
+        );
+        let proposal = optimize(&target.normalize(), constraints, &test_ingredients(), 10).unwrap();
+        assert_eq!(proposal.0["apple"], 3);
+    }
+
+    #[test]
+    fn test_diversity_weight() {
+        let mut t = NormalizedTarget {
+            carb: 0.2,
+            fat: 0.3,
+            protein: 0.5,
+            ..Default::default()
+        };
+        // two ingredients that both match the ratio
+        let mut ingredients = test_ingredients();
+        let apple = ingredients.0["apple"].clone();
+        ingredients.0.insert("apricot".to_string(), apple);
+        let greedy = optimize(&t, TargetConstraints::new(), &ingredients, 100).unwrap();
+        assert_eq!(greedy.0.values().filter(|pieces| **pieces > 0).count(), 1);
+        // large weights trade the ratio for the other ingredient, too
+        t.diversity_weight = 0.0001;
+        let diverse = optimize(&t, TargetConstraints::new(), &ingredients, 100).unwrap();
+        assert_eq!((diverse.0["apple"], diverse.0["apricot"]), (50, 50));
+        assert!((t.evaluate(&diverse, &ingredients) - 0.00005).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_expiry() {
+        let t = NormalizedTarget {
+            carb: 0.2,
+            fat: 0.3,
+            protein: 0.5,
+            ..Default::default()
+        };
+        let mut ingredients = test_ingredients();
+        let apple = ingredients.0["apple"].clone();
+        ingredients.0.insert("apricot".to_string(), apple);
+        // of two equal ingredients the one that expires is used instead of the first one
+        let mut constraints = TargetConstraints::new();
+        constraints.expiry = Some(Expiry {
+            urgency: vec![("apricot".to_string(), 0.5)].into_iter().collect(),
+            weight: 0.001,
+        });
+        let proposal = optimize(&t, constraints.clone(), &ingredients, 100).unwrap();
+        assert_eq!((proposal.0["apple"], proposal.0["apricot"]), (0, 100));
+        assert!((constraints.evaluate(&proposal) - 0.0005).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_optimize_plateau() {
+        let t = NormalizedTarget {
+            carb: 0.23,
+            fat: 0.315,
+            protein: 0.45,
+            ..Default::default()
+        };
+        let ingredients = test_ingredients();
+        let coarse = optimize(&t, TargetConstraints::new(), &ingredients, 1000).unwrap();
+        // large steps on the plateau don't make the result worse
+        let fine = optimize(&t, TargetConstraints::new(), &ingredients, 100000).unwrap();
+        assert_eq!(fine.kcal(), 100000);
+        assert!(t.evaluate(&fine, &ingredients) <= t.evaluate(&coarse, &ingredients));
+    }
+
+    #[test]
+    fn test_optimize_restarts() {
+        let t = NormalizedTarget {
+            carb: 0.23,
+            fat: 0.315,
+            protein: 0.45,
+            ..Default::default()
+        };
+        let ingredients = test_ingredients();
+        let restarts = |seed| {
+            let constraints = TargetConstraints::new();
+            let restarts = Restarts { count: 10, seed };
+            optimize_restarts(&t, constraints, &ingredients, 100, restarts, None, None).unwrap()
+        };
+        let proposal = restarts(7);
+        assert_eq!(proposal, restarts(7));
+        let single = optimize(&t, TargetConstraints::new(), &ingredients, 100).unwrap();
+        assert!(t.evaluate(&proposal, &ingredients) <= t.evaluate(&single, &ingredients));
+    }
+
+    #[test]
+    fn test_optimize_parallel() {
+        let t = NormalizedTarget {
+            carb: 0.23,
+            fat: 0.315,
+            protein: 0.45,
+            ..Default::default()
+        };
+        let ingredients = test_ingredients();
+        // enough copies of the ingredients to evaluate them in parallel
+        let mut many = Ingredients(BTreeMap::new());
+        for i in 0..MIN_PARALLEL_CANDIDATES {
+            for (name, ingredient) in &ingredients.0 {
+                many.0.insert(format!("{}{}", name, i), ingredient.clone());
+            }
+        }
+        let proposal = optimize(&t, TargetConstraints::new(), &many, 1000).unwrap();
+        assert_eq!(proposal.kcal(), 1000);
+        let expected = optimize(&t, TargetConstraints::new(), &ingredients, 1000).unwrap();
+        assert_eq!(
+            t.evaluate(&proposal, &many),
+            t.evaluate(&expected, &ingredients)
+        );
+    }
+
+    #[test]
+    fn test_optimize() {
+        // apple target
+        let t = NormalizedTarget {
+            carb: 0.20,
+            fat: 0.30,
+            protein: 0.50,
+            ..Default::default()
+        };
+        let ingredients = test_ingredients();
+        let proposal = optimize(&t, TargetConstraints::new(), &ingredients, 2).unwrap();
+
+        let mut expected_proposal = Proposal(BTreeMap::new());
+        expected_proposal.0.insert("apple".to_string(), 2);
+        expected_proposal.0.insert("banana".to_string(), 0);
+        assert_eq!(proposal, expected_proposal);
+
+        // banana target
+        let t = NormalizedTarget {
+            carb: 0.26,
+            fat: 0.33,
+            protein: 0.4,
+            ..Default::default()
+        };
+        let ingredients = test_ingredients();
+        let proposal = optimize(&t, TargetConstraints::new(), &ingredients, 2).unwrap();
+        let mut expected_proposal = Proposal(BTreeMap::new());
+        expected_proposal.0.insert("apple".to_string(), 0);
+        expected_proposal.0.insert("banana".to_string(), 2);
+        assert_eq!(proposal, expected_proposal);
+
+        let t = NormalizedTarget {
+            carb: 0.23,
+            fat: 0.315,
+            protein: 0.45,
+            ..Default::default()
+        };
+        let ingredients = test_ingredients();
+        let proposal = optimize(&t, TargetConstraints::new(), &ingredients, 2).unwrap();
+        let mut expected_proposal = Proposal(BTreeMap::new());
+        expected_proposal.0.insert("apple".to_string(), 1);
+        expected_proposal.0.insert("banana".to_string(), 1);
+        assert_eq!(proposal, expected_proposal);
+    }
+
+    #[test]
+    fn test_optimize_step_band() {
+        // apple target with a banana, which more apples than the target's kcal dilute
+        let t = NormalizedTarget {
+            carb: 0.20,
+            fat: 0.30,
+            protein: 0.50,
+            ..Default::default()
+        };
+        let ingredients = test_ingredients();
+        let mut constraints = TargetConstraints::new();
+        constraints.exact.0.insert("banana".to_string(), 1);
+        let proposal = optimize(&t, constraints.clone(), &ingredients, 2).unwrap();
+        assert_eq!(proposal.0["apple"], 1);
+        constraints.step_band = Some((1, 4));
+        let proposal = optimize(&t, constraints, &ingredients, 2).unwrap();
+        assert_eq!(proposal.0["apple"], 3);
+        // without a better fit, the result stays at the target's kcal
+        let mut constraints = TargetConstraints::new();
+        constraints.step_band = Some((1, 4));
+        let proposal = optimize(&t, constraints, &ingredients, 2).unwrap();
+        assert_eq!(proposal.kcal(), 2);
+    }
+
+    #[test]
+    fn test_optimize_max_weight_share() {
+        // banana target, but banana may only be half of the weight
+        let t = NormalizedTarget {
+            carb: 0.26,
+            fat: 0.33,
+            protein: 0.4,
+            ..Default::default()
+        };
+        let mut constraints = TargetConstraints::new();
+        constraints
+            .max_weight_share
+            .insert("banana".to_string(), 0.5);
+        let proposal = optimize(&t, constraints, &test_ingredients(), 6).unwrap();
+        let mut expected_proposal = Proposal(BTreeMap::new());
+        expected_proposal.0.insert("apple".to_string(), 4);
+        expected_proposal.0.insert("banana".to_string(), 2);
+        assert_eq!(proposal, expected_proposal);
+    }
+
+    #[test]
+    fn test_optimize_portion() {
+        let t = NormalizedTarget {
+            carb: 0.23,
+            fat: 0.315,
+            protein: 0.45,
+            ..Default::default()
+        };
+        // banana portions are too large to fill the last piece
+        let mut constraints = TargetConstraints::new();
+        constraints.portion.0.insert("banana".to_string(), 2);
+        constraints.at_most.0.insert("apple".to_string(), 1);
+        let proposal = optimize(&t, constraints, &test_ingredients(), 4).unwrap();
+        let mut expected_proposal = Proposal(BTreeMap::new());
+        expected_proposal.0.insert("apple".to_string(), 1);
+        expected_proposal.0.insert("banana".to_string(), 2);
+        assert_eq!(proposal, expected_proposal);
+    }
+
+    #[test]
+    fn test_optimize_at_least_if_used() {
+        // one apple and one banana without the constraint
+        let t = NormalizedTarget {
+            carb: 0.23,
+            fat: 0.315,
+            protein: 0.45,
+            ..Default::default()
+        };
+        // banana is only worth using in amounts of at least 2 pieces
+        let mut constraints = TargetConstraints::new();
+        constraints
+            .at_least_if_used
+            .0
+            .insert("banana".to_string(), 2);
+        let proposal = optimize(&t, constraints, &test_ingredients(), 2).unwrap();
+        let mut expected_proposal = Proposal(BTreeMap::new());
+        expected_proposal.0.insert("apple".to_string(), 2);
+        expected_proposal.0.insert("banana".to_string(), 0);
+        assert_eq!(proposal, expected_proposal);
+    }
+
+    #[test]
+    fn test_proposal_order() {
+        let mut proposal = Proposal(BTreeMap::new());
+        proposal.0.insert("banana".to_string(), 0);
+        proposal.0.insert("apple".to_string(), 2);
+        // printed in the same order in every run
+        assert_eq!(
+            format!("{:?}", proposal),
+            "Proposal({\"apple\": 2, \"banana\": 0})"
+        );
+    }
+}
@@ -0,0 +1,221 @@
+//! Shareable meal files.
+//!
+//! A meal is a fixed block of ingredients in grams together with its macros, so it can be
+//! imported by someone who doesn't have the same ingredient files.
+use crate::error::Error;
+use crate::{round_decigram, Macros, Proposal, RawIngredients, Target};
+use crate::{KCAL_PER_G_CARB, KCAL_PER_G_FAT, KCAL_PER_G_PROTEIN};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Meal {
+    pub name: String,
+    pub kcal: f64,
+    // in g
+    pub carb: f64,
+    // in g
+    pub fat: f64,
+    // in g
+    pub protein: f64,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    pub ingredients: Vec<MealIngredient>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MealIngredient {
+    pub name: String,
+    pub g: u64,
+}
+
+/// Food that is already eaten or planned, e.g. a restaurant meal, of which only the macros are
+/// known
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FixedFood {
+    pub name: String,
+    pub kcal: f64,
+    // in g
+    #[serde(default)]
+    pub carb: f64,
+    // in g
+    #[serde(default)]
+    pub fat: f64,
+    // in g
+    #[serde(default)]
+    pub protein: f64,
+}
+
+impl FixedFood {
+    /// The food as a meal without ingredients
+    pub fn meal(&self) -> Meal {
+        Meal {
+            name: self.name.clone(),
+            kcal: self.kcal,
+            carb: self.carb,
+            fat: self.fat,
+            protein: self.protein,
+            tags: Vec::new(),
+            notes: None,
+            ingredients: Vec::new(),
+        }
+    }
+}
+
+impl Meal {
+    /// Turns a result in grams into a meal, leaving out ingredients that aren't used
+    pub fn from_grams(
+        name: &str,
+        gram_proposal: &Proposal,
+        raw_ingredients: &RawIngredients,
+    ) -> Meal {
+        let macros = Macros::from_grams(gram_proposal, raw_ingredients);
+        let mut ingredients: Vec<MealIngredient> = gram_proposal
+            .0
+            .iter()
+            .filter(|(_, g)| **g > 0)
+            .map(|(name, g)| MealIngredient {
+                name: name.to_string(),
+                g: *g,
+            })
+            .collect();
+        ingredients.sort_by(|a, b| a.name.cmp(&b.name));
+        Meal {
+            name: name.to_string(),
+            kcal: macros.kcal.round(),
+            carb: round_decigram(macros.carb),
+            fat: round_decigram(macros.fat),
+            protein: round_decigram(macros.protein),
+            tags: Vec::new(),
+            notes: None,
+            ingredients,
+        }
+    }
+
+    /// The meal's ingredients as a result in grams
+    pub fn grams(&self) -> Proposal {
+        let mut gram_proposal = Proposal(Default::default());
+        for ingredient in &self.ingredients {
+            *gram_proposal.0.entry(ingredient.name.clone()).or_insert(0) += ingredient.g;
+        }
+        gram_proposal
+    }
+
+    pub fn macros(&self) -> Macros {
+        Macros {
+            kcal: self.kcal,
+            carb: self.carb,
+            fat: self.fat,
+            protein: self.protein,
+            ..Default::default()
+        }
+    }
+}
+
+/// Returns the target for the rest of the day after the meals and the target's fixed foods are
+/// eaten.
+///
+/// The target's macro grams are derived from its kcal and ratio, the meals' grams are
+/// subtracted and the remainder is turned back into a ratio.
+pub fn reconcile(target: &Target, meals: &[Meal]) -> Result<Target, Error> {
+    let fixed: Vec<Meal> = target.fixed.iter().flatten().map(FixedFood::meal).collect();
+    if meals.is_empty() && fixed.is_empty() {
+        return Ok(target.clone());
+    }
+    let mut eaten = Macros::default();
+    for meal in meals.iter().chain(&fixed) {
+        eaten.add(&meal.macros());
+    }
+    if eaten.kcal >= target.kcal as f64 {
+        return Err(Error::MealsExceedTarget {
+            meal_kcal: eaten.kcal,
+            target_kcal: target.kcal,
+        });
+    }
+
+    let normalized = target.normalize();
+    let kcal_per_gram = KCAL_PER_G_CARB * normalized.carb
+        + KCAL_PER_G_FAT * normalized.fat
+        + KCAL_PER_G_PROTEIN * normalized.protein;
+    let grams = target.kcal as f64 / kcal_per_gram;
+    let carb = (grams * normalized.carb - eaten.carb).max(0.0);
+    let fat = (grams * normalized.fat - eaten.fat).max(0.0);
+    let protein = (grams * normalized.protein - eaten.protein).max(0.0);
+    let sum = carb + fat + protein;
+    // kcal can be left without any macros, e.g. of meals that declare fewer kcal than their
+    // macros have
+    if sum == 0.0 {
+        return Err(Error::MealsExceedMacros {
+            meal_kcal: eaten.kcal,
+            target_kcal: target.kcal,
+        });
+    }
+
+    let mut reconciled = target.clone();
+    reconciled.kcal = (target.kcal as f64 - eaten.kcal).round() as u64;
+    // a kcal band is for the whole day too
+    let rest = |kcal: u64| (kcal as f64 - eaten.kcal).max(0.0).round() as u64;
+    reconciled.kcal_min = target.kcal_min.map(rest);
+    reconciled.kcal_max = target.kcal_max.map(rest);
+    reconciled.carb = (100.0 * carb / sum).round() as u64;
+    reconciled.fat = (100.0 * fat / sum).round() as u64;
+    reconciled.protein = (100.0 * protein / sum).round() as u64;
+    Ok(reconciled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reconcile() {
+        // 2100 kcal at 50:25:25 is 200g carb, 100g fat, 100g protein
+        let target: Target =
+            toml::from_str("kcal = 2100\ncarb = 50\nfat = 25\nprotein = 25").unwrap();
+        let meal = Meal {
+            name: "shake".to_string(),
+            kcal: 500.0,
+            carb: 50.0,
+            fat: 0.0,
+            protein: 75.0,
+            tags: Vec::new(),
+            notes: None,
+            ingredients: Vec::new(),
+        };
+        let reconciled = reconcile(&target, &[meal]).unwrap();
+        assert_eq!(reconciled.kcal, 1600);
+        // 150g carb, 100g fat, 25g protein remain
+        assert_eq!(reconciled.carb, 55);
+        assert_eq!(reconciled.fat, 36);
+        assert_eq!(reconciled.protein, 9);
+
+        // the same as a fixed food of the target
+        let mut target = target;
+        target.fixed = Some(vec![FixedFood {
+            name: "shake".to_string(),
+            kcal: 500.0,
+            carb: 50.0,
+            fat: 0.0,
+            protein: 75.0,
+        }]);
+        let reconciled = reconcile(&target, &[]).unwrap();
+        assert_eq!(
+            (reconciled.kcal, reconciled.carb, reconciled.fat),
+            (1600, 55, 36)
+        );
+
+        // all of the macros but fewer declared kcal leave kcal without a ratio
+        target.fixed = Some(vec![FixedFood {
+            name: "buffet".to_string(),
+            kcal: 2000.0,
+            carb: 200.0,
+            fat: 100.0,
+            protein: 100.0,
+        }]);
+        assert!(matches!(
+            reconcile(&target, &[]),
+            Err(Error::MealsExceedMacros { .. })
+        ));
+    }
+}
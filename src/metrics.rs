@@ -0,0 +1,329 @@
+//! Derived metrics that the target defines as arithmetic over nutrients, e.g. a P:E ratio.
+use crate::Macros;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fmt;
+
+/// Names that expressions can refer to
+pub const NUTRIENTS: &[&str] = &[
+    "kcal",
+    "carb",
+    "net_carbs",
+    "fat",
+    "protein",
+    "leucine_g",
+    "eaa_g",
+    "omega3_g",
+    "omega6_g",
+    "alcohol",
+    "polyols",
+    "sat_fat",
+    "sugar",
+    "sodium_mg",
+    "water_ml",
+    "caffeine_mg",
+    "glycemic_load",
+];
+
+fn nutrient(macros: &Macros, name: &str) -> f64 {
+    match name {
+        "kcal" => macros.kcal,
+        "carb" => macros.carb,
+        "net_carbs" => macros.carb - macros.polyols,
+        "fat" => macros.fat,
+        "protein" => macros.protein,
+        "leucine_g" => macros.leucine_g,
+        "eaa_g" => macros.eaa_g,
+        "omega3_g" => macros.omega3_g,
+        "omega6_g" => macros.omega6_g,
+        "alcohol" => macros.alcohol,
+        "polyols" => macros.polyols,
+        "sat_fat" => macros.sat_fat,
+        "sugar" => macros.sugar,
+        "sodium_mg" => macros.sodium_mg,
+        "water_ml" => macros.water_ml,
+        "caffeine_mg" => macros.caffeine_mg,
+        "glycemic_load" => macros.glycemic_load,
+        _ => unreachable!("unknown nutrients are rejected by the parser"),
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Metric {
+    pub name: String,
+    // e.g. "protein / (fat + net_carbs)"
+    pub expr: Expr,
+}
+
+impl Metric {
+    pub fn value(&self, macros: &Macros) -> f64 {
+        self.expr.eval(macros)
+    }
+}
+
+/// e.g. "P:E ratio 0.52, sugar share 0.08"
+///
+/// Metrics whose value is undefined, e.g. because of a division by zero, are shown as n/a.
+pub fn summary(metrics: &[Metric], macros: &Macros) -> String {
+    join(&values(metrics, macros))
+}
+
+/// The same as `summary` for values that are already computed
+pub fn join(values: &[MetricValue]) -> String {
+    let values: Vec<String> = values
+        .iter()
+        .map(|metric| match metric.value {
+            value if value.is_finite() => format!("{} {:.2}", metric.name, value),
+            _ => format!("{} n/a", metric.name),
+        })
+        .collect();
+    values.join(", ")
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct MetricValue {
+    pub name: String,
+    pub value: f64,
+}
+
+pub fn values(metrics: &[Metric], macros: &Macros) -> Vec<MetricValue> {
+    metrics
+        .iter()
+        .map(|metric| MetricValue {
+            name: metric.name.clone(),
+            value: metric.value(macros),
+        })
+        .collect()
+}
+
+/// A parsed arithmetic expression that keeps its source for serialization
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(try_from = "String", into = "String")]
+pub struct Expr {
+    source: String,
+    node: Node,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Number(f64),
+    Nutrient(String),
+    Neg(Box<Node>),
+    Binary(char, Box<Node>, Box<Node>),
+}
+
+impl Expr {
+    fn eval(&self, macros: &Macros) -> f64 {
+        self.node.eval(macros)
+    }
+}
+
+impl Node {
+    fn eval(&self, macros: &Macros) -> f64 {
+        match self {
+            Node::Number(value) => *value,
+            Node::Nutrient(name) => nutrient(macros, name),
+            Node::Neg(node) => -node.eval(macros),
+            Node::Binary(op, left, right) => {
+                let (left, right) = (left.eval(macros), right.eval(macros));
+                match op {
+                    '+' => left + right,
+                    '-' => left - right,
+                    '*' => left * right,
+                    _ => left / right,
+                }
+            }
+        }
+    }
+}
+
+impl TryFrom<String> for Expr {
+    type Error = ParseError;
+
+    fn try_from(source: String) -> Result<Self, Self::Error> {
+        let mut parser = Parser {
+            tokens: tokenize(&source)?,
+            pos: 0,
+        };
+        let node = parser.expr()?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            return Err(ParseError(format!("unexpected {}", token)));
+        }
+        Ok(Expr { source, node })
+    }
+}
+
+impl From<Expr> for String {
+    fn from(expr: Expr) -> String {
+        expr.source
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseError(String);
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid expression: {}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Name(String),
+    Op(char),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Number(value) => write!(f, "number {}", value),
+            Token::Name(name) => write!(f, "name {}", name),
+            Token::Op(op) => write!(f, "'{}'", op),
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if "+-*/()".contains(c) {
+            tokens.push(Token::Op(c));
+            chars.next();
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut number = String::new();
+            while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit() || **c == '.') {
+                number.push(c);
+                chars.next();
+            }
+            let value = number
+                .parse()
+                .map_err(|_| ParseError(format!("invalid number {}", number)))?;
+            tokens.push(Token::Number(value));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let mut name = String::new();
+            while let Some(&c) = chars
+                .peek()
+                .filter(|c| c.is_ascii_alphanumeric() || **c == '_')
+            {
+                name.push(c);
+                chars.next();
+            }
+            if !NUTRIENTS.contains(&name.as_str()) {
+                return Err(ParseError(format!(
+                    "unknown nutrient {}, expected one of {}",
+                    name,
+                    NUTRIENTS.join(", ")
+                )));
+            }
+            tokens.push(Token::Name(name));
+        } else {
+            return Err(ParseError(format!("unexpected character '{}'", c)));
+        }
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent over `expr = term (("+" | "-") term)*` and
+/// `term = factor (("*" | "/") factor)*`
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next_op(&mut self, ops: &str) -> Option<char> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Op(op)) if ops.contains(*op) => {
+                self.pos += 1;
+                Some(*op)
+            }
+            _ => None,
+        }
+    }
+
+    fn expr(&mut self) -> Result<Node, ParseError> {
+        let mut node = self.term()?;
+        while let Some(op) = self.next_op("+-") {
+            node = Node::Binary(op, Box::new(node), Box::new(self.term()?));
+        }
+        Ok(node)
+    }
+
+    fn term(&mut self) -> Result<Node, ParseError> {
+        let mut node = self.factor()?;
+        while let Some(op) = self.next_op("*/") {
+            node = Node::Binary(op, Box::new(node), Box::new(self.factor()?));
+        }
+        Ok(node)
+    }
+
+    fn factor(&mut self) -> Result<Node, ParseError> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        match token {
+            Some(Token::Number(value)) => Ok(Node::Number(value)),
+            Some(Token::Name(name)) => Ok(Node::Nutrient(name)),
+            Some(Token::Op('-')) => Ok(Node::Neg(Box::new(self.factor()?))),
+            Some(Token::Op('(')) => {
+                let node = self.expr()?;
+                match self.next_op(")") {
+                    Some(_) => Ok(node),
+                    None => Err(ParseError("missing ')'".to_string())),
+                }
+            }
+            Some(token) => Err(ParseError(format!("unexpected {}", token))),
+            None => Err(ParseError("unexpected end".to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Result<Expr, ParseError> {
+        Expr::try_from(source.to_string())
+    }
+
+    #[test]
+    fn test_eval() {
+        let macros = Macros {
+            kcal: 2000.0,
+            carb: 200.0,
+            fat: 60.0,
+            protein: 160.0,
+            polyols: 20.0,
+            ..Default::default()
+        };
+        let pe_ratio = parse("protein / (fat + net_carbs)").unwrap();
+        assert!((pe_ratio.eval(&macros) - 160.0 / 240.0).abs() < 1e-9);
+        // precedence and unary minus
+        assert_eq!(parse("2 + 3 * -fat / 6").unwrap().eval(&macros), -28.0);
+        assert_eq!(parse("(2 + 3) * 4").unwrap().eval(&macros), 20.0);
+
+        let metrics = vec![
+            Metric {
+                name: "P:E ratio".to_string(),
+                expr: pe_ratio,
+            },
+            Metric {
+                name: "alcohol share".to_string(),
+                expr: parse("alcohol / alcohol").unwrap(),
+            },
+        ];
+        assert_eq!(
+            summary(&metrics, &macros),
+            "P:E ratio 0.67, alcohol share n/a"
+        );
+
+        assert!(parse("protein / fiber").is_err());
+        assert!(parse("(protein + fat").is_err());
+        assert!(parse("protein fat").is_err());
+        assert!(parse("protein *").is_err());
+    }
+}
@@ -0,0 +1,57 @@
+//! Finds the ingredients that a misspelled name most likely refers to.
+
+/// Largest number of names suggested for one that isn't loaded
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Levenshtein distance, the number of characters to insert, delete or replace to turn `a` into
+/// `b`
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let replace = previous[j] + usize::from(a != *b);
+            current.push(replace.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// The candidates that differ from `name` by at most a third of its characters, ignoring case,
+/// closest first
+pub fn closest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+    let name = name.to_lowercase();
+    let max_distance = (name.chars().count() / 3).max(2);
+    let mut close: Vec<(usize, &str)> = candidates
+        .into_iter()
+        .map(|candidate| (distance(&name, &candidate.to_lowercase()), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+    close.sort();
+    close.dedup_by_key(|(_, candidate)| *candidate);
+    close
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_closest() {
+        assert_eq!(distance("oats", "oat"), 1);
+        assert_eq!(distance("kitten", "sitting"), 3);
+        let names = ["chicken breast", "chickpeas", "oats", "olive oil"];
+        assert_eq!(
+            closest("Chicken brest", names.iter().copied()),
+            vec!["chicken breast"]
+        );
+        assert_eq!(closest("oat", names.iter().copied()), vec!["oats"]);
+        assert!(closest("banana", names.iter().copied()).is_empty());
+    }
+}
@@ -0,0 +1,714 @@
+//! Structured results for consumption by scripts and spreadsheets.
+use crate::adjust::Adjustment;
+use crate::batch::Batch;
+use crate::flavoring::Flavoring;
+use crate::meal::Meal;
+use crate::metrics::{self, MetricValue};
+use crate::uncertainty::MacroIntervals;
+use crate::{Macros, Proposal, RawIngredients};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::Write;
+
+#[derive(Serialize, Debug)]
+pub struct Report {
+    pub ingredients: Vec<IngredientAmount>,
+    pub total: IngredientAmount,
+    // the whole mix treated as a single food
+    pub per_100g: IngredientAmount,
+    pub ratio: Ratio,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub intervals: Option<MacroIntervals>,
+    // kcal and macros per group of ingredients, if any ingredient of the mix has a group
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<IngredientAmount>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub imported_meals: Vec<Meal>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub flavorings: Vec<Flavoring>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sodium: Option<Limited>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub glycemic_load: Option<Limited>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub water: Option<Limited>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub caffeine: Option<Limited>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quality_protein: Option<QualityProtein>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub leucine: Option<Limited>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub eaa: Option<Limited>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub omega: Option<OmegaRatio>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prep_minutes: Option<Limited>,
+    // derived metrics of the target
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub metrics: Vec<MetricValue>,
+    // where the optimized target differs from the given one
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub adjustments: Vec<Adjustment>,
+    // batch-cooked ingredients over the days of the plan
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub batches: Vec<Batch>,
+    // nearly as good mixes that differ from this one
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub alternatives: Vec<Report>,
+    #[serde(skip)]
+    rounding: Rounding,
+}
+
+/// Amount of a nutrient in the mix that the target can limit, such as sodium
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Limited {
+    pub amount: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl Limited {
+    /// Only if any ingredient declares the nutrient or the target limits it
+    pub fn of(amount: f64, max: Option<f64>) -> Option<Limited> {
+        Limited::between(amount, None, max)
+    }
+
+    /// Same as `of` for a nutrient that the target can also ask a minimum of, such as water
+    pub fn between(amount: f64, min: Option<f64>, max: Option<f64>) -> Option<Limited> {
+        if amount == 0.0 && min.is_none() && max.is_none() {
+            return None;
+        }
+        Some(Limited {
+            amount: amount.round(),
+            min,
+            max,
+        })
+    }
+
+    /// e.g. "Sodium 1800 mg, 78% of the limit of 2300 mg" or "Water 2400 ml, 80% of the
+    /// minimum of 3000 ml"
+    pub fn summary(&self, name: &str, unit: &str) -> String {
+        let mut summary = format!("{} {}{}", name, self.amount, unit);
+        let bounds = [(self.min, "minimum"), (self.max, "limit")];
+        let bounds: Vec<String> = bounds
+            .iter()
+            .filter_map(|(bound, kind)| {
+                bound.map(|bound| {
+                    format!(
+                        "{}% of the {} of {}{}",
+                        (100.0 * self.amount / bound).round(),
+                        kind,
+                        bound,
+                        unit
+                    )
+                })
+            })
+            .collect();
+        if !bounds.is_empty() {
+            summary = format!("{}, {}", summary, bounds.join(" and "));
+        }
+        summary
+    }
+}
+
+/// Protein of the mix from ingredients with a high protein score
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct QualityProtein {
+    pub g: f64,
+    // of the protein
+    pub pct: f64,
+    pub min_pct: Option<f64>,
+}
+
+impl QualityProtein {
+    /// Only if any ingredient of the mix has a high protein score or the target asks for it
+    pub fn of(macros: &Macros, min_pct: Option<f64>) -> Option<QualityProtein> {
+        if macros.quality_protein == 0.0 && min_pct.is_none() {
+            return None;
+        }
+        let pct = if macros.protein > 0.0 {
+            100.0 * macros.quality_protein / macros.protein
+        } else {
+            0.0
+        };
+        Some(QualityProtein {
+            g: macros.quality_protein.round(),
+            pct: pct.round(),
+            min_pct,
+        })
+    }
+
+    /// e.g. "High-quality protein 120g, 80% of the protein, at least 60% wanted"
+    pub fn summary(&self) -> String {
+        let summary = format!(
+            "High-quality protein {}g, {}% of the protein",
+            self.g, self.pct
+        );
+        match self.min_pct {
+            Some(min_pct) => format!("{}, at least {}% wanted", summary, min_pct),
+            None => summary,
+        }
+    }
+}
+
+/// Omega-6 and omega-3 fats of the mix
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct OmegaRatio {
+    pub omega3_g: f64,
+    pub omega6_g: f64,
+    // g of omega-6 per g of omega-3, none without omega-3
+    pub ratio: Option<f64>,
+    pub max_ratio: Option<f64>,
+}
+
+impl OmegaRatio {
+    /// Only if any ingredient of the mix has omega fats or the target limits their ratio
+    pub fn of(macros: &Macros, max_ratio: Option<f64>) -> Option<OmegaRatio> {
+        if macros.omega3_g == 0.0 && macros.omega6_g == 0.0 && max_ratio.is_none() {
+            return None;
+        }
+        let ratio = Some(macros.omega6_g / macros.omega3_g)
+            .filter(|_| macros.omega3_g > 0.0)
+            .map(|ratio| (ratio * 10.0).round() / 10.0);
+        Some(OmegaRatio {
+            omega3_g: (macros.omega3_g * 10.0).round() / 10.0,
+            omega6_g: (macros.omega6_g * 10.0).round() / 10.0,
+            ratio,
+            max_ratio,
+        })
+    }
+
+    /// e.g. "Omega-6 12g and omega-3 3g, a ratio of 4:1, at most 5:1 wanted"
+    pub fn summary(&self) -> String {
+        let ratio = match self.ratio {
+            Some(ratio) => format!("a ratio of {}:1", ratio),
+            None => "no omega-3".to_string(),
+        };
+        let summary = format!(
+            "Omega-6 {}g and omega-3 {}g, {}",
+            self.omega6_g, self.omega3_g, ratio
+        );
+        match self.max_ratio {
+            Some(max_ratio) => format!("{}, at most {}:1 wanted", summary, max_ratio),
+            None => summary,
+        }
+    }
+}
+
+/// How amounts in the result are rounded, the same for every output format
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rounding {
+    // grams of ingredients without a portion size are rounded to multiples of this
+    pub g: u64,
+    pub macro_decimals: usize,
+    pub pct_decimals: usize,
+}
+
+impl Default for Rounding {
+    fn default() -> Rounding {
+        Rounding {
+            g: 1,
+            macro_decimals: 1,
+            pct_decimals: 1,
+        }
+    }
+}
+
+fn round_to(x: f64, decimals: usize) -> f64 {
+    let factor = 10f64.powi(decimals as i32);
+    (x * factor).round() / factor
+}
+
+impl Rounding {
+    pub fn macro_g(&self, g: f64) -> f64 {
+        round_to(g, self.macro_decimals)
+    }
+
+    pub fn pct(&self, pct: f64) -> f64 {
+        round_to(pct, self.pct_decimals)
+    }
+
+    /// Rounds the grams of the ingredients, except for those that come in portions
+    pub fn grams(&self, gram_proposal: &Proposal, raw_ingredients: &RawIngredients) -> Proposal {
+        let mut rounded = gram_proposal.clone();
+        for (name, g) in rounded.0.iter_mut() {
+            if self.g > 1 && raw_ingredients.0[name].portion_g.is_none() {
+                *g = (*g as f64 / self.g as f64).round() as u64 * self.g;
+            }
+        }
+        rounded
+    }
+}
+
+/// Amount of a single ingredient, or of the whole mix
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct IngredientAmount {
+    pub name: String,
+    pub g: u64,
+    pub kcal: f64,
+    pub carb: f64,
+    pub fat: f64,
+    pub protein: f64,
+    pub alcohol: f64,
+    // part of carb
+    pub polyols: f64,
+}
+
+impl IngredientAmount {
+    fn new(name: &str, g: u64, macros: &Macros, rounding: &Rounding) -> IngredientAmount {
+        IngredientAmount {
+            name: name.to_string(),
+            g,
+            kcal: macros.kcal.round(),
+            carb: rounding.macro_g(macros.carb),
+            fat: rounding.macro_g(macros.fat),
+            protein: rounding.macro_g(macros.protein),
+            alcohol: rounding.macro_g(macros.alcohol),
+            polyols: rounding.macro_g(macros.polyols),
+        }
+    }
+}
+
+/// Share of carb, fat and protein of the macro grams (including alcohol) in percent
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Ratio {
+    pub carb: f64,
+    pub fat: f64,
+    pub protein: f64,
+}
+
+impl Ratio {
+    fn of(macros: &Macros, rounding: &Rounding) -> Ratio {
+        let sum = macros.carb + macros.fat + macros.protein + macros.alcohol;
+        let percent = |g: f64| {
+            if sum > 0.0 {
+                rounding.pct(100.0 * g / sum)
+            } else {
+                0.0
+            }
+        };
+        Ratio {
+            carb: percent(macros.carb),
+            fat: percent(macros.fat),
+            protein: percent(macros.protein),
+        }
+    }
+}
+
+/// Amounts per group of the ingredients, sorted by group with ungrouped ingredients last.
+///
+/// Empty if no ingredient of the mix has a group.
+fn groups(
+    gram_proposal: &Proposal,
+    raw_ingredients: &RawIngredients,
+    rounding: &Rounding,
+) -> Vec<IngredientAmount> {
+    let used = gram_proposal.0.iter().filter(|(_, g)| **g > 0);
+    let mut groups: BTreeMap<Option<&str>, Proposal> = BTreeMap::new();
+    for (name, g) in used {
+        let group = raw_ingredients.0[name].group.as_deref();
+        let proposal = groups
+            .entry(group)
+            .or_insert_with(|| Proposal(Default::default()));
+        proposal.0.insert(name.to_string(), *g);
+    }
+    if groups.keys().all(Option::is_none) {
+        return Vec::new();
+    }
+    // None sorts first
+    let mut groups: Vec<(Option<&str>, Proposal)> = groups.into_iter().collect();
+    let ungrouped = usize::from(groups[0].0.is_none());
+    groups.rotate_left(ungrouped);
+    groups
+        .iter()
+        .map(|(group, proposal)| {
+            let macros = Macros::from_grams(proposal, raw_ingredients);
+            let g = proposal.0.values().sum();
+            IngredientAmount::new(group.unwrap_or("other"), g, &macros, rounding)
+        })
+        .collect()
+}
+
+impl Report {
+    /// Writes a sentence for each nutrient of the report and one for its metrics
+    pub fn write_nutrients<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
+        let limited = |limited: &Option<Limited>, name: &str, unit: &str| {
+            limited.as_ref().map(|limited| limited.summary(name, unit))
+        };
+        let metrics = Some(format!("Metrics {}", metrics::join(&self.metrics)))
+            .filter(|_| !self.metrics.is_empty());
+        let lines = [
+            limited(&self.sodium, "Sodium", " mg"),
+            limited(&self.glycemic_load, "Glycemic load", ""),
+            limited(&self.water, "Water", " ml"),
+            limited(&self.caffeine, "Caffeine", " mg"),
+            self.quality_protein.as_ref().map(QualityProtein::summary),
+            limited(&self.leucine, "Leucine", "g"),
+            limited(&self.eaa, "Essential amino acids", "g"),
+            self.omega.as_ref().map(OmegaRatio::summary),
+            limited(&self.prep_minutes, "Prep time", " min"),
+            metrics,
+        ];
+        for line in lines.iter().flatten() {
+            writeln!(writer, "{}.", line)?;
+        }
+        Ok(())
+    }
+
+    pub fn new(
+        gram_proposal: &Proposal,
+        raw_ingredients: &RawIngredients,
+        rounding: Rounding,
+    ) -> Report {
+        let gram_proposal = &rounding.grams(gram_proposal, raw_ingredients);
+        let ingredients = gram_proposal
+            .0
+            .iter()
+            .map(|(name, &g)| {
+                let mut single = Proposal(Default::default());
+                single.0.insert(name.to_string(), g);
+                let macros = Macros::from_grams(&single, raw_ingredients);
+                IngredientAmount::new(name, g, &macros, &rounding)
+            })
+            .collect();
+        let total = Macros::from_grams(gram_proposal, raw_ingredients);
+        let total_g = gram_proposal.0.values().sum();
+        let per_100g = total.per_g(100.0, total_g as f64);
+        Report {
+            ingredients,
+            groups: groups(gram_proposal, raw_ingredients, &rounding),
+            total: IngredientAmount::new("total", total_g, &total, &rounding),
+            per_100g: IngredientAmount::new("per 100g", 100, &per_100g, &rounding),
+            ratio: Ratio::of(&total, &rounding),
+            intervals: None,
+            imported_meals: Vec::new(),
+            flavorings: Vec::new(),
+            sodium: None,
+            glycemic_load: None,
+            water: None,
+            caffeine: None,
+            quality_protein: None,
+            leucine: None,
+            eaa: None,
+            omega: None,
+            prep_minutes: None,
+            metrics: Vec::new(),
+            adjustments: Vec::new(),
+            batches: Vec::new(),
+            alternatives: Vec::new(),
+            rounding,
+        }
+    }
+
+    /// Writes a table with one row per ingredient and the total, followed by the ratio and
+    /// its deviation from the `target` ratio in percentage points
+    /// The vs target row is only written if there is a target
+    pub fn write_table<W: Write>(
+        &self,
+        mut writer: W,
+        target: Option<&Ratio>,
+    ) -> std::io::Result<()> {
+        let pct_decimals = self.rounding.pct_decimals;
+        let width = self.write_amounts(&mut writer, "ingredient", &self.ingredients)?;
+        writeln!(
+            writer,
+            "{:<width$} {:>6} {:>6} {:>6.pct_decimals$} {:>6.pct_decimals$} {:>7.pct_decimals$}",
+            "ratio %",
+            "",
+            "",
+            self.ratio.carb,
+            self.ratio.fat,
+            self.ratio.protein,
+            width = width,
+            pct_decimals = pct_decimals
+        )?;
+        let target = match target {
+            Some(target) => target,
+            None => return Ok(()),
+        };
+        writeln!(
+            writer,
+            "{:<width$} {:>6} {:>6} {:>+6.pct_decimals$} {:>+6.pct_decimals$} {:>+7.pct_decimals$}",
+            "vs target",
+            "",
+            "",
+            self.rounding.pct(self.ratio.carb - target.carb),
+            self.rounding.pct(self.ratio.fat - target.fat),
+            self.rounding.pct(self.ratio.protein - target.protein),
+            width = width,
+            pct_decimals = pct_decimals
+        )
+    }
+
+    /// Writes a table with one row per group of ingredients and the total
+    pub fn write_groups<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
+        self.write_amounts(&mut writer, "group", &self.groups)?;
+        Ok(())
+    }
+
+    /// Writes the header, a row per amount that isn't 0g and the total, and returns the width
+    /// of the first column
+    fn write_amounts<W: Write>(
+        &self,
+        writer: &mut W,
+        first_column: &str,
+        amounts: &[IngredientAmount],
+    ) -> std::io::Result<usize> {
+        let decimals = self.rounding.macro_decimals;
+        let pct_decimals = self.rounding.pct_decimals;
+        // unused ingredients of a large pantry would hide the plan
+        let amounts: Vec<&IngredientAmount> =
+            amounts.iter().filter(|amount| amount.g > 0).collect();
+        let width = amounts
+            .iter()
+            .map(|amount| amount.name.len())
+            .chain(Some(first_column.len()))
+            .max()
+            .unwrap();
+        writeln!(
+            writer,
+            "{:<width$} {:>6} {:>6} {:>6} {:>6} {:>7} {:>6}",
+            first_column,
+            "g",
+            "kcal",
+            "carb",
+            "fat",
+            "protein",
+            "kcal %",
+            width = width
+        )?;
+        for amount in amounts.into_iter().chain(Some(&self.total)) {
+            let kcal_share = if self.total.kcal > 0.0 {
+                100.0 * amount.kcal / self.total.kcal
+            } else {
+                0.0
+            };
+            writeln!(
+                writer,
+                "{:<width$} {:>6} {:>6.0} {:>6.decimals$} {:>6.decimals$} {:>7.decimals$} {:>6.pct_decimals$}",
+                amount.name,
+                amount.g,
+                amount.kcal,
+                amount.carb,
+                amount.fat,
+                amount.protein,
+                self.rounding.pct(kcal_share),
+                width = width,
+                decimals = decimals,
+                pct_decimals = pct_decimals
+            )?;
+        }
+        Ok(width)
+    }
+
+    pub fn write_json<W: Write>(&self, writer: W) -> serde_json::Result<()> {
+        serde_json::to_writer_pretty(writer, self)
+    }
+
+    /// Writes one row per ingredient followed by a total and a per 100g row
+    pub fn write_csv<W: Write>(&self, writer: W) -> csv::Result<()> {
+        let mut csv_writer = csv::Writer::from_writer(writer);
+        let summary = [&self.total, &self.per_100g];
+        for amount in self.ingredients.iter().chain(summary) {
+            let macros = Macros {
+                kcal: amount.kcal,
+                carb: amount.carb,
+                fat: amount.fat,
+                protein: amount.protein,
+                alcohol: amount.alcohol,
+                polyols: amount.polyols,
+                ..Default::default()
+            };
+            csv_writer.serialize(CsvRow {
+                amount,
+                ratio: Ratio::of(&macros, &self.rounding),
+            })?;
+        }
+        csv_writer.flush()?;
+        Ok(())
+    }
+}
+
+struct CsvRow<'a> {
+    amount: &'a IngredientAmount,
+    ratio: Ratio,
+}
+
+// csv can't serialize flattened structs, so the columns are written out by hand
+impl Serialize for CsvRow<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut row = serializer.serialize_struct("CsvRow", 11)?;
+        row.serialize_field("name", &self.amount.name)?;
+        row.serialize_field("g", &self.amount.g)?;
+        row.serialize_field("kcal", &self.amount.kcal)?;
+        row.serialize_field("carb", &self.amount.carb)?;
+        row.serialize_field("fat", &self.amount.fat)?;
+        row.serialize_field("protein", &self.amount.protein)?;
+        row.serialize_field("alcohol", &self.amount.alcohol)?;
+        row.serialize_field("polyols", &self.amount.polyols)?;
+        row.serialize_field("carb_pct", &self.ratio.carb)?;
+        row.serialize_field("fat_pct", &self.ratio.fat)?;
+        row.serialize_field("protein_pct", &self.ratio.protein)?;
+        row.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Ingredient;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_table() {
+        let mut raw_ingredients = RawIngredients(BTreeMap::new());
+        raw_ingredients.0.insert(
+            "oats".to_string(),
+            Ingredient {
+                name: "oats".to_string(),
+                g: 100,
+                kcal: 400.0,
+                carb: 60.0,
+                fat: 10.0,
+                protein: 30.0,
+                ..Default::default()
+            },
+        );
+        raw_ingredients.0.insert(
+            "quark".to_string(),
+            Ingredient {
+                name: "quark".to_string(),
+                g: 100,
+                kcal: 100.0,
+                carb: 5.0,
+                fat: 5.0,
+                protein: 10.0,
+                ..Default::default()
+            },
+        );
+        raw_ingredients.0.insert(
+            "unused ingredient".to_string(),
+            Ingredient {
+                name: "unused ingredient".to_string(),
+                g: 100,
+                kcal: 100.0,
+                ..Default::default()
+            },
+        );
+        let mut gram_proposal = Proposal(BTreeMap::new());
+        gram_proposal.0.insert("oats".to_string(), 50);
+        gram_proposal.0.insert("quark".to_string(), 200);
+        // has no row and doesn't widen the first column
+        gram_proposal.0.insert("unused ingredient".to_string(), 0);
+        let target = Ratio {
+            carb: 50.0,
+            fat: 15.0,
+            protein: 35.0,
+        };
+
+        let mut table = Vec::new();
+        Report::new(&gram_proposal, &raw_ingredients, Rounding::default())
+            .write_table(&mut table, Some(&target))
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(table).unwrap(),
+            "ingredient      g   kcal   carb    fat protein kcal %\n\
+             oats           50    200   30.0    5.0    15.0   50.0\n\
+             quark         200    200   10.0   10.0    20.0   50.0\n\
+             total         250    400   40.0   15.0    35.0  100.0\n\
+             ratio %                    44.4   16.7    38.9\n\
+             vs target                  -5.6   +1.7    +3.9\n"
+        );
+
+        // without a target there's nothing to compare the ratio to
+        let mut table = Vec::new();
+        Report::new(&gram_proposal, &raw_ingredients, Rounding::default())
+            .write_table(&mut table, None)
+            .unwrap();
+        assert!(String::from_utf8(table)
+            .unwrap()
+            .ends_with("ratio %                    44.4   16.7    38.9\n"));
+
+        // ungrouped ingredients come last
+        raw_ingredients.0.get_mut("oats").unwrap().group = Some("grains".to_string());
+        let report = Report::new(&gram_proposal, &raw_ingredients, Rounding::default());
+        let mut groups = Vec::new();
+        report.write_groups(&mut groups).unwrap();
+        assert_eq!(
+            String::from_utf8(groups).unwrap(),
+            "group       g   kcal   carb    fat protein kcal %\n\
+             grains     50    200   30.0    5.0    15.0   50.0\n\
+             other     200    200   10.0   10.0    20.0   50.0\n\
+             total     250    400   40.0   15.0    35.0  100.0\n"
+        );
+    }
+
+    #[test]
+    fn test_rounding() {
+        let mut raw_ingredients = RawIngredients(BTreeMap::new());
+        for (name, portion_g) in &[("oats", None), ("egg", Some(60))] {
+            raw_ingredients.0.insert(
+                name.to_string(),
+                Ingredient {
+                    name: name.to_string(),
+                    g: 100,
+                    kcal: 370.0,
+                    carb: 58.7,
+                    fat: 7.0,
+                    protein: 13.5,
+                    portion_g: *portion_g,
+                    ..Default::default()
+                },
+            );
+        }
+        let mut gram_proposal = Proposal(BTreeMap::new());
+        gram_proposal.0.insert("oats".to_string(), 83);
+        gram_proposal.0.insert("egg".to_string(), 120);
+        let rounding = Rounding {
+            g: 5,
+            macro_decimals: 0,
+            pct_decimals: 0,
+        };
+
+        let report = Report::new(&gram_proposal, &raw_ingredients, rounding);
+        assert_eq!(report.ingredients[0].g, 120);
+        assert_eq!(report.ingredients[1].g, 85);
+        assert_eq!(report.ingredients[1].carb, 50.0);
+        assert_eq!(report.total.g, 205);
+        assert_eq!(report.ratio.carb, 74.0);
+    }
+
+    #[test]
+    fn test_csv() {
+        let mut raw_ingredients = RawIngredients(BTreeMap::new());
+        raw_ingredients.0.insert(
+            "oats, rolled".to_string(),
+            Ingredient {
+                name: "oats, rolled".to_string(),
+                g: 100,
+                kcal: 400.0,
+                carb: 60.0,
+                fat: 10.0,
+                protein: 30.0,
+                ..Default::default()
+            },
+        );
+        let mut gram_proposal = Proposal(BTreeMap::new());
+        gram_proposal.0.insert("oats, rolled".to_string(), 50);
+
+        let mut csv = Vec::new();
+        Report::new(&gram_proposal, &raw_ingredients, Rounding::default())
+            .write_csv(&mut csv)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "name,g,kcal,carb,fat,protein,alcohol,polyols,carb_pct,fat_pct,protein_pct\n\
+             \"oats, rolled\",50,200.0,30.0,5.0,15.0,0.0,0.0,60.0,10.0,30.0\n\
+             total,50,200.0,30.0,5.0,15.0,0.0,0.0,60.0,10.0,30.0\n\
+             per 100g,100,400.0,60.0,10.0,30.0,0.0,0.0,60.0,10.0,30.0\n"
+        );
+    }
+}
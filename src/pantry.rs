@@ -0,0 +1,334 @@
+//! Field-level diffs and patches between pantries, i.e. directories of ingredient files.
+use crate::error::Error;
+use crate::{data_files, read_data};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use toml::value::{Table, Value};
+
+/// File of an ingredient or recipe in a pantry, e.g. olive-oil.toml
+///
+/// Path separators become dashes and leading dots are dropped, so the file can't be outside
+/// the pantry or hidden.
+pub fn file_name(name: &str) -> String {
+    let name = name.replace([' ', '/', '\\'], "-");
+    format!("{}.toml", name.trim_start_matches('.'))
+}
+
+/// Path of a new file of the ingredient in the pantry `dir`
+pub fn file_path(dir: &Path, name: &str) -> Result<PathBuf, Error> {
+    let file_name = file_name(name);
+    let path = dir.join(&file_name);
+    if file_name == ".toml" || path.parent() != Some(dir) {
+        return Err(Error::InvalidFileName(name.to_string()));
+    }
+    Ok(path)
+}
+
+/// Ingredient and recipe files of a pantry by name
+pub struct Pantry(pub BTreeMap<String, PantryFile>);
+
+pub struct PantryFile {
+    pub path: PathBuf,
+    pub fields: Table,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct Patch {
+    #[serde(default)]
+    pub changes: Vec<Change>,
+}
+
+/// Change of a single field of an ingredient.
+///
+/// Without a field the whole ingredient is added (no `old`) or removed (no `new`).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Change {
+    pub ingredient: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new: Option<Value>,
+}
+
+/// A change whose `old` value doesn't match the pantry
+#[derive(Debug, PartialEq)]
+pub struct Conflict {
+    pub change: Change,
+    pub found: Option<Value>,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ingredient = &self.change.ingredient;
+        let field = match &self.change.field {
+            Some(field) => field,
+            None => {
+                return match (&self.found, &self.change.old) {
+                    (None, _) => write!(f, "{} doesn't exist but the patch removes it", ingredient),
+                    (Some(_), None) => {
+                        write!(f, "{} already exists but the patch adds it", ingredient)
+                    }
+                    (Some(_), Some(_)) => write!(
+                        f,
+                        "{} differs from the version the patch removes",
+                        ingredient
+                    ),
+                };
+            }
+        };
+        let show = |value: &Option<Value>| match value {
+            Some(value) => value.to_string(),
+            None => "missing".to_string(),
+        };
+        write!(
+            f,
+            "{}.{} is {} but the patch expects {}",
+            ingredient,
+            field,
+            show(&self.found),
+            show(&self.change.old)
+        )
+    }
+}
+
+/// File operation that applies a patch
+#[derive(Debug, PartialEq)]
+pub enum Edit {
+    Write { path: PathBuf, fields: Table },
+    Remove(PathBuf),
+}
+
+impl Pantry {
+    pub fn load(dir: &Path) -> Result<Pantry, Error> {
+        let mut paths = Vec::new();
+        data_files(dir, &mut paths)?;
+        paths.sort();
+        let mut files = BTreeMap::new();
+        for path in paths {
+            let fields: Table = read_data(&path)?;
+            let name = match fields.get("name") {
+                Some(Value::String(name)) => name.clone(),
+                _ => return Err(Error::MissingName(path)),
+            };
+            files.insert(name, PantryFile { path, fields });
+        }
+        Ok(Pantry(files))
+    }
+}
+
+/// Returns the changes that turn `pantry` into `other`
+pub fn diff(pantry: &Pantry, other: &Pantry) -> Patch {
+    let mut changes = Vec::new();
+    for (name, file) in &pantry.0 {
+        let other_file = match other.0.get(name) {
+            Some(other_file) => other_file,
+            None => {
+                changes.push(Change {
+                    ingredient: name.clone(),
+                    field: None,
+                    old: Some(Value::Table(file.fields.clone())),
+                    new: None,
+                });
+                continue;
+            }
+        };
+        let mut fields: Vec<&String> = file.fields.keys().collect();
+        fields.extend(other_file.fields.keys());
+        fields.sort();
+        fields.dedup();
+        for field in fields {
+            let old = file.fields.get(field);
+            let new = other_file.fields.get(field);
+            if old != new {
+                changes.push(Change {
+                    ingredient: name.clone(),
+                    field: Some(field.clone()),
+                    old: old.cloned(),
+                    new: new.cloned(),
+                });
+            }
+        }
+    }
+    for (name, other_file) in &other.0 {
+        if !pantry.0.contains_key(name) {
+            changes.push(Change {
+                ingredient: name.clone(),
+                field: None,
+                old: None,
+                new: Some(Value::Table(other_file.fields.clone())),
+            });
+        }
+    }
+    Patch { changes }
+}
+
+/// Computes the file operations that apply the patch to the pantry in `dir`.
+///
+/// New ingredients are written to `<dir>/<name>.toml`. If any change conflicts, nothing is
+/// applied and all conflicts are returned.
+pub fn apply(pantry: &Pantry, patch: &Patch, dir: &Path) -> Result<Vec<Edit>, Vec<Conflict>> {
+    // None once an ingredient is removed
+    let mut patched: BTreeMap<&str, Option<Table>> = BTreeMap::new();
+    let mut conflicts = Vec::new();
+    for change in &patch.changes {
+        let current = patched
+            .get(change.ingredient.as_str())
+            .cloned()
+            .unwrap_or_else(|| {
+                pantry
+                    .0
+                    .get(&change.ingredient)
+                    .map(|file| file.fields.clone())
+            });
+        let found = match (&change.field, &current) {
+            (Some(field), Some(fields)) => fields.get(field).cloned(),
+            (Some(_), None) => None,
+            (None, fields) => fields.clone().map(Value::Table),
+        };
+        if found != change.old || (change.field.is_some() && current.is_none()) {
+            conflicts.push(Conflict {
+                change: change.clone(),
+                found,
+            });
+            continue;
+        }
+        let updated = match (&change.field, current) {
+            (Some(field), Some(mut fields)) => {
+                match &change.new {
+                    Some(value) => fields.insert(field.clone(), value.clone()),
+                    None => fields.remove(field),
+                };
+                Some(fields)
+            }
+            (_, _) => match &change.new {
+                Some(Value::Table(fields)) => Some(fields.clone()),
+                _ => None,
+            },
+        };
+        patched.insert(&change.ingredient, updated);
+    }
+    if !conflicts.is_empty() {
+        return Err(conflicts);
+    }
+
+    // files are removed last, so a failed write leaves them in place
+    let mut edits = Vec::new();
+    let mut removals = Vec::new();
+    for (name, fields) in patched {
+        let path = pantry.0.get(name).map(|file| file.path.clone());
+        match (fields, path) {
+            (Some(fields), Some(path)) => edits.push(Edit::Write { path, fields }),
+            (Some(fields), None) => edits.push(Edit::Write {
+                path: dir.join(file_name(name)),
+                fields,
+            }),
+            (None, Some(path)) => removals.push(Edit::Remove(path)),
+            // added and removed again by the same patch
+            (None, None) => (),
+        }
+    }
+    edits.extend(removals);
+    Ok(edits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pantry(files: &[&str]) -> Pantry {
+        let mut pantry = Pantry(BTreeMap::new());
+        for contents in files {
+            let fields: Table = toml::from_str(contents).unwrap();
+            let name = fields["name"].as_str().unwrap().to_string();
+            let path = PathBuf::from(format!("pantry/{}.toml", name));
+            pantry.0.insert(name, PantryFile { path, fields });
+        }
+        pantry
+    }
+
+    #[test]
+    fn test_file_path() {
+        let dir = Path::new("pantry");
+        assert_eq!(
+            file_path(dir, "olive oil").unwrap(),
+            Path::new("pantry/olive-oil.toml")
+        );
+        assert_eq!(file_path(dir, "../x").unwrap(), Path::new("pantry/-x.toml"));
+        assert_eq!(
+            file_path(dir, "1/2 cup").unwrap(),
+            Path::new("pantry/1-2-cup.toml")
+        );
+        assert!(matches!(
+            file_path(dir, ".."),
+            Err(Error::InvalidFileName(_))
+        ));
+    }
+
+    #[test]
+    fn test_diff_apply() {
+        let ours = pantry(&[
+            "name = \"oats\"\ng = 100\nkcal = 389",
+            "name = \"egg\"\ng = 60\nkcal = 90",
+        ]);
+        let theirs = pantry(&[
+            "name = \"oats\"\ng = 100\nkcal = 379\nportion_g = 40",
+            "name = \"whey\"\ng = 30\nkcal = 120",
+        ]);
+        let patch = diff(&ours, &theirs);
+        assert_eq!(patch.changes.len(), 4);
+        assert_eq!(
+            patch.changes[0],
+            Change {
+                ingredient: "egg".to_string(),
+                field: None,
+                old: Some(Value::Table(ours.0["egg"].fields.clone())),
+                new: None,
+            }
+        );
+        assert_eq!(patch.changes[1].field.as_deref(), Some("kcal"));
+        assert_eq!(patch.changes[2].field.as_deref(), Some("portion_g"));
+        assert_eq!(patch.changes[2].old, None);
+
+        // the patch survives being written and read
+        let patch: Patch = toml::from_str(&toml::to_string(&patch).unwrap()).unwrap();
+        let edits = apply(&ours, &patch, Path::new("pantry")).unwrap();
+        assert_eq!(
+            edits,
+            vec![
+                Edit::Write {
+                    path: PathBuf::from("pantry/oats.toml"),
+                    fields: theirs.0["oats"].fields.clone(),
+                },
+                Edit::Write {
+                    path: PathBuf::from("pantry/whey.toml"),
+                    fields: theirs.0["whey"].fields.clone(),
+                },
+                Edit::Remove(PathBuf::from("pantry/egg.toml")),
+            ]
+        );
+
+        // someone else changed the kcal in the meantime
+        let changed = pantry(&[
+            "name = \"oats\"\ng = 100\nkcal = 380",
+            "name = \"egg\"\ng = 60\nkcal = 90",
+        ]);
+        let conflicts = apply(&changed, &patch, Path::new("pantry")).unwrap_err();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(
+            conflicts[0].to_string(),
+            "oats.kcal is 380 but the patch expects 389"
+        );
+
+        // the patch was already applied
+        let conflicts = apply(&theirs, &patch, Path::new("pantry")).unwrap_err();
+        assert_eq!(conflicts.len(), 4);
+        assert_eq!(
+            conflicts[0].to_string(),
+            "egg doesn't exist but the patch removes it"
+        );
+    }
+}
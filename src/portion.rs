@@ -0,0 +1,107 @@
+//! Divides cooked meals across meal prep containers.
+use crate::error::Error;
+use crate::meal::Meal;
+use crate::Macros;
+
+#[derive(Debug, PartialEq)]
+pub struct Container {
+    // cooked weight in the container
+    pub g: u64,
+    pub macros: Macros,
+}
+
+/// Checks that there is a container and the relative sizes can divide a meal
+pub fn check_shares(shares: &[f64]) -> Result<(), Error> {
+    if shares.is_empty() {
+        return Err(Error::InvalidShares(
+            "need at least one container".to_string(),
+        ));
+    }
+    if shares
+        .iter()
+        .any(|share| *share < 0.0 || !share.is_finite())
+    {
+        return Err(Error::InvalidShares(
+            "must be numbers of 0 or more".to_string(),
+        ));
+    }
+    if shares.iter().sum::<f64>() <= 0.0 {
+        return Err(Error::InvalidShares(
+            "must add up to more than 0".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Splits `cooked_g` grams of the meal into containers of the given relative sizes.
+///
+/// The grams are rounded such that they add up to the cooked weight.
+pub fn split(meal: &Meal, cooked_g: u64, shares: &[f64]) -> Vec<Container> {
+    let total_share: f64 = shares.iter().sum();
+    let macros = meal.macros();
+    let mut containers = Vec::new();
+    let mut cumulative_share = 0.0;
+    let mut assigned_g = 0;
+    for share in shares {
+        cumulative_share += share;
+        let g = (cooked_g as f64 * cumulative_share / total_share).round() as u64 - assigned_g;
+        assigned_g += g;
+        containers.push(Container {
+            g,
+            macros: macros.per_g(*share, total_share),
+        });
+    }
+    containers
+}
+
+pub fn print_portions(meal: &Meal, cooked_g: u64, containers: &[Container]) {
+    println!("{} ({}g cooked)", meal.name, cooked_g);
+    for (i, container) in containers.iter().enumerate() {
+        println!(
+            "\tcontainer {}: {}g with {}",
+            i + 1,
+            container.g,
+            container
+                .macros
+                .summary(container.macros.kcal.round() as u64)
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Cli;
+    use clap::Parser;
+
+    #[test]
+    fn test_split() {
+        let meal = Meal {
+            name: "chili".to_string(),
+            kcal: 2000.0,
+            carb: 200.0,
+            fat: 60.0,
+            protein: 160.0,
+            tags: Vec::new(),
+            notes: None,
+            ingredients: Vec::new(),
+        };
+        let containers = split(&meal, 1000, &[2.0, 1.0, 1.0, 1.0, 1.0, 1.0]);
+        let grams: Vec<u64> = containers.iter().map(|container| container.g).collect();
+        assert_eq!(grams, vec![286, 143, 142, 143, 143, 143]);
+        assert_eq!(grams.iter().sum::<u64>(), 1000);
+        assert!((containers[0].macros.kcal - 2000.0 * 2.0 / 7.0).abs() < 1e-9);
+        assert!((containers[1].macros.protein - 160.0 / 7.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_check_shares() {
+        assert!(check_shares(&[2.0, 0.0, 1.0]).is_ok());
+        for shares in [&[2.0, -1.0, 1.0][..], &[0.0, 0.0], &[]] {
+            assert!(matches!(check_shares(shares), Err(Error::InvalidShares(_))));
+        }
+        let containers = ["natural-bodybuilder", "portion", "--meal", "chili.toml"];
+        assert!(Cli::try_parse_from(containers.iter().chain(&["--containers", "0"])).is_err());
+        assert!(Cli::try_parse_from(containers.iter().chain(&["--containers", "5"])).is_ok());
+    }
+}
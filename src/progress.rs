@@ -0,0 +1,75 @@
+//! A progress bar of the search on stderr, for large pantries and many steps.
+use std::cell::Cell;
+use std::io::Write;
+
+const WIDTH: usize = 40;
+
+/// Progress of the search over all of its runs
+pub struct Progress {
+    // pieces of all runs
+    total: usize,
+    // pieces of the runs before the current one
+    offset: Cell<usize>,
+    // percent that was drawn last
+    drawn: Cell<Option<usize>>,
+}
+
+impl Progress {
+    pub fn new(steps: usize, runs: usize) -> Progress {
+        Progress {
+            total: steps * runs.max(1),
+            offset: Cell::new(0),
+            drawn: Cell::new(None),
+        }
+    }
+
+    /// Starts the run with index `run` of runs of `steps` pieces
+    pub fn start_run(&self, run: usize, steps: usize) {
+        self.offset.set(run * steps);
+    }
+
+    /// Redraws the bar if the pieces assigned in the current run changed the percentage
+    pub fn update(&self, assigned: usize) {
+        let done = (self.offset.get() + assigned).min(self.total);
+        let pct = 100 * done / self.total.max(1);
+        if self.drawn.get() == Some(pct) {
+            return;
+        }
+        self.drawn.set(Some(pct));
+        eprint!("\r{}", bar(done, self.total));
+        std::io::stderr().flush().ok();
+    }
+
+    /// Clears the bar so that it doesn't mix with the output
+    pub fn finish(&self) {
+        if self.drawn.get().is_some() {
+            eprint!("\r{}\r", " ".repeat(WIDTH + 7));
+        }
+    }
+}
+
+/// e.g. "[##########                              ]  25%"
+fn bar(done: usize, total: usize) -> String {
+    let filled = WIDTH * done / total.max(1);
+    format!(
+        "[{}{}] {:>3}%",
+        "#".repeat(filled),
+        " ".repeat(WIDTH - filled),
+        100 * done / total.max(1)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bar() {
+        assert_eq!(bar(0, 200), format!("[{}]   0%", " ".repeat(40)));
+        assert_eq!(
+            bar(50, 200),
+            format!("[{}{}]  25%", "#".repeat(10), " ".repeat(30))
+        );
+        assert_eq!(bar(200, 200), format!("[{}] 100%", "#".repeat(40)));
+    }
+}
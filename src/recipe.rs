@@ -0,0 +1,266 @@
+//! Recipes combine ingredients in fixed proportions and are used like a single ingredient.
+use crate::error::Error;
+use crate::{Ingredient, RawIngredients};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Recipe {
+    pub name: String,
+    pub components: Vec<Component>,
+    // bounds, portion and batch size of the recipe as for ingredients, in g
+    pub min_g: Option<u64>,
+    pub max_g: Option<u64>,
+    pub portion_g: Option<u64>,
+    pub batch_g: Option<u64>,
+    // minutes of preparing the recipe, the sum of its components' if omitted
+    pub prep_minutes: Option<f64>,
+    pub group: Option<String>,
+    // in addition to the ones derived from the components
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub meals: Vec<String>,
+}
+
+// tags that a recipe only has if all of its components have them
+const DIET_TAGS: &[&str] = &["vegetarian", "vegan"];
+
+/// An ingredient, or another recipe, in a recipe
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Component {
+    pub name: String,
+    pub g: u64,
+}
+
+impl Recipe {
+    /// The recipe's own tags, the diets that all of its components fit and every other tag of
+    /// the components, such as allergens
+    fn tags(&self, raw_ingredients: &RawIngredients) -> Vec<String> {
+        let components: Vec<&Ingredient> = self
+            .components
+            .iter()
+            .map(|component| &raw_ingredients.0[&component.name])
+            .collect();
+        let all_have = |tags: &[&str]| {
+            components.iter().all(|ingredient| {
+                ingredient
+                    .tags
+                    .iter()
+                    .any(|tag| tags.contains(&tag.as_str()))
+            })
+        };
+        let mut tags = self.tags.clone();
+        if all_have(&["vegan"]) {
+            tags.push("vegan".to_string());
+        }
+        if all_have(&["vegetarian", "vegan"]) {
+            tags.push("vegetarian".to_string());
+        }
+        for ingredient in &components {
+            let other = ingredient
+                .tags
+                .iter()
+                .filter(|tag| !DIET_TAGS.contains(&tag.as_str()));
+            tags.extend(other.cloned());
+        }
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+
+    /// Computes the recipe's macros from the ones of its components
+    fn to_ingredient(&self, raw_ingredients: &RawIngredients) -> Ingredient {
+        let mut kcal = 0.0;
+        let mut carb = 0.0;
+        let mut fat = 0.0;
+        let mut protein = 0.0;
+        // protein times its score
+        let mut scored_protein = 0.0;
+        let mut leucine_g = 0.0;
+        let mut eaa_g = 0.0;
+        let mut omega3_g = 0.0;
+        let mut omega6_g = 0.0;
+        let mut alcohol = 0.0;
+        let mut polyols = 0.0;
+        let mut sat_fat = 0.0;
+        let mut sugar = 0.0;
+        let mut sodium_mg = 0.0;
+        let mut water_ml = 0.0;
+        let mut caffeine_mg = 0.0;
+        let mut glycemic_load = 0.0;
+        let mut prep_minutes = 0.0;
+        for component in &self.components {
+            let ingredient = &raw_ingredients.0[&component.name];
+            let factor = component.g as f64 / ingredient.g as f64;
+            kcal += factor * ingredient.kcal;
+            carb += factor * ingredient.carb;
+            fat += factor * ingredient.fat;
+            protein += factor * ingredient.protein;
+            scored_protein += factor * ingredient.protein * ingredient.protein_score.unwrap_or(0.0);
+            leucine_g += factor * ingredient.leucine_g.unwrap_or(0.0);
+            eaa_g += factor * ingredient.eaa_g.unwrap_or(0.0);
+            omega3_g += factor * ingredient.omega3_g.unwrap_or(0.0);
+            omega6_g += factor * ingredient.omega6_g.unwrap_or(0.0);
+            alcohol += factor * ingredient.alcohol.unwrap_or(0.0);
+            polyols += factor * ingredient.polyols.unwrap_or(0.0);
+            sat_fat += factor * ingredient.sat_fat.unwrap_or(0.0);
+            sugar += factor * ingredient.sugar.unwrap_or(0.0);
+            sodium_mg += factor * ingredient.sodium_mg.unwrap_or(0.0);
+            water_ml += factor * ingredient.water_ml.unwrap_or(0.0);
+            caffeine_mg += factor * ingredient.caffeine_mg.unwrap_or(0.0);
+            glycemic_load += factor * ingredient.glycemic_load();
+            prep_minutes += ingredient.prep_minutes.unwrap_or(0.0);
+        }
+        Ingredient {
+            name: self.name.clone(),
+            g: self.components.iter().map(|component| component.g).sum(),
+            kcal,
+            carb,
+            fat,
+            protein,
+            // the average score of the protein, components without one count as 0
+            protein_score: Some(scored_protein / protein).filter(|_| scored_protein > 0.0),
+            leucine_g: Some(leucine_g).filter(|g| *g > 0.0),
+            eaa_g: Some(eaa_g).filter(|g| *g > 0.0),
+            omega3_g: Some(omega3_g).filter(|g| *g > 0.0),
+            omega6_g: Some(omega6_g).filter(|g| *g > 0.0),
+            alcohol: Some(alcohol).filter(|g| *g > 0.0),
+            polyols: Some(polyols).filter(|g| *g > 0.0),
+            sat_fat: Some(sat_fat).filter(|g| *g > 0.0),
+            sugar: Some(sugar).filter(|g| *g > 0.0),
+            sodium_mg: Some(sodium_mg).filter(|mg| *mg > 0.0),
+            water_ml: Some(water_ml).filter(|ml| *ml > 0.0),
+            caffeine_mg: Some(caffeine_mg).filter(|mg| *mg > 0.0),
+            // the index of the recipe's carbs that gives the same load
+            glycemic_index: Some(100.0 * glycemic_load / (carb - polyols))
+                .filter(|_| glycemic_load > 0.0),
+            min_g: self.min_g,
+            max_g: self.max_g,
+            portion_g: self.portion_g,
+            batch_g: self.batch_g,
+            prep_minutes: self
+                .prep_minutes
+                .or(Some(prep_minutes).filter(|minutes| *minutes > 0.0)),
+            group: self.group.clone(),
+            tags: self.tags(raw_ingredients),
+            meals: self.meals.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Adds the recipes to the ingredients.
+///
+/// Recipes may contain other recipes, as long as they don't contain themselves.
+pub fn resolve(recipes: Vec<Recipe>, raw_ingredients: &mut RawIngredients) -> Result<(), Error> {
+    let mut unresolved = recipes;
+    while !unresolved.is_empty() {
+        let (ready, waiting): (Vec<Recipe>, Vec<Recipe>) =
+            unresolved.into_iter().partition(|recipe| {
+                recipe
+                    .components
+                    .iter()
+                    .all(|component| raw_ingredients.0.contains_key(&component.name))
+            });
+        if ready.is_empty() {
+            // every remaining recipe misses a component, or they contain each other
+            let recipe = &waiting[0];
+            let component = recipe
+                .components
+                .iter()
+                .find(|component| !raw_ingredients.0.contains_key(&component.name))
+                .unwrap();
+            return Err(Error::MissingComponent {
+                recipe: recipe.name.clone(),
+                component: component.name.clone(),
+            });
+        }
+        for recipe in ready {
+            let ingredient = recipe.to_ingredient(raw_ingredients);
+            raw_ingredients.0.insert(recipe.name.clone(), ingredient);
+        }
+        unresolved = waiting;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn recipe(name: &str, components: &[(&str, u64)]) -> Recipe {
+        Recipe {
+            name: name.to_string(),
+            components: components
+                .iter()
+                .map(|(name, g)| Component {
+                    name: name.to_string(),
+                    g: *g,
+                })
+                .collect(),
+            min_g: None,
+            max_g: None,
+            portion_g: None,
+            batch_g: None,
+            prep_minutes: None,
+            group: None,
+            tags: Vec::new(),
+            meals: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_resolve() {
+        let mut raw_ingredients = RawIngredients(BTreeMap::new());
+        raw_ingredients.0.insert(
+            "oats".to_string(),
+            Ingredient {
+                name: "oats".to_string(),
+                g: 100,
+                kcal: 380.0,
+                carb: 60.0,
+                fat: 7.0,
+                protein: 13.0,
+                tags: vec!["vegan".to_string(), "gluten".to_string()],
+                ..Default::default()
+            },
+        );
+        raw_ingredients.0.insert(
+            "egg".to_string(),
+            Ingredient {
+                name: "egg".to_string(),
+                g: 60,
+                kcal: 90.0,
+                carb: 0.0,
+                fat: 6.0,
+                protein: 8.0,
+                tags: vec!["vegetarian".to_string()],
+                ..Default::default()
+            },
+        );
+        let recipes = vec![
+            recipe("pancake stack", &[("pancake", 340), ("oats", 10)]),
+            recipe("pancake", &[("oats", 100), ("egg", 240)]),
+        ];
+        resolve(recipes, &mut raw_ingredients).unwrap();
+
+        let pancake = &raw_ingredients.0["pancake"];
+        assert_eq!(pancake.g, 340);
+        assert_eq!(pancake.kcal, 740.0);
+        assert_eq!(pancake.carb, 60.0);
+        assert_eq!(pancake.fat, 31.0);
+        assert_eq!(pancake.protein, 45.0);
+        let stack = &raw_ingredients.0["pancake stack"];
+        assert_eq!(stack.g, 350);
+        assert_eq!(stack.kcal.round(), 778.0);
+        // the egg isn't vegan, the oats contain gluten
+        assert_eq!(stack.tags, vec!["gluten", "vegetarian"]);
+
+        let cyclic = vec![recipe("a", &[("b", 10)]), recipe("b", &[("a", 10)])];
+        assert!(matches!(
+            resolve(cyclic, &mut raw_ingredients),
+            Err(Error::MissingComponent { .. })
+        ));
+    }
+}
@@ -0,0 +1,90 @@
+//! HTTP server with a JSON API to the optimizer, e.g. for a web frontend or a phone shortcut.
+use crate::api::{error_json, optimize_request, OptimizeRequest};
+use crate::error::Error;
+use tiny_http::{Header, Method, Response, Server};
+
+/// Returns the status code and json body of the response to a request.
+///
+/// Errors have the same `code` and `message` as with `--error-format json`.
+fn respond(method: &Method, url: &str, body: &str) -> (u16, String) {
+    if url != "/optimize" {
+        return (
+            404,
+            error_json("not_found", "the only endpoint is /optimize"),
+        );
+    }
+    if *method != Method::Post {
+        return (405, error_json("method_not_allowed", "use POST"));
+    }
+    let request: OptimizeRequest = match serde_json::from_str(body) {
+        Ok(request) => request,
+        Err(e) => return (400, error_json("parse", &e.to_string())),
+    };
+    match optimize_request(request) {
+        Ok(report) => (200, serde_json::to_string(&report).unwrap()),
+        Err(e) => (422, serde_json::to_string(&e.report()).unwrap()),
+    }
+}
+
+/// Serves requests on `addr` until the process is stopped
+pub fn serve(addr: &str) -> Result<(), Error> {
+    let server = Server::http(addr).map_err(|source| Error::Serve {
+        addr: addr.to_string(),
+        source,
+    })?;
+    println!("listening on http://{}", addr);
+    let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+    for mut request in server.incoming_requests() {
+        let mut body = String::new();
+        let (status, response) = match request.as_reader().read_to_string(&mut body) {
+            Ok(_) => respond(request.method(), request.url(), &body),
+            Err(e) => (400, error_json("read", &e.to_string())),
+        };
+        let response = Response::from_string(response)
+            .with_status_code(status)
+            .with_header(content_type.clone());
+        if let Err(e) = request.respond(response) {
+            eprintln!("warning: could not send response: {}", e);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_respond() {
+        let body = r#"{
+            "target": {"kcal": 500, "carb": 60, "fat": 10, "protein": 30},
+            "ingredients": [
+                {"name": "oats", "g": 100, "carb": 60, "fat": 7, "protein": 13},
+                {"name": "quark", "g": 100, "kcal": 67, "carb": 4, "fat": 0.2, "protein": 12}
+            ],
+            "steps": 100
+        }"#;
+        let (status, response) = respond(&Method::Post, "/optimize", body);
+        assert_eq!(status, 200);
+        let report: serde_json::Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(report["ingredients"].as_array().unwrap().len(), 2);
+        assert!((report["total"]["kcal"].as_f64().unwrap() - 500.0).abs() < 5.0);
+
+        let missing = r#"{
+            "target": {
+                "kcal": 500, "carb": 60, "fat": 10, "protein": 30,
+                "constraint_at_least": [{"name": "quark", "g": 100}]
+            },
+            "ingredients": [{"name": "oats", "g": 100, "carb": 60, "fat": 7, "protein": 13}]
+        }"#;
+        let (status, response) = respond(&Method::Post, "/optimize", missing);
+        assert_eq!(
+            (status, response.contains("missing_ingredient")),
+            (422, true)
+        );
+
+        assert_eq!(respond(&Method::Post, "/optimize", "{").0, 400);
+        assert_eq!(respond(&Method::Get, "/optimize", body).0, 405);
+        assert_eq!(respond(&Method::Post, "/", body).0, 404);
+    }
+}
@@ -0,0 +1,111 @@
+//! Aggregates the ingredients of several plans into a single shopping list.
+use crate::{Proposal, RawIngredients};
+
+#[derive(Debug, PartialEq)]
+pub struct ShoppingItem {
+    pub name: String,
+    // needed amount in g
+    pub g: u64,
+    // size of the package the ingredient is sold in, if known
+    pub package_g: Option<u64>,
+}
+
+impl ShoppingItem {
+    /// Number of packages to buy to have at least the needed amount
+    pub fn packages(&self) -> Option<u64> {
+        self.package_g
+            .filter(|package_g| *package_g > 0)
+            .map(|package_g| self.g.div_ceil(package_g))
+    }
+}
+
+/// Adds up the grams of the proposals
+pub fn aggregate<'a, I: IntoIterator<Item = &'a Proposal>>(gram_proposals: I) -> Proposal {
+    let mut total = Proposal(Default::default());
+    for gram_proposal in gram_proposals {
+        for (name, g) in &gram_proposal.0 {
+            *total.0.entry(name.to_string()).or_insert(0) += g;
+        }
+    }
+    total
+}
+
+/// Lists the ingredients that are needed sorted by name.
+///
+/// Ingredients that aren't loaded (e.g. from someone else's meal) have no package size.
+pub fn shopping_list(total: &Proposal, raw_ingredients: &RawIngredients) -> Vec<ShoppingItem> {
+    let mut items: Vec<ShoppingItem> = total
+        .0
+        .iter()
+        .filter(|(_, g)| **g > 0)
+        .map(|(name, g)| ShoppingItem {
+            name: name.to_string(),
+            g: *g,
+            package_g: raw_ingredients
+                .0
+                .get(name)
+                .and_then(|ingredient| ingredient.package_g),
+        })
+        .collect();
+    items.sort_by(|a, b| a.name.cmp(&b.name));
+    items
+}
+
+pub fn print_shopping_list(items: &[ShoppingItem]) {
+    println!("---- SHOPPING LIST ----");
+    for item in items {
+        match (item.packages(), item.package_g) {
+            (Some(packages), Some(package_g)) => println!(
+                "{}: {}g ({} x {}g package)",
+                item.name, item.g, packages, package_g
+            ),
+            _ => println!("{}: {}g", item.name, item.g),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Ingredient;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_shopping_list() {
+        let mut monday = Proposal(BTreeMap::new());
+        monday.0.insert("oats".to_string(), 300);
+        monday.0.insert("banana".to_string(), 0);
+        let mut tuesday = Proposal(BTreeMap::new());
+        tuesday.0.insert("oats".to_string(), 250);
+        tuesday.0.insert("quark".to_string(), 500);
+
+        let mut raw_ingredients = RawIngredients(BTreeMap::new());
+        raw_ingredients.0.insert(
+            "oats".to_string(),
+            Ingredient {
+                name: "oats".to_string(),
+                package_g: Some(500),
+                ..Default::default()
+            },
+        );
+
+        let items = shopping_list(&aggregate(&[monday, tuesday]), &raw_ingredients);
+        assert_eq!(
+            items,
+            vec![
+                ShoppingItem {
+                    name: "oats".to_string(),
+                    g: 550,
+                    package_g: Some(500),
+                },
+                ShoppingItem {
+                    name: "quark".to_string(),
+                    g: 500,
+                    package_g: None,
+                },
+            ]
+        );
+        assert_eq!(items[0].packages(), Some(2));
+        assert_eq!(items[1].packages(), None);
+    }
+}
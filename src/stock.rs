@@ -0,0 +1,326 @@
+//! Recommends standing stock levels from the meals of past weeks, limits plans to the
+//! inventory and takes accepted plans out of it.
+use crate::{Proposal, RawIngredients, Target, TargetConstraint};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StockLevel {
+    pub name: String,
+    // amount to keep in the pantry in g
+    pub g: u64,
+    // best before, the plan prefers the items that expire soonest
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<Date>,
+}
+
+/// A day as the number of days since 1970-01-01, written as YYYY-MM-DD
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(try_from = "String", into = "String")]
+pub struct Date(i64);
+
+impl Date {
+    pub fn today() -> Date {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+        Date((seconds / (24 * 60 * 60)) as i64)
+    }
+
+    /// Monday of the date's week, 1970-01-01 was a Thursday
+    pub fn monday(self) -> Date {
+        Date(self.0 - (self.0 + 3).rem_euclid(7))
+    }
+
+    /// Days of a date of the Gregorian calendar, by Howard Hinnant's `days_from_civil`
+    fn from_civil(year: i64, month: i64, day: i64) -> Date {
+        let year = if month <= 2 { year - 1 } else { year };
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        Date(era * 146097 + day_of_era - 719468)
+    }
+
+    /// Year, month and day, the inverse of `from_civil`
+    fn to_civil(self) -> (i64, i64, i64) {
+        let days = self.0 + 719468;
+        let era = days.div_euclid(146097);
+        let day_of_era = days - era * 146097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        };
+        let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+        (year, month, day)
+    }
+}
+
+impl TryFrom<String> for Date {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Date, String> {
+        let invalid = || format!("invalid date {}, expected YYYY-MM-DD", text);
+        let parts: Vec<i64> = text
+            .split('-')
+            .map(|part| part.parse().map_err(|_| invalid()))
+            .collect::<Result<_, _>>()?;
+        let (year, month, day) = match parts[..] {
+            [year, month, day] => (year, month, day),
+            _ => return Err(invalid()),
+        };
+        let date = Date::from_civil(year, month, day);
+        // e.g. February 30th turns into another day
+        if !(1..=12).contains(&month) || date.to_civil() != (year, month, day) {
+            return Err(invalid());
+        }
+        Ok(date)
+    }
+}
+
+impl From<Date> for String {
+    fn from(date: Date) -> String {
+        let (year, month, day) = date.to_civil();
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+/// Inventory template with one `[[item]]` per ingredient
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct Inventory {
+    #[serde(default)]
+    pub item: Vec<StockLevel>,
+}
+
+/// Average weekly consumption plus a safety margin, rounded up to whole grams
+pub fn recommend(total: &Proposal, weeks: u64, margin_pct: f64) -> Inventory {
+    let item = total
+        .0
+        .iter()
+        .filter(|(_, g)| **g > 0)
+        .map(|(name, g)| StockLevel {
+            name: name.to_string(),
+            g: (*g as f64 / weeks as f64 * (1.0 + margin_pct / 100.0)).ceil() as u64,
+            expires: None,
+        })
+        .collect();
+    Inventory { item }
+}
+
+impl Inventory {
+    /// How soon each ingredient in stock with an expiry date expires, 1 if it expires today or
+    /// has expired, 1/2 tomorrow, 1/3 the day after and so on, of several items the soonest
+    pub fn urgency(&self, today: Date) -> BTreeMap<String, f64> {
+        let mut urgency = BTreeMap::new();
+        for item in self.item.iter().filter(|item| item.g > 0) {
+            if let Some(expires) = item.expires {
+                let days_left = (expires.0 - today.0).max(0);
+                let soonest = urgency.entry(item.name.clone()).or_insert(0.0);
+                *soonest = f64::max(*soonest, 1.0 / (1 + days_left) as f64);
+            }
+        }
+        urgency
+    }
+
+    /// Adds an at most constraint to the target for each loaded ingredient of the inventory, of
+    /// the grams of all its items shared by the `days` of the plan
+    pub fn limit(&self, target: &mut Target, raw_ingredients: &RawIngredients, days: u64) {
+        let mut stock: BTreeMap<&str, u64> = BTreeMap::new();
+        for item in &self.item {
+            if raw_ingredients.0.contains_key(&item.name) {
+                *stock.entry(&item.name).or_default() += item.g;
+            }
+        }
+        let at_most = target.constraint_at_most.get_or_insert_with(Vec::new);
+        for (name, g) in stock {
+            let g = g / days;
+            match at_most
+                .iter_mut()
+                .find(|constraint| constraint.name == name)
+            {
+                Some(constraint) => constraint.g = constraint.g.min(g),
+                None => at_most.push(TargetConstraint {
+                    name: name.to_string(),
+                    g,
+                }),
+            }
+        }
+    }
+}
+
+/// Takes the plan's grams of `days` days from the inventory, from the items that expire soonest
+/// first, and returns a note for each ingredient of the plan that isn't in it or not enough of it
+pub fn consume(inventory: &mut Inventory, gram_proposal: &Proposal, days: u64) -> Vec<String> {
+    let mut notes = Vec::new();
+    for (name, g) in gram_proposal.0.iter().filter(|(_, g)| **g > 0) {
+        let mut g = g * days;
+        let mut items: Vec<&mut StockLevel> = inventory
+            .item
+            .iter_mut()
+            .filter(|item| item.name == *name)
+            .collect();
+        if items.is_empty() {
+            notes.push(format!("{} isn't in the inventory", name));
+            continue;
+        }
+        let left: u64 = items.iter().map(|item| item.g).sum();
+        if g > left {
+            notes.push(format!(
+                "the plan uses {}g of {} but only {}g are left",
+                g, name, left
+            ));
+        }
+        // items without an expiry date last
+        items.sort_by_key(|item| (item.expires.is_none(), item.expires));
+        for item in items {
+            let taken = g.min(item.g);
+            item.g -= taken;
+            g -= taken;
+        }
+    }
+    notes
+}
+
+pub fn print_inventory(inventory: &Inventory) {
+    println!("---- STOCK ----");
+    for item in &inventory.item {
+        println!("{}: {}g", item.name, item.g);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_consume() {
+        let mut inventory: Inventory = toml::from_str(
+            "[[item]]\nname = \"oats\"\ng = 1000\n[[item]]\nname = \"quark\"\ng = 500",
+        )
+        .unwrap();
+        let mut plan = Proposal(BTreeMap::new());
+        plan.0.insert("oats".to_string(), 100);
+        plan.0.insert("quark".to_string(), 300);
+        plan.0.insert("banana".to_string(), 120);
+        assert_eq!(
+            consume(&mut inventory, &plan, 2),
+            vec![
+                "banana isn't in the inventory",
+                "the plan uses 600g of quark but only 500g are left",
+            ]
+        );
+        let left: Vec<u64> = inventory.item.iter().map(|item| item.g).collect();
+        assert_eq!(left, vec![800, 0]);
+    }
+
+    #[test]
+    fn test_lots() {
+        let mut inventory: Inventory = toml::from_str(
+            "[[item]]\nname = \"quark\"\ng = 500\n\
+             [[item]]\nname = \"quark\"\ng = 250\nexpires = \"2024-03-05\"\n\
+             [[item]]\nname = \"quark\"\ng = 250\nexpires = \"2024-03-01\"",
+        )
+        .unwrap();
+        let today = Date::try_from("2024-02-28".to_string()).unwrap();
+        assert_eq!(inventory.urgency(today)["quark"], 1.0 / 3.0);
+        let mut plan = Proposal(BTreeMap::new());
+        plan.0.insert("quark".to_string(), 200);
+        assert!(consume(&mut inventory, &plan, 2).is_empty());
+        let left: Vec<u64> = inventory.item.iter().map(|item| item.g).collect();
+        assert_eq!(left, vec![500, 100, 0]);
+        assert_eq!(inventory.urgency(today)["quark"], 1.0 / 7.0);
+        assert_eq!(
+            consume(&mut inventory, &plan, 4),
+            vec!["the plan uses 800g of quark but only 600g are left"]
+        );
+        assert!(inventory.item.iter().all(|item| item.g == 0));
+    }
+
+    #[test]
+    fn test_urgency() {
+        for text in ["1970-01-01", "2024-02-29", "2026-12-31"] {
+            assert_eq!(
+                String::from(Date::try_from(text.to_string()).unwrap()),
+                text
+            );
+        }
+        for text in ["2023-02-29", "2024-13-01", "2024-05", "tomorrow"] {
+            assert!(Date::try_from(text.to_string()).is_err(), "{}", text);
+        }
+        let inventory: Inventory = toml::from_str(
+            "[[item]]\nname = \"quark\"\ng = 500\nexpires = \"2024-02-29\"\n\
+             [[item]]\nname = \"milk\"\ng = 1000\nexpires = \"2024-02-27\"\n\
+             [[item]]\nname = \"oats\"\ng = 1000",
+        )
+        .unwrap();
+        let today = Date::try_from("2024-02-28".to_string()).unwrap();
+        let urgency: Vec<(String, f64)> = inventory.urgency(today).into_iter().collect();
+        assert_eq!(
+            urgency,
+            vec![("milk".to_string(), 1.0), ("quark".to_string(), 0.5)]
+        );
+    }
+
+    #[test]
+    fn test_limit() {
+        let inventory: Inventory = toml::from_str(
+            "[[item]]\nname = \"oats\"\ng = 1000\n[[item]]\nname = \"quark\"\ng = 300\n\
+             [[item]]\nname = \"ham\"\ng = 200\n\
+             [[item]]\nname = \"quark\"\ng = 400\nexpires = \"2024-03-01\"",
+        )
+        .unwrap();
+        let mut target: Target = toml::from_str(
+            "kcal = 2000\ncarb = 40\nfat = 25\nprotein = 35\n\
+             [[constraint_at_most]]\nname = \"oats\"\ng = 80",
+        )
+        .unwrap();
+        let mut raw_ingredients = RawIngredients(BTreeMap::new());
+        for name in ["oats", "quark"] {
+            raw_ingredients
+                .0
+                .insert(name.to_string(), Default::default());
+        }
+        inventory.limit(&mut target, &raw_ingredients, 7);
+        let at_most: Vec<(&str, u64)> = target
+            .constraint_at_most
+            .iter()
+            .flatten()
+            .map(|constraint| (constraint.name.as_str(), constraint.g))
+            .collect();
+        // ham isn't loaded, the quark of both items is 700g
+        assert_eq!(at_most, vec![("oats", 80), ("quark", 100)]);
+    }
+
+    #[test]
+    fn test_recommend() {
+        let mut total = Proposal(BTreeMap::new());
+        total.0.insert("oats".to_string(), 2000);
+        total.0.insert("quark".to_string(), 3001);
+        total.0.insert("banana".to_string(), 0);
+        let inventory = recommend(&total, 4, 20.0);
+        assert_eq!(
+            inventory.item,
+            vec![
+                StockLevel {
+                    name: "oats".to_string(),
+                    g: 600,
+                    expires: None,
+                },
+                StockLevel {
+                    name: "quark".to_string(),
+                    g: 901,
+                    expires: None,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,69 @@
+//! Built-in targets for common diets, so they don't need a target file.
+use crate::calculator::DailyMacros;
+use crate::{Target, KCAL_PER_G_CARB, KCAL_PER_G_FAT, KCAL_PER_G_PROTEIN};
+use clap::ValueEnum;
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Preset {
+    /// 2000 kcal with plenty of protein to keep muscle in a deficit (35:25:40 of the kcal)
+    #[value(name = "cutting-2000")]
+    Cutting2000,
+    /// Ketogenic, 2000 kcal with very few carbs (5:75:20 of the kcal)
+    Keto,
+    /// The Zone diet's 40:30:30 of the kcal at 2000 kcal
+    Zone,
+    /// 3000 kcal surplus for gaining muscle (45:20:35 of the kcal)
+    HighProteinBulk,
+}
+
+impl Preset {
+    /// kcal and the carb:fat:protein split of the kcal in percent
+    fn kcal_split(self) -> (u64, f64, f64, f64) {
+        match self {
+            Preset::Cutting2000 => (2000, 35.0, 25.0, 40.0),
+            Preset::Keto => (2000, 5.0, 75.0, 20.0),
+            Preset::Zone => (2000, 40.0, 30.0, 30.0),
+            Preset::HighProteinBulk => (3000, 45.0, 20.0, 35.0),
+        }
+    }
+
+    pub(crate) fn target(self) -> Target {
+        let (kcal, carb, fat, protein) = self.kcal_split();
+        // the target's ratio is of the macros' grams
+        let g = |pct: f64, kcal_per_g: f64| kcal as f64 * pct / 100.0 / kcal_per_g;
+        DailyMacros {
+            kcal,
+            carb: g(carb, KCAL_PER_G_CARB),
+            fat: g(fat, KCAL_PER_G_FAT),
+            protein: g(protein, KCAL_PER_G_PROTEIN),
+        }
+        .target()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets() {
+        for preset in Preset::value_variants() {
+            let target = preset.target();
+            assert_eq!(target.carb + target.fat + target.protein, 100);
+            // the ratio of grams is the preset's split of the kcal, up to rounding
+            let carb = target.carb as f64 * KCAL_PER_G_CARB;
+            let fat = target.fat as f64 * KCAL_PER_G_FAT;
+            let protein = target.protein as f64 * KCAL_PER_G_PROTEIN;
+            let sum = carb + fat + protein;
+            let (kcal, carb_pct, fat_pct, protein_pct) = preset.kcal_split();
+            assert_eq!(target.kcal, kcal);
+            assert!((100.0 * carb / sum - carb_pct).abs() < 1.0);
+            assert!((100.0 * fat / sum - fat_pct).abs() < 1.0);
+            assert!((100.0 * protein / sum - protein_pct).abs() < 1.0);
+        }
+        let zone = Preset::from_str("zone", false).unwrap().target();
+        assert_eq!((zone.carb, zone.fat, zone.protein), (48, 16, 36));
+        let cutting = Preset::from_str("cutting-2000", false).unwrap().target();
+        assert_eq!(cutting.kcal, 2000);
+    }
+}
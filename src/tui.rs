@@ -0,0 +1,144 @@
+//! Interactive mode in which ingredients are toggled and the kcal changed while the mix is
+//! optimized again after every change.
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::Frame;
+
+// change of the kcal per key press
+const KCAL_STEP: u64 = 50;
+
+pub struct App {
+    names: Vec<String>,
+    enabled: Vec<bool>,
+    selected: usize,
+    pub kcal: u64,
+    quit: bool,
+}
+
+impl App {
+    pub fn new(names: Vec<String>, kcal: u64) -> App {
+        App {
+            enabled: vec![true; names.len()],
+            names,
+            selected: 0,
+            kcal,
+            quit: false,
+        }
+    }
+
+    pub fn enabled_names(&self) -> Vec<&str> {
+        self.names
+            .iter()
+            .zip(&self.enabled)
+            .filter(|(_, enabled)| **enabled)
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    /// Returns whether the mix needs to be optimized again
+    fn handle_key(&mut self, key: KeyCode) -> bool {
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.selected = self.selected.saturating_sub(1);
+                false
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(self.names.len().saturating_sub(1));
+                false
+            }
+            KeyCode::Char(' ') if !self.names.is_empty() => {
+                self.enabled[self.selected] = !self.enabled[self.selected];
+                true
+            }
+            KeyCode::Left => {
+                self.kcal = self.kcal.saturating_sub(KCAL_STEP).max(KCAL_STEP);
+                true
+            }
+            KeyCode::Right => {
+                self.kcal += KCAL_STEP;
+                true
+            }
+            KeyCode::Char('q') | KeyCode::Esc => {
+                self.quit = true;
+                false
+            }
+            _ => false,
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame, result: &str) {
+        let [ingredients_area, result_area] =
+            Layout::horizontal([Constraint::Length(32), Constraint::Min(0)]).areas(frame.area());
+        let items: Vec<ListItem> = self
+            .names
+            .iter()
+            .zip(&self.enabled)
+            .map(|(name, enabled)| {
+                let check = if *enabled { "x" } else { " " };
+                ListItem::new(format!("[{}] {}", check, name))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("ingredients (space)"),
+            )
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        let mut state = ListState::default().with_selected(Some(self.selected));
+        frame.render_stateful_widget(list, ingredients_area, &mut state);
+        let title = format!("{} kcal (left/right), q to quit", self.kcal);
+        let result =
+            Paragraph::new(result).block(Block::default().borders(Borders::ALL).title(title));
+        frame.render_widget(result, result_area);
+    }
+}
+
+/// Runs the interactive mode until it's quit; `optimize` returns the result to show for the
+/// app's kcal and enabled ingredients
+pub fn run<F: FnMut(&App) -> String>(mut app: App, mut optimize: F) -> std::io::Result<()> {
+    let mut terminal = ratatui::init();
+    let mut result = optimize(&app);
+    let outcome = loop {
+        if let Err(e) = terminal.draw(|frame| app.draw(frame, &result)) {
+            break Err(e);
+        }
+        let key = match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => key.code,
+            Ok(_) => continue,
+            Err(e) => break Err(e),
+        };
+        if app.handle_key(key) {
+            result = optimize(&app);
+        }
+        if app.quit {
+            break Ok(());
+        }
+    };
+    ratatui::restore();
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handle_key() {
+        let mut app = App::new(vec!["banana".to_string(), "oats".to_string()], 100);
+        assert!(!app.handle_key(KeyCode::Down));
+        assert!(!app.handle_key(KeyCode::Down));
+        assert!(app.handle_key(KeyCode::Char(' ')));
+        assert_eq!(app.enabled_names(), vec!["banana"]);
+        assert!(app.handle_key(KeyCode::Left));
+        assert!(app.handle_key(KeyCode::Left));
+        // the kcal don't drop to zero
+        assert_eq!(app.kcal, KCAL_STEP);
+        assert!(app.handle_key(KeyCode::Right));
+        assert_eq!(app.kcal, 2 * KCAL_STEP);
+        assert!(!app.handle_key(KeyCode::Char('q')));
+        assert!(app.quit);
+    }
+}
@@ -0,0 +1,128 @@
+//! Propagates the tolerance of nutrition labels into an interval on the result's macros.
+//!
+//! The true value of a label entry is assumed to be uniformly distributed within the
+//! ingredient's relative tolerance, independently for every ingredient.
+use crate::{Macros, Proposal, RawIngredients};
+use serde::Serialize;
+
+/// Two-sided 95% quantile of the normal distribution
+const Z_95: f64 = 1.96;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+pub struct Interval {
+    pub low: f64,
+    pub high: f64,
+}
+
+impl Interval {
+    fn around(value: f64, variance: f64) -> Interval {
+        let half_width = Z_95 * variance.sqrt();
+        Interval {
+            low: (value - half_width).max(0.0),
+            high: value + half_width,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct MacroIntervals {
+    pub kcal: Interval,
+    pub carb: Interval,
+    pub fat: Interval,
+    pub protein: Interval,
+}
+
+/// Computes 95% intervals of the result's macros, or `None` if neither the ingredients nor the
+/// command line declare a tolerance.
+///
+/// `default_tolerance` (in percent) is used for ingredients that don't declare their own.
+pub fn intervals(
+    gram_proposal: &Proposal,
+    raw_ingredients: &RawIngredients,
+    default_tolerance: Option<f64>,
+) -> Option<MacroIntervals> {
+    let mut variance = Macros::default();
+    let mut any_tolerance = false;
+    for (name, g) in &gram_proposal.0 {
+        let ingredient = &raw_ingredients.0[name];
+        let tolerance = match ingredient.tolerance.or(default_tolerance) {
+            Some(tolerance) => tolerance,
+            None => continue,
+        };
+        any_tolerance = true;
+        let mut single = Proposal(Default::default());
+        single.0.insert(name.to_string(), *g);
+        let amounts = Macros::from_grams(&single, raw_ingredients);
+        // variance of a uniform distribution on [-t, t] is t^2 / 3
+        let relative_variance = (tolerance / 100.0) * (tolerance / 100.0) / 3.0;
+        variance.kcal += relative_variance * amounts.kcal * amounts.kcal;
+        variance.carb += relative_variance * amounts.carb * amounts.carb;
+        variance.fat += relative_variance * amounts.fat * amounts.fat;
+        variance.protein += relative_variance * amounts.protein * amounts.protein;
+    }
+    if !any_tolerance {
+        return None;
+    }
+    let macros = Macros::from_grams(gram_proposal, raw_ingredients);
+    Some(MacroIntervals {
+        kcal: Interval::around(macros.kcal, variance.kcal),
+        carb: Interval::around(macros.carb, variance.carb),
+        fat: Interval::around(macros.fat, variance.fat),
+        protein: Interval::around(macros.protein, variance.protein),
+    })
+}
+
+pub fn print_intervals(intervals: &MacroIntervals) {
+    println!(
+        "Given label tolerances the mix has (95% interval) {}-{}g carb, {}-{}g fat, {}-{}g protein in {}-{} kcal.",
+        intervals.carb.low.round(),
+        intervals.carb.high.round(),
+        intervals.fat.low.round(),
+        intervals.fat.high.round(),
+        intervals.protein.low.round(),
+        intervals.protein.high.round(),
+        intervals.kcal.low.round(),
+        intervals.kcal.high.round()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Ingredient;
+    use std::collections::BTreeMap;
+
+    fn raw_ingredients(tolerance: Option<f64>) -> RawIngredients {
+        let mut raw_ingredients = RawIngredients(BTreeMap::new());
+        raw_ingredients.0.insert(
+            "oats".to_string(),
+            Ingredient {
+                name: "oats".to_string(),
+                g: 100,
+                kcal: 400.0,
+                carb: 60.0,
+                fat: 10.0,
+                protein: 20.0,
+                tolerance,
+                ..Default::default()
+            },
+        );
+        raw_ingredients
+    }
+
+    #[test]
+    fn test_intervals() {
+        let mut proposal = Proposal(BTreeMap::new());
+        proposal.0.insert("oats".to_string(), 100);
+
+        assert_eq!(intervals(&proposal, &raw_ingredients(None), None), None);
+
+        let with_own = intervals(&proposal, &raw_ingredients(Some(20.0)), None).unwrap();
+        let with_default = intervals(&proposal, &raw_ingredients(None), Some(20.0)).unwrap();
+        assert_eq!(with_own, with_default);
+        // 60g carb +- 1.96 * 0.2 / sqrt(3) * 60g
+        assert!((with_own.carb.low - 46.42).abs() < 0.01);
+        assert!((with_own.carb.high - 73.58).abs() < 0.01);
+        assert!((with_own.kcal.high - 490.53).abs() < 0.01);
+    }
+}
@@ -0,0 +1,104 @@
+//! Shows the amounts of the result in kitchen units such as ml, pieces or ounces.
+use crate::cli::Units;
+use crate::{Ingredient, Proposal, RawIngredients};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+const G_PER_OZ: f64 = 28.3495;
+const ML_PER_FL_OZ: f64 = 29.5735;
+
+/// Unit an ingredient is measured in, besides grams
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Unit {
+    // by volume, with the ingredient's density
+    Ml,
+    // by count, with the ingredient's piece weight
+    Piece,
+}
+
+/// e.g. "250 ml", "3 pieces" or "2.8 oz" for `g` grams of the ingredient
+pub fn measure(ingredient: &Ingredient, g: u64, units: Units) -> String {
+    let g = g as f64;
+    let piece_g = ingredient
+        .piece_weight
+        .or(ingredient.portion_g.map(|g| g as f64));
+    let unit = match (units, ingredient.unit) {
+        (Units::Metric, _) => None,
+        // pieces of unknown weight can't be counted
+        (_, Some(Unit::Piece)) if piece_g.is_none() => None,
+        (_, unit) => unit,
+    };
+    match (unit, units) {
+        (Some(Unit::Piece), _) => {
+            let piece_g = piece_g.unwrap_or(g);
+            let pieces = (10.0 * g / piece_g).round() / 10.0;
+            let noun = if pieces == 1.0 { "piece" } else { "pieces" };
+            format!("{} {}", pieces, noun)
+        }
+        (Some(Unit::Ml), _) => {
+            let ml = g / ingredient.density.unwrap_or(1.0);
+            if units == Units::Imperial {
+                format!("{:.1} fl oz", ml / ML_PER_FL_OZ)
+            } else {
+                format!("{} ml", ml.round())
+            }
+        }
+        (None, Units::Imperial) => format!("{:.1} oz", g / G_PER_OZ),
+        (None, _) => format!("{}g", g),
+    }
+}
+
+/// Prints the amounts of the used ingredients unless they are shown in grams anyway
+pub fn print_measures(gram_proposal: &Proposal, raw_ingredients: &RawIngredients, units: Units) {
+    if units == Units::Metric {
+        return;
+    }
+    if let Some(value) = units.to_possible_value() {
+        println!("Measured in {} units", value.get_name());
+    }
+    for (name, g) in gram_proposal.0.iter().filter(|(_, g)| **g > 0) {
+        println!(
+            "\t{}: {}",
+            name,
+            measure(&raw_ingredients.0[name], *g, units)
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_measure() {
+        let eggs = Ingredient {
+            name: "eggs".to_string(),
+            unit: Some(Unit::Piece),
+            piece_weight: Some(60.0),
+            ..Default::default()
+        };
+        let milk = Ingredient {
+            name: "milk".to_string(),
+            unit: Some(Unit::Ml),
+            density: Some(1.03),
+            ..Default::default()
+        };
+        let oats = Ingredient {
+            name: "oats".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(measure(&eggs, 180, Units::Natural), "3 pieces");
+        assert_eq!(measure(&eggs, 180, Units::Imperial), "3 pieces");
+        assert_eq!(measure(&eggs, 180, Units::Metric), "180g");
+        assert_eq!(measure(&milk, 258, Units::Natural), "250 ml");
+        assert_eq!(measure(&milk, 258, Units::Imperial), "8.5 fl oz");
+        assert_eq!(measure(&oats, 80, Units::Natural), "80g");
+        let eggs = Ingredient {
+            piece_weight: None,
+            ..eggs
+        };
+        assert_eq!(measure(&eggs, 180, Units::Natural), "180g");
+        assert_eq!(measure(&oats, 80, Units::Imperial), "2.8 oz");
+    }
+}
@@ -0,0 +1,286 @@
+//! Checks a plan against the constraints and limits of a target, e.g. a plan made by hand.
+use crate::{
+    prep_minutes, Macros, Proposal, RawIngredients, Target, KCAL_PER_G_CARB, KCAL_PER_G_FAT,
+};
+
+/// To one decimal, e.g. 2.4 or 250
+fn round(amount: f64) -> f64 {
+    (amount * 10.0).round() / 10.0
+}
+
+/// e.g. "oats 120g, more than the at most 80g"
+fn exceeds(name: &str, amount: f64, limit: f64, unit: &str, kind: &str) -> Option<String> {
+    Some(format!(
+        "{} {}{}, more than the {} {}{}",
+        name,
+        round(amount),
+        unit,
+        kind,
+        limit,
+        unit
+    ))
+    .filter(|_| amount > limit)
+}
+
+/// e.g. "water 800 ml, less than the minimum of 1000 ml"
+fn falls_short(name: &str, amount: f64, min: f64, unit: &str, kind: &str) -> Option<String> {
+    Some(format!(
+        "{} {}{}, less than the {} {}{}",
+        name,
+        round(amount),
+        unit,
+        kind,
+        min,
+        unit
+    ))
+    .filter(|_| amount < min)
+}
+
+/// Each constraint or limit of the target that the plan in grams doesn't meet, and each
+/// ingredient that the target's diet, excluded tags or meal rule out
+pub fn violations(
+    gram_proposal: &Proposal,
+    target: &Target,
+    raw_ingredients: &RawIngredients,
+) -> Vec<String> {
+    let mut violations = Vec::new();
+    let grams = |name: &str| gram_proposal.0.get(name).copied().unwrap_or(0) as f64;
+    for constraint in target.constraint_exact.iter().flatten() {
+        let g = grams(&constraint.name);
+        if g != constraint.g as f64 {
+            violations.push(format!(
+                "{} {}g instead of exactly {}g",
+                constraint.name, g, constraint.g
+            ));
+        }
+    }
+    for constraint in target.constraint_at_least.iter().flatten() {
+        let g = grams(&constraint.name);
+        violations.extend(falls_short(
+            &constraint.name,
+            g,
+            constraint.g as f64,
+            "g",
+            "at least",
+        ));
+    }
+    for constraint in target.constraint_at_most.iter().flatten() {
+        let g = grams(&constraint.name);
+        violations.extend(exceeds(
+            &constraint.name,
+            g,
+            constraint.g as f64,
+            "g",
+            "at most",
+        ));
+    }
+
+    let total_g: u64 = gram_proposal.0.values().sum();
+    for constraint in target.constraint_max_weight_pct.iter().flatten() {
+        if total_g > 0 {
+            let pct = 100.0 * grams(&constraint.name) / total_g as f64;
+            violations.extend(exceeds(
+                &constraint.name,
+                pct,
+                constraint.pct,
+                "%",
+                "weight share of at most",
+            ));
+        }
+    }
+    let macros = Macros::from_grams(gram_proposal, raw_ingredients);
+    let kcal = |name: &str| {
+        let mut single = Proposal(Default::default());
+        single.0.insert(name.to_string(), grams(name) as u64);
+        Macros::from_grams(&single, raw_ingredients).kcal
+    };
+    for constraint in target.constraint_max_kcal_pct.iter().flatten() {
+        if raw_ingredients.0.contains_key(&constraint.name) && macros.kcal > 0.0 {
+            violations.extend(exceeds(
+                &constraint.name,
+                100.0 * kcal(&constraint.name) / macros.kcal,
+                constraint.pct,
+                "%",
+                "kcal share of at most",
+            ));
+        }
+    }
+    for (name, g) in &gram_proposal.0 {
+        if *g > 0 && target.excludes(&raw_ingredients.0[name]) {
+            violations.push(format!(
+                "{} is ruled out by the target's diet, excluded tags or meal",
+                name
+            ));
+        }
+        if let Some(share) = target.max_single_ingredient_kcal_share {
+            if macros.kcal > 0.0 {
+                violations.extend(exceeds(
+                    name,
+                    100.0 * kcal(name) / macros.kcal,
+                    100.0 * share,
+                    "%",
+                    "kcal share of at most",
+                ));
+            }
+        }
+    }
+
+    let sub_macros = [
+        (
+            "sat fat",
+            &target.sat_fat_at_most,
+            macros.sat_fat,
+            KCAL_PER_G_FAT,
+        ),
+        (
+            "sugar",
+            &target.sugar_at_most,
+            macros.sugar,
+            KCAL_PER_G_CARB,
+        ),
+    ];
+    for (name, limit, g, kcal_per_g) in sub_macros.iter() {
+        if let (Some(limit), true) = (limit, macros.kcal > 0.0) {
+            let pct = 100.0 * limit.kcal_share(*kcal_per_g, target.kcal);
+            violations.extend(exceeds(
+                name,
+                100.0 * g * kcal_per_g / macros.kcal,
+                pct,
+                "% of the kcal",
+                "limit of",
+            ));
+        }
+    }
+    let maximums = [
+        ("sodium", target.max_sodium_mg, macros.sodium_mg, " mg"),
+        (
+            "glycemic load",
+            target.max_glycemic_load,
+            macros.glycemic_load,
+            "",
+        ),
+        ("water", target.max_water_ml, macros.water_ml, " ml"),
+        (
+            "caffeine",
+            target.max_caffeine_mg,
+            macros.caffeine_mg,
+            " mg",
+        ),
+        (
+            "prep time",
+            target.max_prep_minutes,
+            prep_minutes(gram_proposal, raw_ingredients),
+            " min",
+        ),
+    ];
+    for (name, max, amount, unit) in maximums.iter() {
+        if let Some(max) = max {
+            violations.extend(exceeds(name, *amount, *max, unit, "maximum of"));
+        }
+    }
+    let minimums = [
+        ("water", target.min_water_ml, macros.water_ml, " ml"),
+        (
+            "caffeine",
+            target.min_caffeine_mg,
+            macros.caffeine_mg,
+            " mg",
+        ),
+        ("leucine", target.min_leucine_g, macros.leucine_g, "g"),
+        ("essential amino acids", target.min_eaa_g, macros.eaa_g, "g"),
+    ];
+    for (name, min, amount, unit) in minimums.iter() {
+        if let Some(min) = min {
+            violations.extend(falls_short(name, *amount, *min, unit, "minimum of"));
+        }
+    }
+    if let (Some(min_pct), true) = (target.min_quality_protein_pct, macros.protein > 0.0) {
+        violations.extend(falls_short(
+            "high-quality protein",
+            100.0 * macros.quality_protein / macros.protein,
+            min_pct,
+            "% of the protein",
+            "minimum of",
+        ));
+    }
+    if let (Some(max), true) = (target.max_omega6_to_omega3, macros.omega6_g > 0.0) {
+        if macros.omega6_g > max * macros.omega3_g {
+            violations.push(format!(
+                "omega-6 {}g per g of omega-3, more than the maximum of {}",
+                round(macros.omega6_g / macros.omega3_g),
+                max
+            ));
+        }
+    }
+    let (kcal_min, kcal_max) = target.kcal_band();
+    if kcal_min < kcal_max && (macros.kcal < kcal_min || macros.kcal > kcal_max) {
+        violations.push(format!(
+            "{:.0} kcal, outside of the {}-{} kcal band",
+            macros.kcal, kcal_min, kcal_max
+        ));
+    }
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Ingredient;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_violations() {
+        let target: Target = toml::from_str(
+            "kcal = 1000\ncarb = 20\nfat = 30\nprotein = 50\nmax_sodium_mg = 500\n\
+             exclude_tags = [\"nuts\"]\n\
+             [[constraint_at_most]]\nname = \"ham\"\ng = 200\n\
+             [[constraint_at_least]]\nname = \"oats\"\ng = 50",
+        )
+        .unwrap();
+        let ingredient = |name: &str, sodium_mg, tags: &[&str]| Ingredient {
+            name: name.to_string(),
+            g: 100,
+            kcal: 200.0,
+            sodium_mg: Some(sodium_mg),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            ..Default::default()
+        };
+        let mut raw_ingredients = RawIngredients(BTreeMap::new());
+        for ingredient in [
+            ingredient("ham", 1000.0, &[]),
+            ingredient("oats", 0.0, &[]),
+            ingredient("peanuts", 0.0, &["nuts"]),
+        ] {
+            raw_ingredients
+                .0
+                .insert(ingredient.name.clone(), ingredient);
+        }
+        let proposal = |pieces: &[(&str, u64)]| {
+            Proposal(
+                pieces
+                    .iter()
+                    .map(|(name, g)| (name.to_string(), *g))
+                    .collect(),
+            )
+        };
+        assert_eq!(
+            violations(
+                &proposal(&[("ham", 250), ("peanuts", 20)]),
+                &target,
+                &raw_ingredients
+            ),
+            vec![
+                "oats 0g, less than the at least 50g",
+                "ham 250g, more than the at most 200g",
+                "peanuts is ruled out by the target's diet, excluded tags or meal",
+                "sodium 2500 mg, more than the maximum of 500 mg",
+            ]
+        );
+        assert!(violations(
+            &proposal(&[("ham", 40), ("oats", 60)]),
+            &target,
+            &raw_ingredients
+        )
+        .is_empty());
+    }
+}
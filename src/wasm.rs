@@ -0,0 +1,10 @@
+//! `wasm-bindgen` interface to the optimizer for a web page without a backend.
+use crate::api::optimize_json;
+use wasm_bindgen::prelude::*;
+
+/// JSON report of the plan for a target and a list of ingredients, like `POST /optimize` of
+/// the server; errors are JSON with a `code` and `message`
+#[wasm_bindgen]
+pub fn optimize(target_json: &str, ingredients_json: &str) -> Result<String, JsError> {
+    optimize_json(target_json, ingredients_json).map_err(|error| JsError::new(&error))
+}
@@ -0,0 +1,97 @@
+//! Asks for the values on a nutrition label to add an ingredient to the pantry.
+use crate::{round_decigram, Ingredient, KCAL_MISMATCH_PCT};
+use std::io::{self, BufRead, Write};
+
+struct Prompt<R, W> {
+    input: R,
+    output: W,
+}
+
+impl<R: BufRead, W: Write> Prompt<R, W> {
+    /// The trimmed answer to `question`
+    fn line(&mut self, question: &str) -> io::Result<String> {
+        write!(self.output, "{}: ", question)?;
+        self.output.flush()?;
+        let mut line = String::new();
+        if self.input.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "input ended before the ingredient was complete",
+            ));
+        }
+        Ok(line.trim().to_string())
+    }
+
+    /// A number that isn't negative, with a decimal point or comma, or nothing if `optional`
+    fn number(&mut self, question: &str, optional: bool) -> io::Result<Option<f64>> {
+        loop {
+            let answer = self.line(question)?;
+            if answer.is_empty() && optional {
+                return Ok(None);
+            }
+            match answer.replace(',', ".").parse::<f64>() {
+                Ok(value) if value >= 0.0 => return Ok(Some(value)),
+                _ => writeln!(self.output, "{} isn't a valid amount", answer)?,
+            }
+        }
+    }
+}
+
+/// Asks for the ingredient's values until they are valid
+pub fn ask<R: BufRead, W: Write>(input: R, output: W) -> io::Result<Ingredient> {
+    let mut prompt = Prompt { input, output };
+    let mut name = String::new();
+    while name.is_empty() {
+        name = prompt.line("Name")?;
+    }
+    loop {
+        let g = loop {
+            let serving = prompt.number("Serving size in g (100 for values per 100g)", false)?;
+            match serving {
+                Some(g) if g >= 1.0 => break g.round() as u64,
+                _ => continue,
+            }
+        };
+        let kcal = prompt.number("kcal (empty to derive them from the macros)", true)?;
+        let mut ingredient = Ingredient {
+            name: name.clone(),
+            g,
+            carb: prompt.number("Carbs in g", false)?.unwrap_or(0.0),
+            fat: prompt.number("Fat in g", false)?.unwrap_or(0.0),
+            protein: prompt.number("Protein in g", false)?.unwrap_or(0.0),
+            ..Default::default()
+        };
+        ingredient.kcal = kcal.unwrap_or_else(|| round_decigram(ingredient.macro_kcal()));
+        let mismatch = match ingredient.kcal_mismatch(KCAL_MISMATCH_PCT) {
+            Some(mismatch) => mismatch,
+            None => return Ok(ingredient),
+        };
+        writeln!(prompt.output, "{}", mismatch)?;
+        if prompt
+            .line("Keep these values anyway? [y/N]")?
+            .eq_ignore_ascii_case("y")
+        {
+            return Ok(ingredient);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ask() {
+        let answer = |input: &str| ask(input.as_bytes(), Vec::new());
+        let oats = answer("oats\n100\n\n60\n7\n13\n").unwrap();
+        assert_eq!((oats.g, oats.kcal, oats.protein), (100, 355.0, 13.0));
+
+        // invalid numbers are asked again, and inconsistent kcal are only kept if confirmed
+        let bar = answer("bar\n-5\n60\n500\n20,5\n9\n20\n\n60\n250\n20\n9\n20\n").unwrap();
+        assert_eq!((bar.kcal, bar.carb), (250.0, 20.0));
+        let bar = answer("bar\n60\n500\n20\n9\n20\ny\n").unwrap();
+        assert_eq!(bar.kcal, 500.0);
+
+        assert!(answer("bar\n60\n").is_err());
+    }
+}